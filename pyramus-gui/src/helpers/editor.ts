@@ -2,11 +2,59 @@ import {
   imageBufferPointer,
   testRenderStringResvg,
   getStage,
+  getItem,
   removeObject,
+  extractProp,
   editTransform,
   renameObject,
+  setOpacity,
+  setHidden,
+  setBlendMode,
+  addEffect,
+  editEffect,
+  removeEffect,
+  setHitTest,
+  setMask,
+  removeMask,
+  setCrop,
+  removeCrop,
+  setImageAdjustments,
+  setPropOutline,
+  removePropOutline,
+  editShape,
+  editFill,
+  removeFill,
+  setFillPattern,
+  editStroke,
+  removeStroke,
+  editPathNode,
+  insertPathNode,
+  removePathNode,
   selectObjects,
+  groupObjects,
+  ungroupObjects,
+  enterGroup,
+  exitGroup,
+  enterProp,
+  exitProp,
+  alignObjects,
+  distributeObjects,
   type FrontendStage,
+  type FrontendItem,
+  type FrontendAlignment,
+  type FrontendAlignTo,
+  type FrontendAxis,
+  type FrontendBlendMode,
+  type FrontendEffect,
+  type FrontendHitTest,
+  type FrontendMaskMode,
+  type FrontendImageCrop,
+  type FrontendImageAdjustments,
+  type FrontendPropOutline,
+  type FrontendShapeKind,
+  type FrontendPaint,
+  type FrontendStroke,
+  type FrontendPathNode,
   getStageJson,
 } from '@/../wasm/pkg/pyramus_wasm.js'
 
@@ -22,6 +70,10 @@ export function getStageObject(): FrontendStage {
   return getStage()
 }
 
+export function getItemObject(item: number): FrontendItem | undefined {
+  return getItem(item)
+}
+
 // TODO: Can we attach this to the stage object?
 export function deleteItem(item: number) {
   removeObject(item)
@@ -33,6 +85,56 @@ export function selectItems(items: number[]) {
   selectObjects(itemsUint32)
 }
 
+// TODO: Can we attach this to the stage object?
+export function groupItems(items: number[]) {
+  groupObjects(new Uint32Array(items))
+}
+
+// TODO: Can we attach this to the stage object?
+export function ungroupItems(items: number[]) {
+  ungroupObjects(new Uint32Array(items))
+}
+
+// TODO: Can we attach this to the stage object?
+// Returns the ID of the new prop, which can be opened with switchEditor
+export function extractItemsToProp(items: number[], name: string): string | undefined {
+  return extractProp(new Uint32Array(items), name)
+}
+
+// TODO: Can we attach this to the stage object?
+export function enterItemGroup(item: number) {
+  enterGroup(item)
+}
+
+// TODO: Can we attach this to the stage object?
+export function exitItemGroup() {
+  exitGroup()
+}
+
+// TODO: Can we attach this to the stage object?
+export function enterItemProp(item: number) {
+  enterProp(item)
+}
+
+// TODO: Can we attach this to the stage object?
+export function exitItemProp() {
+  exitProp()
+}
+
+// TODO: Can we attach this to the stage object?
+export function alignItems(
+  items: number[],
+  alignment: FrontendAlignment,
+  alignTo: FrontendAlignTo,
+) {
+  alignObjects(new Uint32Array(items), alignment, alignTo)
+}
+
+// TODO: Can we attach this to the stage object?
+export function distributeItems(items: number[], axis: FrontendAxis) {
+  distributeObjects(new Uint32Array(items), axis)
+}
+
 // TODO: Can we attach this to the stage object?
 export function editItemTransform(
   item: number,
@@ -48,6 +150,122 @@ export function editItemName(item: number, name: string) {
   renameObject(item, name)
 }
 
+// TODO: Can we attach this to the stage object?
+export function editItemOpacity(item: number, opacity: number) {
+  setOpacity(item, opacity)
+}
+
+// TODO: Can we attach this to the stage object?
+export function editItemHidden(item: number, hidden: boolean) {
+  setHidden(item, hidden)
+}
+
+// TODO: Can we attach this to the stage object?
+export function editItemBlendMode(item: number, blendMode: FrontendBlendMode) {
+  setBlendMode(item, blendMode)
+}
+
+// TODO: Can we attach this to the stage object?
+export function addItemEffect(item: number, effect: FrontendEffect) {
+  addEffect(item, effect)
+}
+
+// TODO: Can we attach this to the stage object?
+export function editItemEffect(item: number, index: number, effect: FrontendEffect) {
+  editEffect(item, index, effect)
+}
+
+// TODO: Can we attach this to the stage object?
+export function removeItemEffect(item: number, index: number) {
+  removeEffect(item, index)
+}
+
+// TODO: Can we attach this to the stage object?
+export function editItemHitTest(item: number, hitTest: FrontendHitTest) {
+  setHitTest(item, hitTest)
+}
+
+// TODO: Can we attach this to the stage object?
+export function editItemMask(item: number, mask: FrontendMaskMode | null) {
+  if (mask) {
+    setMask(item, mask)
+  } else {
+    removeMask(item)
+  }
+}
+
+// TODO: Can we attach this to the stage object?
+export function editItemCrop(item: number, crop: FrontendImageCrop | null) {
+  if (crop) {
+    setCrop(item, crop.x, crop.y, crop.width, crop.height)
+  } else {
+    removeCrop(item)
+  }
+}
+
+// TODO: Can we attach this to the stage object?
+export function editImageAdjustments(item: number, adjustments: FrontendImageAdjustments) {
+  setImageAdjustments(item, adjustments)
+}
+
+// TODO: Can we attach this to the stage object?
+export function editPropOutline(item: number, outline: FrontendPropOutline | null) {
+  if (outline) {
+    setPropOutline(item, outline)
+  } else {
+    removePropOutline(item)
+  }
+}
+
+// TODO: Can we attach this to the stage object?
+export function editItemShape(
+  item: number,
+  shape: FrontendShapeKind,
+  size: [number, number]
+) {
+  editShape(item, shape, size[0], size[1])
+}
+
+// TODO: Can we attach this to the stage object?
+export function editItemFill(item: number, fill: FrontendPaint | null) {
+  if (fill) {
+    editFill(item, fill)
+  } else {
+    removeFill(item)
+  }
+}
+
+// TODO: Can we attach this to the stage object?
+export async function editItemFillPattern(item: number, file: File, size: [number, number]) {
+  const data: ArrayBuffer = await file.arrayBuffer()
+  const array = new Uint8Array(data)
+  setFillPattern(item, array, size[0], size[1])
+}
+
+// TODO: Can we attach this to the stage object?
+export function editItemStroke(item: number, stroke: FrontendStroke | null) {
+  if (stroke) {
+    editStroke(item, stroke)
+  } else {
+    removeStroke(item)
+  }
+}
+
+// TODO: Can we attach this to the stage object?
+export function editItemPathNode(item: number, index: number, node: FrontendPathNode) {
+  editPathNode(item, index, node)
+}
+
+// TODO: Can we attach this to the stage object?
+export function insertItemPathNode(item: number, index: number, node: FrontendPathNode) {
+  insertPathNode(item, index, node)
+}
+
+// TODO: Can we attach this to the stage object?
+export function removeItemPathNode(item: number, index: number) {
+  removePathNode(item, index)
+}
+
 export function getImageBufferPointer() {
   return imageBufferPointer()
 }
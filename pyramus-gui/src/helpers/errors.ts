@@ -0,0 +1,95 @@
+// Errors thrown from the WASM side are serialized `PyramusError`s
+// `code` is the name of the error variant, and `params` contains any offending IDs or values
+export type PyramusErrorParams =
+  | null
+  | { id: number }
+  | { id: number; index: number }
+  | { width: number; height: number }
+  | { size: number }
+  | { opacity: number }
+  | { format: string }
+  | { field: string; reason: InvalidReason }
+  | { expected: number; found: number }
+  | { count: number }
+
+// Why a field of a paint, effect, shape, outline or image adjustment was rejected
+export type InvalidReason =
+  | 'NotFinite'
+  | 'Negative'
+  | 'NotPositive'
+  | { OutOfRange: { min: number; max: number } }
+  | { TooFew: { min: number } }
+  | 'SelfIntersecting'
+  | 'Unsupported'
+
+export type PyramusError = {
+  code: string
+  message: string
+  params: PyramusErrorParams
+}
+
+export function isPyramusError(e: unknown): e is PyramusError {
+  return typeof e === 'object' && e !== null && 'code' in e && 'message' in e
+}
+
+// Describe why a field was rejected, for the Invalid* errors
+function invalidField({ field, reason }: { field: string; reason: InvalidReason }): string {
+  const name = field.replace(/_/g, ' ')
+  if (typeof reason === 'object' && 'OutOfRange' in reason) {
+    return `${name} must be between ${reason.OutOfRange.min} and ${reason.OutOfRange.max}`
+  }
+  if (typeof reason === 'object' && 'TooFew' in reason) {
+    return `${name} needs at least ${reason.TooFew.min}`
+  }
+  switch (reason) {
+    case 'NotFinite':
+      return `${name} must be a finite number`
+    case 'Negative':
+      return `${name} must not be negative`
+    case 'NotPositive':
+      return `${name} must be greater than 0`
+    case 'SelfIntersecting':
+      return `${name} must not cross itself`
+    case 'Unsupported':
+      return `${name} cannot be set this way`
+  }
+}
+
+// TODO: Localization- these should be looked up by code from a translation file
+// eslint-disable-next-line @typescript-eslint/no-explicit-any
+const errorMessages: Record<string, (params: any) => string> = {
+  NoRuntimeFound: () => 'The editor has not finished loading.',
+  ItemNotFound: ({ id }) => `Item ${id} could not be found.`,
+  RootImmutable: ({ id }) => `Item ${id} is the root item, and cannot be edited or removed.`,
+  CycleDetected: ({ id }) => `Item ${id} is contained within itself.`,
+  DuplicateChild: ({ id }) => `Item ${id} is listed as a child more than once.`,
+  WrongItemType: ({ id }) => `Item ${id} cannot be edited this way.`,
+  SingularTransform: ({ id }) => `Item ${id} is scaled to nothing, so items cannot be placed in it.`,
+  PathNodeNotFound: ({ id, index }) => `Item ${id} has no path node ${index}.`,
+  NonFiniteTransform: ({ id }) => `The position, scale and rotation of item ${id} must be numbers.`,
+  NonFinitePathNode: ({ id, index }) =>
+    `Path node ${index} of item ${id} must be at a finite position.`,
+  InvalidPaint: (params) => `Invalid fill or stroke: ${invalidField(params)}.`,
+  InvalidEffect: (params) => `Invalid effect: ${invalidField(params)}.`,
+  EffectNotFound: ({ id, index }) => `Item ${id} has no effect ${index}.`,
+  InvalidShape: (params) => `Invalid shape: ${invalidField(params)}.`,
+  InvalidOutline: (params) => `Invalid prop outline: ${invalidField(params)}.`,
+  InvalidAdjustment: (params) => `Invalid image adjustment: ${invalidField(params)}.`,
+  InvalidSize: ({ width, height }) => `${width} x ${height} is not a valid size.`,
+  InvalidFontSize: ({ size }) => `${size} is not a valid font size.`,
+  InvalidOpacity: ({ opacity }) => `${opacity} is not a valid opacity (it must be between 0 and 1).`,
+  UnsupportedImageFormat: ({ format }) => `Images of type '${format}' are not supported.`,
+  VersionMismatch: ({ expected, found }) =>
+    `This document was made with a newer version of Pyramus (version ${found}, expected ${expected} or older).`,
+  UnrepairableDocument: ({ count }) =>
+    `This document has ${count} problems that could not be repaired, so it cannot be opened.`,
+}
+
+// Get a user-facing message for an error thrown from the WASM side
+export function errorMessage(e: unknown): string {
+  if (!isPyramusError(e)) {
+    return String(e)
+  }
+  const message = errorMessages[e.code]
+  return message ? message(e.params) : e.message
+}
@@ -1,4 +1,12 @@
-import { inputMouseMove, inputMouseDown, inputMouseUp } from '@/../wasm/pkg/pyramus_wasm.js'
+import {
+  inputMouseMove,
+  inputMouseDown,
+  inputMouseUp,
+  inputDoubleClick,
+  setToolSelect,
+  setToolPencil,
+  setSnapping,
+} from '@/../wasm/pkg/pyramus_wasm.js'
 
 export function handleMouseDown(x: number, y: number) {
   return inputMouseDown(x, y)
@@ -8,6 +16,23 @@ export function handleMouseUp() {
   return inputMouseUp()
 }
 
-export function handleMouseMove(x: number, y: number) {
-  return inputMouseMove(x, y)
+// Snapping is turned off while `disableSnapping` is set (while a modifier key is held)
+export function handleMouseMove(x: number, y: number, disableSnapping: boolean) {
+  return inputMouseMove(x, y, disableSnapping)
+}
+
+export function handleDoubleClick(x: number, y: number) {
+  return inputDoubleClick(x, y)
+}
+
+export function selectTool() {
+  return setToolSelect()
+}
+
+export function pencilTool(color: [number, number, number], strokeWidth: number) {
+  return setToolPencil(color[0], color[1], color[2], strokeWidth)
+}
+
+export function snapping(enabled: boolean, gridSize?: number) {
+  return setSnapping(enabled, gridSize, true, true)
 }
@@ -9,6 +9,16 @@ export class JsMessage {
 export class Rerender extends JsMessage {}
 export class UpdateStage extends JsMessage {}
 
+// Messages about items carry the IDs of the items they are about
+export class ItemsMessage extends JsMessage {
+  readonly ids!: number[]
+}
+export class ItemsAdded extends ItemsMessage {}
+export class ItemsRemoved extends ItemsMessage {}
+export class ItemsChanged extends ItemsMessage {}
+export class TransformsChanged extends ItemsMessage {}
+export class SelectionChanged extends ItemsMessage {}
+
 // `any` is used since the type of the object should be known from the Rust side
 // eslint-disable-next-line @typescript-eslint/no-explicit-any
 type JSMessageFactory = (data: any) => JsMessage
@@ -17,6 +27,11 @@ type MessageMaker = typeof JsMessage | JSMessageFactory
 export const messageMakers: Record<string, MessageMaker> = {
   Rerender,
   UpdateStage,
+  ItemsAdded,
+  ItemsRemoved,
+  ItemsChanged,
+  TransformsChanged,
+  SelectionChanged,
 } as const
 export type JsMessageType = keyof typeof messageMakers
 
@@ -24,7 +39,7 @@ export function subscribe<T extends JsMessage>(
   messageType: JsMessageType,
   callback: (messageData: T) => void
 ) {
-  const messageMaker: JsMessage = messageMakers[messageType]
+  const messageMaker = messageMakers[messageType]
   if (!messageMaker) {
     // eslint-disable-next-line no-console
     console.error(
@@ -36,9 +51,10 @@ export function subscribe<T extends JsMessage>(
 
   const outerCallback = (messageType: JsMessageType, messageData: Record<string, unknown>) => {
     console.log('Received message', messageType, messageData)
+    // Messages without fields have no data
     const msg: JsMessage = plainToInstance(
-      messageMaker.constructor as ClassConstructor<JsMessage>,
-      {}
+      messageMaker as ClassConstructor<JsMessage>,
+      messageData ?? {}
     )
     callback(msg as T)
   }
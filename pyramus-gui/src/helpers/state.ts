@@ -1,4 +1,4 @@
-import init, { wasmMemory, switchEditorBase } from '@/../wasm/pkg/pyramus_wasm.js'
+import init, { wasmMemory, switchEditorBase, newEditorBase } from '@/../wasm/pkg/pyramus_wasm.js'
 
 export type WasmRawInstance = WebAssembly.Memory
 
@@ -28,4 +28,9 @@ export function getRawBuffer(): ArrayBuffer {
 
 export function switchEditor(id : string) {
   return switchEditorBase(id)
+}
+
+// Generates a new prop from a seed (or a new seed, if none is given) and returns its ID
+export function newEditor(seed?: string): string {
+  return newEditorBase(seed)
 }
\ No newline at end of file
@@ -1,4 +1,11 @@
-import { uploadSvg, uploadImage, uploadText } from '@/../wasm/pkg/pyramus_wasm.js'
+import {
+  uploadSvg,
+  uploadImage,
+  uploadText,
+  uploadShape,
+  setImageImportLimits,
+  type FrontendShape,
+} from '@/../wasm/pkg/pyramus_wasm.js'
 
 export async function createImage(name: string, parent: number, image: File) {
   const data: ArrayBuffer = await image.arrayBuffer()
@@ -6,6 +13,11 @@ export async function createImage(name: string, parent: number, image: File) {
   return uploadImage(name, parent, array)
 }
 
+// Uploaded images larger than these limits are downscaled and recompressed
+export function setImageLimits(maxSize: [number, number], maxBytes: number) {
+  return setImageImportLimits(maxSize[0], maxSize[1], maxBytes)
+}
+
 export function createSvg(name: string, parent: number, svg: string) {
   return uploadSvg(name, parent, svg)
 }
@@ -13,3 +25,7 @@ export function createSvg(name: string, parent: number, svg: string) {
 export function createText(name: string, parent: number, text: string) {
   return uploadText(name, parent, text)
 }
+
+export function createShape(name: string, parent: number, shape: FrontendShape) {
+  return uploadShape(name, parent, shape)
+}
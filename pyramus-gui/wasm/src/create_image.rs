@@ -1,23 +1,49 @@
 use pyramus::{
     command::BackendCommand,
-    models::templates::{builder::ItemBuilder, ids::InternalId},
+    models::templates::{
+        builder::{prop_item_builder::ImageImportLimits, ItemBuilder},
+        ids::InternalId,
+    },
 };
 use wasm_bindgen::prelude::*;
 
-use crate::editor::command;
+use crate::{
+    editor::{self, command},
+    models::FrontendShape,
+};
 
 #[wasm_bindgen(js_name = uploadImage)]
-pub fn upload_image(name: String, parent: u32, data: Vec<u8>) -> Result<(), JsError> {
+pub fn upload_image(name: String, parent: u32, data: Vec<u8>) -> Result<(), JsValue> {
     command(vec![BackendCommand::CreateItem {
-        new_item: ItemBuilder::build_image_from_bytes(data, "png")
+        new_item: ItemBuilder::build_image_from_bytes(data, editor::image_limits()?)
             .name(name)
-            .parent(InternalId(parent)), // TODO: more than just png
+            .parent(InternalId(parent)),
     }])?;
     Ok(())
 }
 
+#[wasm_bindgen(js_name = setImageImportLimits)]
+pub fn set_image_import_limits(
+    max_width: u32,
+    max_height: u32,
+    max_bytes: usize,
+) -> Result<(), JsValue> {
+    editor::RUNTIME.with(|runtime| {
+        let mut runtime = runtime.borrow_mut();
+        let runtime = runtime
+            .as_mut()
+            .ok_or(pyramus::PyramusError::NoRuntimeFound)?;
+        runtime.image_limits = ImageImportLimits {
+            max_width,
+            max_height,
+            max_bytes,
+        };
+        Ok(())
+    })
+}
+
 #[wasm_bindgen(js_name = uploadSvg)]
-pub fn upload_svg(name: String, parent: u32, svg: String) -> Result<(), JsError> {
+pub fn upload_svg(name: String, parent: u32, svg: String) -> Result<(), JsValue> {
     command(vec![BackendCommand::CreateItem {
         new_item: ItemBuilder::build_image_from_svg(svg)
             .name(name)
@@ -27,7 +53,7 @@ pub fn upload_svg(name: String, parent: u32, svg: String) -> Result<(), JsError>
 }
 
 #[wasm_bindgen(js_name = uploadText)]
-pub fn upload_text(name: String, parent: u32, text: String) -> Result<(), JsError> {
+pub fn upload_text(name: String, parent: u32, text: String) -> Result<(), JsValue> {
     command(vec![BackendCommand::CreateItem {
         new_item: ItemBuilder::build_text_basic(text)
             .name(name)
@@ -35,3 +61,13 @@ pub fn upload_text(name: String, parent: u32, text: String) -> Result<(), JsErro
     }])?;
     Ok(())
 }
+
+#[wasm_bindgen(js_name = uploadShape)]
+pub fn upload_shape(name: String, parent: u32, shape: FrontendShape) -> Result<(), JsValue> {
+    command(vec![BackendCommand::CreateItem {
+        new_item: ItemBuilder::build_shape(shape.into_shape()?)
+            .name(name)
+            .parent(InternalId(parent)),
+    }])?;
+    Ok(())
+}
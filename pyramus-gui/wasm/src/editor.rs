@@ -1,8 +1,8 @@
 use pyramus::{
-    cache::Cache, command::{BackendCommand, FrontendCommand}, models::{editor::stage::{example_stage_prop, Stage}, templates::ids::PyramusId}, PyramusError
+    cache::Cache, command::{BackendCommand, FrontendCommand}, models::{editor::{base_item::Base, stage::{example_stage_prop, Stage}, staging::Staging}, templates::{builder::prop_item_builder::ImageImportLimits, ids::{InternalId, PyramusId}, random::new_seed}}, PyramusError
 };
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
-use wasm_bindgen::{JsError, JsValue};
+use wasm_bindgen::JsValue;
 
 type CallbacksMap = HashMap<String, js_sys::Function>;
 
@@ -19,31 +19,39 @@ pub struct Runtime {
 
     // TODO: Should this be hosted on the js side? Or even further in rust?
     pub cache : Cache,
+
+    /// Limits applied to uploaded images
+    pub image_limits: ImageImportLimits,
 }
 
 impl Runtime {
     pub async fn start() -> Runtime {
         let mut cache = Cache::new();
-        cache.fetch().await;
+        if let Err(e) = cache.fetch().await {
+            pyramus::log!("Err: {e}");
+        }
         Runtime {
-            stage: example_stage_prop()
+            stage: example_stage_prop(new_seed())
                 .inspect_err(|e| pyramus::log!("Err: {e}"))
                 .unwrap(),
-            cache
+            cache,
+            image_limits: ImageImportLimits::default(),
         }
     }
 
-    pub fn new_base_prop(&mut self) -> Result<(), PyramusError> {
+    /// Create a new randomly generated base prop, from a seed if given (otherwise a new seed is generated)
+    /// Returns the ID the new base is cached with
+    pub fn new_base_prop(&mut self, seed: Option<u64>) -> Result<PyramusId, PyramusError> {
         // TODO: This should become a call to the server. (Or a different function here entirely)
         let id = PyramusId::debug_new();
 
-        let stage = example_stage_prop()
+        let stage = example_stage_prop(seed.unwrap_or_else(new_seed))
             .inspect_err(|e| pyramus::log!("Err: {e}"))?;
         // TODO: See 'TODO' in set_base
         self.cache.insert_base(id, stage.base.clone());
 
         self.stage = stage; 
-        Ok(())
+        Ok(id)
     }
 
     pub fn set_base(&mut self, id: PyramusId) -> Result<(), PyramusError> {
@@ -54,10 +62,62 @@ impl Runtime {
         Ok(())
     }
 
+    /// Replace items with an instance of a new prop made from them, registering the prop in the cache
+    /// Returns the ID of the new prop (None if there were no items), and the resulting FrontendCommands
+    pub fn extract_prop(
+        &mut self,
+        item_ids: Vec<InternalId>,
+        name: String,
+    ) -> Result<(Option<PyramusId>, Vec<FrontendCommand>), JsValue> {
+        // The old parents of the items lose children
+        let mut changed: Vec<InternalId> = item_ids
+            .iter()
+            .filter_map(|id| self.stage.base.get_item(*id))
+            .filter_map(|item| item.get_parent())
+            .collect();
+        let mut removed = vec![];
+        for item_id in &item_ids {
+            if self.stage.base.get_item(*item_id).is_some() && !removed.contains(item_id) {
+                removed.extend(self.stage.base.get_subtree(*item_id)?);
+            }
+        }
+
+        let Some((instance_id, prop)) = self.stage.extract_prop(&item_ids, name)? else {
+            return Ok((None, vec![]));
+        };
+        // TODO: This should become a call to the server. (Or a different function here entirely)
+        let id = PyramusId::debug_new();
+        self.cache.insert_base(id, Base::new(prop.into()));
+
+        let parent = self
+            .stage
+            .base
+            .get_item(instance_id)
+            .and_then(|item| item.get_parent());
+        changed.extend(parent);
+        changed.retain(|id| !removed.contains(id));
+        changed.sort();
+        changed.dedup();
+        Ok((
+            Some(id),
+            vec![
+                FrontendCommand::ItemsRemoved { ids: removed },
+                FrontendCommand::ItemsAdded {
+                    ids: vec![instance_id],
+                },
+                FrontendCommand::ItemsChanged { ids: changed },
+                FrontendCommand::SelectionChanged {
+                    ids: self.stage.selection.clone(),
+                },
+                FrontendCommand::Rerender,
+            ],
+        ))
+    }
+
     pub fn command(
         &mut self,
         commands: Vec<BackendCommand>,
-    ) -> Result<Vec<FrontendCommand>, JsError> {
+    ) -> Result<Vec<FrontendCommand>, JsValue> {
         let mut frontend_response = vec![];
         for command in commands {
             frontend_response.extend(self.stage.process_command(command)?);
@@ -68,25 +128,25 @@ impl Runtime {
     pub fn input(
         &mut self,
         event: pyramus::input::InputEvent,
-    ) -> Result<Vec<FrontendCommand>, JsError> {
+    ) -> Result<Vec<FrontendCommand>, JsValue> {
         Ok(self.stage.process_event(event)?)
     }
 
-    pub fn render_string(&self) -> Result<String, JsError> {
+    pub fn render_string(&self) -> Result<String, JsValue> {
         Ok(pyramus::render::render_string(&self.stage)?)
     }
 }
 
 // Resolve a BackendCommand, and dispatch any resulting FrontendCommands
 // TODO: turn this back to impl IntoIterator<Item = BackendCommand>
-pub fn command(commands: Vec<BackendCommand>) -> Result<(), JsError> {
+pub fn command(commands: Vec<BackendCommand>) -> Result<(), JsValue> {
     let frontend_response = RUNTIME.with(|runtime| {
         let mut runtime = runtime.borrow_mut();
         let responses = runtime
             .as_mut()
             .map(|runtime| runtime.command(commands))
             .ok_or_else(|| pyramus::PyramusError::NoRuntimeFound)??;
-        Ok::<Vec<_>, JsError>(responses)
+        Ok::<Vec<_>, JsValue>(responses)
     })?;
 
     CALLBACKS.with(|callbacks| {
@@ -94,12 +154,35 @@ pub fn command(commands: Vec<BackendCommand>) -> Result<(), JsError> {
         for command in frontend_response {
             dispatch_frontend_command(&js_callbacks, command)?;
         }
-        Ok::<(), JsError>(())
+        Ok::<(), JsValue>(())
     })?;
 
     Ok(())
 }
 
+// Replace items with an instance of a new prop made from them, and dispatch any resulting FrontendCommands
+// Returns the ID the new prop was registered in the cache under, if one was made
+pub fn extract_prop(item_ids: Vec<InternalId>, name: String) -> Result<Option<PyramusId>, JsValue> {
+    let (prop_id, frontend_response) = RUNTIME.with(|runtime| {
+        let mut runtime = runtime.borrow_mut();
+        let response = runtime
+            .as_mut()
+            .map(|runtime| runtime.extract_prop(item_ids, name))
+            .ok_or_else(|| pyramus::PyramusError::NoRuntimeFound)??;
+        Ok::<_, JsValue>(response)
+    })?;
+
+    CALLBACKS.with(|callbacks| {
+        let js_callbacks = callbacks.borrow();
+        for command in frontend_response {
+            dispatch_frontend_command(&js_callbacks, command)?;
+        }
+        Ok::<(), JsValue>(())
+    })?;
+
+    Ok(prop_id)
+}
+
 pub fn dispatch_frontend_command(
     js_callbacks: &CallbacksMap,
     command: FrontendCommand,
@@ -107,8 +190,10 @@ pub fn dispatch_frontend_command(
     let s: String = command.to_string();
     match js_callbacks.get(&s) {
         Some(callback) => {
+            // The fields of the command (such as the IDs of changed items) are passed as its data
+            let data = serde_wasm_bindgen::to_value(&command).map_err(JsValue::from)?;
             callback
-                .call1(&JsValue::NULL, &JsValue::from_str(&s))
+                .call2(&JsValue::NULL, &JsValue::from_str(&s), &data)
                 .map(pyramus::PyramusError::from)?;
         }
         None => {
@@ -119,17 +204,28 @@ pub fn dispatch_frontend_command(
     Ok(())
 }
 
+// Get the limits applied to uploaded images
+pub fn image_limits() -> Result<ImageImportLimits, JsValue> {
+    RUNTIME.with(|runtime| {
+        let runtime = runtime.borrow();
+        Ok(runtime
+            .as_ref()
+            .map(|runtime| runtime.image_limits)
+            .ok_or_else(|| pyramus::PyramusError::NoRuntimeFound)?)
+    })
+}
+
 // Process an InputEvent, and dispatch any resulting FrontendCommands
 // InputEvents are translated into BackendCommands behind the scenes, and also result in FrontendCommands,
 // but may have additional behaviour that is not related to commands
-pub fn input(event: pyramus::input::InputEvent) -> Result<(), JsError> {
+pub fn input(event: pyramus::input::InputEvent) -> Result<(), JsValue> {
     let frontend_response = RUNTIME.with(|runtime| {
         let mut runtime = runtime.borrow_mut();
         let responses = runtime
             .as_mut()
             .map(|runtime| runtime.input(event))
             .ok_or_else(|| pyramus::PyramusError::NoRuntimeFound)??;
-        Ok::<Vec<_>, JsError>(responses)
+        Ok::<Vec<_>, JsValue>(responses)
     })?;
 
     CALLBACKS.with(|callbacks| {
@@ -137,7 +233,7 @@ pub fn input(event: pyramus::input::InputEvent) -> Result<(), JsError> {
         for command in frontend_response {
             dispatch_frontend_command(&js_callbacks, command)?;
         }
-        Ok::<(), JsError>(())
+        Ok::<(), JsValue>(())
     })?;
 
     Ok(())
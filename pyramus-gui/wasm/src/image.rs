@@ -3,7 +3,8 @@ use wasm_bindgen::prelude::*;
 /// Provides pointer to the start of the image buffer
 #[wasm_bindgen(js_name = imageBufferPointer)]
 pub fn image_buffer_pointer() -> *const u8 {
-    unsafe { OUTPUT_BUFFER.as_ptr() }
+    // A raw pointer is taken without creating a reference to the mutable static
+    std::ptr::addr_of!(OUTPUT_BUFFER) as *const u8
 }
 
 // TODO: Look at this again- checkerboard was for testing, but is now unused
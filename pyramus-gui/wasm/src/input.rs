@@ -1,20 +1,65 @@
-use pyramus::input::InputEvent;
-use wasm_bindgen::{prelude::wasm_bindgen, JsError};
+use pyramus::{
+    command::BackendCommand,
+    input::{snapping::Snapping, InputEvent, Tool},
+    models::templates::paint::Stroke,
+};
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
 
 #[wasm_bindgen(js_name = inputMouseDown)]
-pub fn input_mouse_down(x: f32, y: f32) -> Result<(), JsError> {
+pub fn input_mouse_down(x: f32, y: f32) -> Result<(), JsValue> {
     crate::editor::input(InputEvent::MouseDown { x, y })?;
     Ok(())
 }
 
 #[wasm_bindgen(js_name = inputMouseUp)]
-pub fn input_mouse_up() -> Result<(), JsError> {
+pub fn input_mouse_up() -> Result<(), JsValue> {
     crate::editor::input(InputEvent::MouseUp)?;
     Ok(())
 }
 
 #[wasm_bindgen(js_name = inputMouseMove)]
-pub fn input_mouse_move(delta_x: f32, delta_y: f32) -> Result<(), JsError> {
-    crate::editor::input(InputEvent::MouseMove { delta_x, delta_y })?;
+pub fn input_mouse_move(delta_x: f32, delta_y: f32, disable_snapping: bool) -> Result<(), JsValue> {
+    crate::editor::input(InputEvent::MouseMove {
+        delta_x,
+        delta_y,
+        disable_snapping,
+    })?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = inputDoubleClick)]
+pub fn input_double_click(x: f32, y: f32) -> Result<(), JsValue> {
+    crate::editor::input(InputEvent::DoubleClick { x, y })?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = setToolSelect)]
+pub fn set_tool_select() -> Result<(), JsValue> {
+    crate::editor::command(vec![BackendCommand::SetTool(Tool::Select)])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = setToolPencil)]
+pub fn set_tool_pencil(red: u8, green: u8, blue: u8, stroke_width: f32) -> Result<(), JsValue> {
+    crate::editor::command(vec![BackendCommand::SetTool(Tool::Pencil {
+        stroke: Stroke::solid((red, green, blue), stroke_width),
+    })])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = setSnapping)]
+pub fn set_snapping(
+    enabled: bool,
+    grid: Option<f32>,
+    to_items: bool,
+    to_template: bool,
+) -> Result<(), JsValue> {
+    crate::editor::command(vec![BackendCommand::SetSnapping(Snapping {
+        enabled,
+        grid,
+        to_items,
+        to_template,
+        ..Default::default()
+    })])?;
     Ok(())
 }
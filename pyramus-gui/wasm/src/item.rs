@@ -1,10 +1,19 @@
 use crate::{
     editor::{self, command},
-    models::FrontendStage,
+    models::{
+        FrontendAlignTo, FrontendAlignment, FrontendAxis, FrontendBlendMode, FrontendEffect,
+        FrontendHitTest, FrontendImageAdjustments, FrontendItem, FrontendMaskMode, FrontendPaint,
+        FrontendPathNode, FrontendPropOutline, FrontendShapeKind, FrontendStage, FrontendStroke,
+    },
 };
 use pyramus::{
     command::BackendCommand,
-    models::templates::{ids::InternalId, transform::RelativeTransform},
+    models::templates::{
+        ids::InternalId,
+        paint::Paint,
+        prop_item::{ImageCrop, PropItemImage},
+        transform::RelativeTransform,
+    },
 };
 use wasm_bindgen::prelude::*;
 
@@ -12,21 +21,91 @@ use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen(js_name = removeObject)]
 // TODO: should we have a way this can directly return an error?
-pub fn remove_object(item_id: u32) -> Result<(), JsError> {
+pub fn remove_object(item_id: u32) -> Result<(), JsValue> {
     command(vec![BackendCommand::DeleteItem(InternalId(item_id))])?;
     Ok(())
 }
 
 #[wasm_bindgen(js_name = selectObjects)]
-pub fn select_objects(item_ids: Vec<u32>) -> Result<(), JsError> {
+pub fn select_objects(item_ids: Vec<u32>) -> Result<(), JsValue> {
     command(vec![BackendCommand::SetSelection(
         item_ids.into_iter().map(InternalId).collect(),
     )])?;
     Ok(())
 }
 
+#[wasm_bindgen(js_name = groupObjects)]
+pub fn group_objects(item_ids: Vec<u32>) -> Result<(), JsValue> {
+    command(vec![BackendCommand::GroupItems(
+        item_ids.into_iter().map(InternalId).collect(),
+    )])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = ungroupObjects)]
+pub fn ungroup_objects(item_ids: Vec<u32>) -> Result<(), JsValue> {
+    command(vec![BackendCommand::UngroupItems(
+        item_ids.into_iter().map(InternalId).collect(),
+    )])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = extractProp)]
+// Returns the ID of the new prop, which can be switched to with switchEditorBase
+pub fn extract_prop(item_ids: Vec<u32>, name: String) -> Result<Option<String>, JsValue> {
+    let prop_id = editor::extract_prop(item_ids.into_iter().map(InternalId).collect(), name)?;
+    Ok(prop_id.map(|id| id.to_string()))
+}
+
+#[wasm_bindgen(js_name = enterGroup)]
+pub fn enter_group(item_id: u32) -> Result<(), JsValue> {
+    command(vec![BackendCommand::EnterGroup(InternalId(item_id))])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = exitGroup)]
+pub fn exit_group() -> Result<(), JsValue> {
+    command(vec![BackendCommand::ExitGroup])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = enterProp)]
+pub fn enter_prop(item_id: u32) -> Result<(), JsValue> {
+    command(vec![BackendCommand::EnterProp(InternalId(item_id))])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = exitProp)]
+pub fn exit_prop() -> Result<(), JsValue> {
+    command(vec![BackendCommand::ExitProp])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = alignObjects)]
+pub fn align_objects(
+    item_ids: Vec<u32>,
+    alignment: FrontendAlignment,
+    align_to: FrontendAlignTo,
+) -> Result<(), JsValue> {
+    command(vec![BackendCommand::AlignItems(
+        item_ids.into_iter().map(InternalId).collect(),
+        alignment.into_alignment(),
+        align_to.into_align_to(),
+    )])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = distributeObjects)]
+pub fn distribute_objects(item_ids: Vec<u32>, axis: FrontendAxis) -> Result<(), JsValue> {
+    command(vec![BackendCommand::DistributeItems(
+        item_ids.into_iter().map(InternalId).collect(),
+        axis.into_axis(),
+    )])?;
+    Ok(())
+}
+
 #[wasm_bindgen(js_name = renameObject)]
-pub fn rename_object(item_id: u32, name: String) -> Result<(), JsError> {
+pub fn rename_object(item_id: u32, name: String) -> Result<(), JsValue> {
     command(vec![BackendCommand::RenameItem(InternalId(item_id), name)])?;
     Ok(())
 }
@@ -39,7 +118,7 @@ pub fn edit_transform(
     rotation: f32,
     scale_x: f32,
     scale_y: f32,
-) -> Result<(), JsError> {
+) -> Result<(), JsValue> {
     // TODO: This might make more sense as 3 separate functions
     command(vec![BackendCommand::EditTransform(
         InternalId(item_id),
@@ -52,8 +131,226 @@ pub fn edit_transform(
     Ok(())
 }
 
+#[wasm_bindgen(js_name = setOpacity)]
+pub fn set_opacity(item_id: u32, opacity: f32) -> Result<(), JsValue> {
+    command(vec![BackendCommand::SetOpacity(
+        InternalId(item_id),
+        opacity,
+    )])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = setHidden)]
+pub fn set_hidden(item_id: u32, hidden: bool) -> Result<(), JsValue> {
+    command(vec![BackendCommand::SetHidden(InternalId(item_id), hidden)])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = setBlendMode)]
+pub fn set_blend_mode(item_id: u32, blend_mode: FrontendBlendMode) -> Result<(), JsValue> {
+    command(vec![BackendCommand::SetBlendMode(
+        InternalId(item_id),
+        blend_mode.into_blend_mode(),
+    )])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = addEffect)]
+pub fn add_effect(item_id: u32, effect: FrontendEffect) -> Result<(), JsValue> {
+    command(vec![BackendCommand::AddEffect(
+        InternalId(item_id),
+        effect.into_effect(),
+    )])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = editEffect)]
+pub fn edit_effect(item_id: u32, index: usize, effect: FrontendEffect) -> Result<(), JsValue> {
+    command(vec![BackendCommand::EditEffect(
+        InternalId(item_id),
+        index,
+        effect.into_effect(),
+    )])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = removeEffect)]
+pub fn remove_effect(item_id: u32, index: usize) -> Result<(), JsValue> {
+    command(vec![BackendCommand::RemoveEffect(
+        InternalId(item_id),
+        index,
+    )])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = setHitTest)]
+pub fn set_hit_test(item_id: u32, hit_test: FrontendHitTest) -> Result<(), JsValue> {
+    command(vec![BackendCommand::SetHitTest(
+        InternalId(item_id),
+        hit_test.into_hit_test(),
+    )])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = setMask)]
+pub fn set_mask(item_id: u32, mask: FrontendMaskMode) -> Result<(), JsValue> {
+    command(vec![BackendCommand::SetMask(
+        InternalId(item_id),
+        Some(mask.into_mask_mode()),
+    )])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = removeMask)]
+pub fn remove_mask(item_id: u32) -> Result<(), JsValue> {
+    command(vec![BackendCommand::SetMask(InternalId(item_id), None)])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = setCrop)]
+pub fn set_crop(item_id: u32, x: f32, y: f32, width: f32, height: f32) -> Result<(), JsValue> {
+    command(vec![BackendCommand::SetCrop(
+        InternalId(item_id),
+        Some(ImageCrop {
+            x,
+            y,
+            width,
+            height,
+        }),
+    )])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = removeCrop)]
+pub fn remove_crop(item_id: u32) -> Result<(), JsValue> {
+    command(vec![BackendCommand::SetCrop(InternalId(item_id), None)])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = setImageAdjustments)]
+pub fn set_image_adjustments(
+    item_id: u32,
+    adjustments: FrontendImageAdjustments,
+) -> Result<(), JsValue> {
+    command(vec![BackendCommand::SetImageAdjustments(
+        InternalId(item_id),
+        adjustments.into_adjustments(),
+    )])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = setPropOutline)]
+pub fn set_prop_outline(item_id: u32, outline: FrontendPropOutline) -> Result<(), JsValue> {
+    command(vec![BackendCommand::SetPropOutline(
+        InternalId(item_id),
+        Some(outline.into_outline()),
+    )])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = removePropOutline)]
+pub fn remove_prop_outline(item_id: u32) -> Result<(), JsValue> {
+    command(vec![BackendCommand::SetPropOutline(
+        InternalId(item_id),
+        None,
+    )])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = editShape)]
+pub fn edit_shape(
+    item_id: u32,
+    shape: FrontendShapeKind,
+    width: f32,
+    height: f32,
+) -> Result<(), JsValue> {
+    command(vec![BackendCommand::EditShape(
+        InternalId(item_id),
+        shape.into_shape(width, height),
+    )])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = editFill)]
+pub fn edit_fill(item_id: u32, fill: FrontendPaint) -> Result<(), JsValue> {
+    command(vec![BackendCommand::EditFill(
+        InternalId(item_id),
+        Some(fill.into_paint()?),
+    )])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = removeFill)]
+pub fn remove_fill(item_id: u32) -> Result<(), JsValue> {
+    command(vec![BackendCommand::EditFill(InternalId(item_id), None)])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = setFillPattern)]
+pub fn set_fill_pattern(
+    item_id: u32,
+    data: Vec<u8>,
+    width: f32,
+    height: f32,
+) -> Result<(), JsValue> {
+    let image = PropItemImage::from_bytes(data, &editor::image_limits()?)?;
+    command(vec![BackendCommand::EditFill(
+        InternalId(item_id),
+        Some(Paint::Pattern {
+            image: image.data,
+            width,
+            height,
+        }),
+    )])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = editStroke)]
+pub fn edit_stroke(item_id: u32, stroke: FrontendStroke) -> Result<(), JsValue> {
+    command(vec![BackendCommand::EditStroke(
+        InternalId(item_id),
+        Some(stroke.into_stroke()?),
+    )])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = removeStroke)]
+pub fn remove_stroke(item_id: u32) -> Result<(), JsValue> {
+    command(vec![BackendCommand::EditStroke(InternalId(item_id), None)])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = editPathNode)]
+pub fn edit_path_node(item_id: u32, index: usize, node: FrontendPathNode) -> Result<(), JsValue> {
+    command(vec![BackendCommand::EditPathNode(
+        InternalId(item_id),
+        index,
+        node.into_node(),
+    )])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = insertPathNode)]
+pub fn insert_path_node(item_id: u32, index: usize, node: FrontendPathNode) -> Result<(), JsValue> {
+    command(vec![BackendCommand::InsertPathNode(
+        InternalId(item_id),
+        index,
+        node.into_node(),
+    )])?;
+    Ok(())
+}
+
+#[wasm_bindgen(js_name = removePathNode)]
+pub fn remove_path_node(item_id: u32, index: usize) -> Result<(), JsValue> {
+    command(vec![BackendCommand::RemovePathNode(
+        InternalId(item_id),
+        index,
+    )])?;
+    Ok(())
+}
+
 #[wasm_bindgen(js_name = getStage)]
-pub fn get_items() -> Result<FrontendStage, JsError> {
+pub fn get_items() -> Result<FrontendStage, JsValue> {
     editor::RUNTIME.with(|runtime| {
         let runtime = runtime.borrow();
         Ok(runtime
@@ -62,3 +359,18 @@ pub fn get_items() -> Result<FrontendStage, JsError> {
             .ok_or_else(|| pyramus::PyramusError::NoRuntimeFound)?)
     })
 }
+
+#[wasm_bindgen(js_name = getItem)]
+pub fn get_item(item_id: u32) -> Result<Option<FrontendItem>, JsValue> {
+    editor::RUNTIME.with(|runtime| {
+        let runtime = runtime.borrow();
+        let stage = &runtime
+            .as_ref()
+            .ok_or_else(|| pyramus::PyramusError::NoRuntimeFound)?
+            .stage;
+        Ok(stage
+            .base
+            .get_item(InternalId(item_id))
+            .map(|item| FrontendItem::from(item, stage)))
+    })
+}
@@ -27,22 +27,52 @@ pub fn switch_editor_base(id : String) -> Result<(), JsValue> {
     let id : PyramusId = PyramusId::from_str(&id).map_err(|e| JsValue::from_str(&format!("Could not decode PyramusId {}", e)))?;
     editor::RUNTIME.with(|runtime| {
         let mut runtime = runtime.borrow_mut();
-        let runtime = runtime.as_mut().unwrap();
+        let runtime = runtime
+            .as_mut()
+            .ok_or(pyramus::PyramusError::NoRuntimeFound)?;
         runtime.set_base(id)?;
-        Ok::<(), JsError>(())
+        Ok::<(), JsValue>(())
     })?;
 
     // TODO: Reorganize this
     CALLBACKS.with(|callbacks| {
         let js_callbacks = callbacks.borrow();
-        let command = FrontendCommand::Rerender;
-        dispatch_frontend_command(&js_callbacks, command)?;
-        Ok::<(), JsError>(())
+        for command in [FrontendCommand::UpdateStage, FrontendCommand::Rerender] {
+            dispatch_frontend_command(&js_callbacks, command)?;
+        }
+        Ok::<(), JsValue>(())
     })?;
 
     Ok(())
 }
 
+/// Generate a new base prop and edit it, from a seed if given (otherwise a new seed is generated)
+/// Returns the ID of the new base, which can be opened with switchEditorBase
+#[wasm_bindgen(js_name = newEditorBase)]
+pub fn new_editor_base(seed : Option<String>) -> Result<String, JsValue> {
+    let seed = seed
+        .map(|seed| seed.trim().parse::<u64>())
+        .transpose()
+        .map_err(|e| JsValue::from_str(&format!("Could not decode seed {}", e)))?;
+    let id = editor::RUNTIME.with(|runtime| {
+        let mut runtime = runtime.borrow_mut();
+        let runtime = runtime
+            .as_mut()
+            .ok_or(pyramus::PyramusError::NoRuntimeFound)?;
+        Ok::<PyramusId, JsValue>(runtime.new_base_prop(seed)?)
+    })?;
+
+    CALLBACKS.with(|callbacks| {
+        let js_callbacks = callbacks.borrow();
+        for command in [FrontendCommand::UpdateStage, FrontendCommand::Rerender] {
+            dispatch_frontend_command(&js_callbacks, command)?;
+        }
+        Ok::<(), JsValue>(())
+    })?;
+
+    Ok(id.to_string())
+}
+
 #[wasm_bindgen(js_name = subscribeFrontendCommand)]
 pub fn subscribe(event: String, callback: js_sys::Function) {
     CALLBACKS.with(|callbacks| {
@@ -69,11 +99,12 @@ pub fn get_stage_json() -> Result<String, JsValue> {
         let runtime = runtime
             .as_ref()
             .ok_or_else(|| JsValue::from_str("No runtime found"))?;
-        let base = &runtime.stage.base;
+        // The whole document is saved, even while a nested prop is being edited
+        let base = runtime.stage.to_top_level_base()?;
         // TODO: This creates some huge JSON strings for images, so we need to cache those somehow.
         // (Perhaps online- or in browser cache?)
         let stage_json =
-            serde_json::to_string(base).map_err(|e| JsValue::from_str(&format!("{}", e)))?;
+            serde_json::to_string(&base).map_err(|e| JsValue::from_str(&format!("{}", e)))?;
         Ok(stage_json)
     })
 }
@@ -4,9 +4,22 @@
 use std::collections::HashMap;
 
 use pyramus::models::{
-    editor::{item::StageItem, staging::Staging},
-    templates::prop_item::PropItemType,
+    editor::{
+        item::StageItem,
+        layout::{AlignTo, Alignment, Axis},
+        staging::{BlendMode, HitTest, MaskMode, Staging},
+    },
+    templates::{
+        adjustment::{ImageAdjustments, Tint},
+        effect::Effect,
+        outline::PropOutline,
+        paint::{GradientStop, LineCap, LineJoin, Paint, Stroke},
+        path::{PathNode, PropItemPath},
+        prop_item::PropItemType,
+        shape::{PropItemShape, Shape},
+    },
 };
+use pyramus::{InvalidReason, PyramusError};
 use serde::{Deserialize, Serialize};
 use tsify::Tsify;
 use wasm_bindgen::prelude::wasm_bindgen;
@@ -16,6 +29,13 @@ use wasm_bindgen::prelude::wasm_bindgen;
 pub struct FrontendStage {
     pub items: HashMap<u32, FrontendItem>,
     pub selected: Vec<u32>,
+    /// The group being edited, if any
+    pub entered_group: Option<u32>,
+    /// Names of the nested props being edited, outermost first (empty when editing the base itself)
+    pub entered_props: Vec<String>,
+    /// The seed the base was randomly generated from, if any
+    /// (As a string, as seeds may not fit in a JS number)
+    pub seed: Option<String>,
 }
 
 #[derive(Debug, Tsify, Serialize, Deserialize)]
@@ -35,21 +55,267 @@ pub struct FrontendItem {
     pub position: (f32, f32),
     pub scale: (f32, f32),
     pub rotation: f32, // In degrees
+
+    pub opacity: f32,
+    pub hidden: bool,
+    pub blend_mode: FrontendBlendMode,
+    pub effects: Vec<FrontendEffect>,
+    pub mask: Option<FrontendMaskMode>,
+    pub hit_test: FrontendHitTest,
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum FrontendMaskMode {
+    Clip,
+    Alpha,
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum FrontendHitTest {
+    Bounds,
+    Precise { alpha_threshold: u8 },
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum FrontendAlignment {
+    Left,
+    Center,
+    Right,
+    Top,
+    Middle,
+    Bottom,
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum FrontendAlignTo {
+    Selection,
+    Parent,
+    Template,
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum FrontendAxis {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum FrontendBlendMode {
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
 }
 
 #[derive(Debug, Tsify, Serialize, Deserialize)]
 pub enum FrontendItemType {
     // TODO: This enum might need to be split for different stageable items
-    Prop,
+    Prop {
+        outline: Option<FrontendPropOutline>,
+    },
+    Group,
 
     Text {
         text: String,
         font_family: String,
         font_size: f32,
-        color: (u8, u8, u8),
+        fill: Option<FrontendPaint>,
+        stroke: Option<FrontendStroke>,
         italic: bool,
     },
-    Image,
+    Image {
+        viewport_width: f32,
+        viewport_height: f32,
+        crop: Option<FrontendImageCrop>,
+        original_size: Option<FrontendImageOriginalSize>,
+        adjustments: FrontendImageAdjustments,
+    },
+    Shape(FrontendShape),
+    Path(FrontendPath),
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum FrontendPropOutline {
+    Torn { seed: u32, roughness: f32 },
+    Path(Vec<FrontendPathNode>),
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+pub struct FrontendImageCrop {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct FrontendImageAdjustments {
+    pub brightness: f32,
+    pub contrast: f32,
+    pub saturation: f32,
+    pub hue_shift: f32,
+    pub tint: Option<FrontendTint>,
+    pub threshold: Option<f32>,
+    pub invert: bool,
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+pub struct FrontendTint {
+    pub color: (u8, u8, u8),
+    pub amount: f32,
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+pub struct FrontendImageOriginalSize {
+    pub width: u32,
+    pub height: u32,
+    pub bytes: usize,
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct FrontendShape {
+    pub shape: FrontendShapeKind,
+    pub width: f32,
+    pub height: f32,
+
+    pub fill: Option<FrontendPaint>,
+    pub stroke: Option<FrontendStroke>,
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+pub struct FrontendPath {
+    pub nodes: Vec<FrontendPathNode>,
+    pub closed: bool,
+
+    pub fill: Option<FrontendPaint>,
+    pub stroke: Option<FrontendStroke>,
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct FrontendPathNode {
+    pub position: (f32, f32),
+    pub handle_in: (f32, f32),
+    pub handle_out: (f32, f32),
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum FrontendEffect {
+    DropShadow {
+        offset: (f32, f32),
+        blur: f32,
+        color: (u8, u8, u8, u8),
+    },
+    Blur {
+        radius: f32,
+    },
+    Sepia {
+        amount: f32,
+    },
+    Grayscale {
+        amount: f32,
+    },
+    Noise {
+        amount: f32,
+        scale: f32,
+        seed: i32,
+    },
+    PaperTexture {
+        amount: f32,
+        scale: f32,
+        color: (u8, u8, u8),
+        seed: i32,
+    },
+    BurnedEdges {
+        width: f32,
+        color: (u8, u8, u8),
+        seed: i32,
+    },
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum FrontendPaint {
+    Solid((u8, u8, u8, u8)),
+    LinearGradient {
+        start: (f32, f32),
+        end: (f32, f32),
+        stops: Vec<FrontendGradientStop>,
+    },
+    RadialGradient {
+        center: (f32, f32),
+        radius: f32,
+        stops: Vec<FrontendGradientStop>,
+    },
+    // The image data of patterns is not sent to the frontend (see `setFillPattern`)
+    Pattern {
+        width: f32,
+        height: f32,
+    },
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+pub struct FrontendGradientStop {
+    pub offset: f32,
+    pub color: (u8, u8, u8, u8),
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct FrontendStroke {
+    pub paint: FrontendPaint,
+    pub width: f32,
+    pub dash: Vec<f32>,
+    pub dash_offset: f32,
+    pub line_cap: FrontendLineCap,
+    pub line_join: FrontendLineJoin,
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+pub enum FrontendLineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+pub enum FrontendLineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+#[derive(Debug, Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum FrontendShapeKind {
+    Rectangle,
+    RoundedRectangle { radius: f32 },
+    Ellipse,
+    Polygon { sides: u32 },
+    Star { points: u32, inner_ratio: f32 },
+    Line,
 }
 
 // Don't use 'From' trait because we want to convert with a reference, and with the stage context
@@ -63,6 +329,16 @@ impl FrontendStage {
                 .map(|(id, item)| (id.0, FrontendItem::from(item, stage)))
                 .collect::<HashMap<_, _>>(),
             selected: stage.selection.iter().map(|id| id.0).collect(),
+            entered_group: stage.entered_group.map(|id| id.0),
+            entered_props: stage
+                .edit_contexts
+                .iter()
+                .filter_map(|context| match context.base.get_item(context.prop_id) {
+                    Some(StageItem::Prop(prop)) => Some(prop.name.clone()),
+                    _ => None,
+                })
+                .collect(),
+            seed: stage.get_top_level_base().seed.map(|seed| seed.to_string()),
         }
     }
 }
@@ -80,6 +356,18 @@ impl FrontendItem {
                 scale: item.get_relative_transform().scale,
                 rotation: item.get_relative_transform().rotation,
 
+                opacity: item.staging.opacity,
+                hidden: item.staging.hidden,
+                blend_mode: FrontendBlendMode::from(item.staging.blend_mode),
+                effects: item
+                    .staging
+                    .effects
+                    .iter()
+                    .map(FrontendEffect::from)
+                    .collect(),
+                mask: item.staging.mask.map(FrontendMaskMode::from),
+                hit_test: FrontendHitTest::from(item.staging.hit_test),
+
                 item_type: FrontendItemType::from(&item.item),
             },
             StageItem::Prop(prop) => FrontendItem {
@@ -92,13 +380,95 @@ impl FrontendItem {
                 scale: item.get_relative_transform().scale,
                 rotation: item.get_relative_transform().rotation,
 
-                item_type: FrontendItemType::Prop,
+                opacity: prop.staging.opacity,
+                hidden: prop.staging.hidden,
+                blend_mode: FrontendBlendMode::from(prop.staging.blend_mode),
+                effects: prop
+                    .staging
+                    .effects
+                    .iter()
+                    .map(FrontendEffect::from)
+                    .collect(),
+                mask: prop.staging.mask.map(FrontendMaskMode::from),
+                hit_test: FrontendHitTest::from(prop.staging.hit_test),
+
+                item_type: FrontendItemType::Prop {
+                    outline: prop.outline.as_ref().map(FrontendPropOutline::from),
+                },
+            },
+            StageItem::Group(group) => FrontendItem {
+                id: group.id.0,
+                name: group.name.clone(),
+                is_root: group.id == stage.base.get_root(),
+                parent: group.get_parent().map(|id| id.0),
+                children: group.get_children().iter().map(|id| id.0).collect(),
+                position: item.get_relative_transform().position,
+                scale: item.get_relative_transform().scale,
+                rotation: item.get_relative_transform().rotation,
+
+                opacity: group.staging.opacity,
+                hidden: group.staging.hidden,
+                blend_mode: FrontendBlendMode::from(group.staging.blend_mode),
+                effects: group
+                    .staging
+                    .effects
+                    .iter()
+                    .map(FrontendEffect::from)
+                    .collect(),
+                mask: group.staging.mask.map(FrontendMaskMode::from),
+                hit_test: FrontendHitTest::from(group.staging.hit_test),
+
+                item_type: FrontendItemType::Group,
             },
         }
     }
 }
 
 // Don't use 'From' trait because we want to convert with a reference
+impl FrontendBlendMode {
+    pub fn from(mode: BlendMode) -> FrontendBlendMode {
+        match mode {
+            BlendMode::Normal => FrontendBlendMode::Normal,
+            BlendMode::Multiply => FrontendBlendMode::Multiply,
+            BlendMode::Screen => FrontendBlendMode::Screen,
+            BlendMode::Overlay => FrontendBlendMode::Overlay,
+            BlendMode::Darken => FrontendBlendMode::Darken,
+            BlendMode::Lighten => FrontendBlendMode::Lighten,
+            BlendMode::ColorDodge => FrontendBlendMode::ColorDodge,
+            BlendMode::ColorBurn => FrontendBlendMode::ColorBurn,
+            BlendMode::HardLight => FrontendBlendMode::HardLight,
+            BlendMode::SoftLight => FrontendBlendMode::SoftLight,
+            BlendMode::Difference => FrontendBlendMode::Difference,
+            BlendMode::Exclusion => FrontendBlendMode::Exclusion,
+            BlendMode::Hue => FrontendBlendMode::Hue,
+            BlendMode::Saturation => FrontendBlendMode::Saturation,
+            BlendMode::Color => FrontendBlendMode::Color,
+            BlendMode::Luminosity => FrontendBlendMode::Luminosity,
+        }
+    }
+
+    pub fn into_blend_mode(self) -> BlendMode {
+        match self {
+            FrontendBlendMode::Normal => BlendMode::Normal,
+            FrontendBlendMode::Multiply => BlendMode::Multiply,
+            FrontendBlendMode::Screen => BlendMode::Screen,
+            FrontendBlendMode::Overlay => BlendMode::Overlay,
+            FrontendBlendMode::Darken => BlendMode::Darken,
+            FrontendBlendMode::Lighten => BlendMode::Lighten,
+            FrontendBlendMode::ColorDodge => BlendMode::ColorDodge,
+            FrontendBlendMode::ColorBurn => BlendMode::ColorBurn,
+            FrontendBlendMode::HardLight => BlendMode::HardLight,
+            FrontendBlendMode::SoftLight => BlendMode::SoftLight,
+            FrontendBlendMode::Difference => BlendMode::Difference,
+            FrontendBlendMode::Exclusion => BlendMode::Exclusion,
+            FrontendBlendMode::Hue => BlendMode::Hue,
+            FrontendBlendMode::Saturation => BlendMode::Saturation,
+            FrontendBlendMode::Color => BlendMode::Color,
+            FrontendBlendMode::Luminosity => BlendMode::Luminosity,
+        }
+    }
+}
+
 impl FrontendItemType {
     pub fn from(item_type: &PropItemType) -> FrontendItemType {
         match item_type {
@@ -106,10 +476,458 @@ impl FrontendItemType {
                 text: text.text.clone(),
                 font_family: text.font_family.clone(),
                 font_size: text.font_size.get(),
-                color: text.color,
+                fill: text.fill.as_ref().map(FrontendPaint::from),
+                stroke: text.stroke.as_ref().map(FrontendStroke::from),
                 italic: text.italic,
             },
-            PropItemType::Image { .. } => FrontendItemType::Image,
+            PropItemType::Image(image) => FrontendItemType::Image {
+                viewport_width: image.viewport_width,
+                viewport_height: image.viewport_height,
+                crop: image.crop.map(|crop| FrontendImageCrop {
+                    x: crop.x,
+                    y: crop.y,
+                    width: crop.width,
+                    height: crop.height,
+                }),
+                original_size: image.original_size.map(|size| FrontendImageOriginalSize {
+                    width: size.width,
+                    height: size.height,
+                    bytes: size.bytes,
+                }),
+                adjustments: FrontendImageAdjustments::from(&image.adjustments),
+            },
+            PropItemType::Shape(shape) => FrontendItemType::Shape(FrontendShape::from(shape)),
+            PropItemType::Path(path) => FrontendItemType::Path(FrontendPath::from(path)),
+        }
+    }
+}
+
+impl FrontendShape {
+    pub fn from(item: &PropItemShape) -> FrontendShape {
+        let (shape, width, height) = match item.shape {
+            Shape::Rectangle { width, height } => (FrontendShapeKind::Rectangle, width, height),
+            Shape::RoundedRectangle {
+                width,
+                height,
+                radius,
+            } => (
+                FrontendShapeKind::RoundedRectangle { radius },
+                width,
+                height,
+            ),
+            Shape::Ellipse { width, height } => (FrontendShapeKind::Ellipse, width, height),
+            Shape::Polygon {
+                width,
+                height,
+                sides,
+            } => (FrontendShapeKind::Polygon { sides }, width, height),
+            Shape::Star {
+                width,
+                height,
+                points,
+                inner_ratio,
+            } => (
+                FrontendShapeKind::Star {
+                    points,
+                    inner_ratio,
+                },
+                width,
+                height,
+            ),
+            Shape::Line { width, height } => (FrontendShapeKind::Line, width, height),
+        };
+        FrontendShape {
+            shape,
+            width,
+            height,
+            fill: item.fill.as_ref().map(FrontendPaint::from),
+            stroke: item.stroke.as_ref().map(FrontendStroke::from),
+        }
+    }
+
+    pub fn into_shape(self) -> Result<PropItemShape, PyramusError> {
+        Ok(PropItemShape {
+            shape: self.shape.into_shape(self.width, self.height),
+            fill: self.fill.map(FrontendPaint::into_paint).transpose()?,
+            stroke: self.stroke.map(FrontendStroke::into_stroke).transpose()?,
+        })
+    }
+}
+
+impl FrontendShapeKind {
+    pub fn into_shape(self, width: f32, height: f32) -> Shape {
+        match self {
+            FrontendShapeKind::Rectangle => Shape::Rectangle { width, height },
+            FrontendShapeKind::RoundedRectangle { radius } => Shape::RoundedRectangle {
+                width,
+                height,
+                radius,
+            },
+            FrontendShapeKind::Ellipse => Shape::Ellipse { width, height },
+            FrontendShapeKind::Polygon { sides } => Shape::Polygon {
+                width,
+                height,
+                sides,
+            },
+            FrontendShapeKind::Star {
+                points,
+                inner_ratio,
+            } => Shape::Star {
+                width,
+                height,
+                points,
+                inner_ratio,
+            },
+            FrontendShapeKind::Line => Shape::Line { width, height },
+        }
+    }
+}
+
+impl FrontendPath {
+    pub fn from(path: &PropItemPath) -> FrontendPath {
+        FrontendPath {
+            nodes: path.nodes.iter().map(FrontendPathNode::from).collect(),
+            closed: path.closed,
+            fill: path.fill.as_ref().map(FrontendPaint::from),
+            stroke: path.stroke.as_ref().map(FrontendStroke::from),
+        }
+    }
+}
+
+impl FrontendImageAdjustments {
+    pub fn from(adjustments: &ImageAdjustments) -> FrontendImageAdjustments {
+        FrontendImageAdjustments {
+            brightness: adjustments.brightness,
+            contrast: adjustments.contrast,
+            saturation: adjustments.saturation,
+            hue_shift: adjustments.hue_shift,
+            tint: adjustments.tint.map(|tint| FrontendTint {
+                color: tint.color,
+                amount: tint.amount,
+            }),
+            threshold: adjustments.threshold,
+            invert: adjustments.invert,
+        }
+    }
+
+    pub fn into_adjustments(self) -> ImageAdjustments {
+        ImageAdjustments {
+            brightness: self.brightness,
+            contrast: self.contrast,
+            saturation: self.saturation,
+            hue_shift: self.hue_shift,
+            tint: self.tint.map(|tint| Tint {
+                color: tint.color,
+                amount: tint.amount,
+            }),
+            threshold: self.threshold,
+            invert: self.invert,
+        }
+    }
+}
+
+impl FrontendPropOutline {
+    pub fn from(outline: &PropOutline) -> FrontendPropOutline {
+        match outline {
+            PropOutline::Torn { seed, roughness } => FrontendPropOutline::Torn {
+                seed: *seed,
+                roughness: *roughness,
+            },
+            PropOutline::Path(nodes) => {
+                FrontendPropOutline::Path(nodes.iter().map(FrontendPathNode::from).collect())
+            }
         }
     }
+
+    pub fn into_outline(self) -> PropOutline {
+        match self {
+            FrontendPropOutline::Torn { seed, roughness } => PropOutline::Torn { seed, roughness },
+            FrontendPropOutline::Path(nodes) => {
+                PropOutline::Path(nodes.into_iter().map(FrontendPathNode::into_node).collect())
+            }
+        }
+    }
+}
+
+impl FrontendPathNode {
+    pub fn from(node: &PathNode) -> FrontendPathNode {
+        FrontendPathNode {
+            position: node.position,
+            handle_in: node.handle_in,
+            handle_out: node.handle_out,
+        }
+    }
+
+    pub fn into_node(self) -> PathNode {
+        PathNode {
+            position: self.position,
+            handle_in: self.handle_in,
+            handle_out: self.handle_out,
+        }
+    }
+}
+
+impl FrontendMaskMode {
+    pub fn from(mode: MaskMode) -> FrontendMaskMode {
+        match mode {
+            MaskMode::Clip => FrontendMaskMode::Clip,
+            MaskMode::Alpha => FrontendMaskMode::Alpha,
+        }
+    }
+
+    pub fn into_mask_mode(self) -> MaskMode {
+        match self {
+            FrontendMaskMode::Clip => MaskMode::Clip,
+            FrontendMaskMode::Alpha => MaskMode::Alpha,
+        }
+    }
+}
+
+impl FrontendHitTest {
+    pub fn from(hit_test: HitTest) -> FrontendHitTest {
+        match hit_test {
+            HitTest::Bounds => FrontendHitTest::Bounds,
+            HitTest::Precise { alpha_threshold } => FrontendHitTest::Precise { alpha_threshold },
+        }
+    }
+
+    pub fn into_hit_test(self) -> HitTest {
+        match self {
+            FrontendHitTest::Bounds => HitTest::Bounds,
+            FrontendHitTest::Precise { alpha_threshold } => HitTest::Precise { alpha_threshold },
+        }
+    }
+}
+
+impl FrontendAlignment {
+    pub fn into_alignment(self) -> Alignment {
+        match self {
+            FrontendAlignment::Left => Alignment::Left,
+            FrontendAlignment::Center => Alignment::Center,
+            FrontendAlignment::Right => Alignment::Right,
+            FrontendAlignment::Top => Alignment::Top,
+            FrontendAlignment::Middle => Alignment::Middle,
+            FrontendAlignment::Bottom => Alignment::Bottom,
+        }
+    }
+}
+
+impl FrontendAlignTo {
+    pub fn into_align_to(self) -> AlignTo {
+        match self {
+            FrontendAlignTo::Selection => AlignTo::Selection,
+            FrontendAlignTo::Parent => AlignTo::Parent,
+            FrontendAlignTo::Template => AlignTo::Template,
+        }
+    }
+}
+
+impl FrontendAxis {
+    pub fn into_axis(self) -> Axis {
+        match self {
+            FrontendAxis::Horizontal => Axis::Horizontal,
+            FrontendAxis::Vertical => Axis::Vertical,
+        }
+    }
+}
+
+impl FrontendEffect {
+    pub fn from(effect: &Effect) -> FrontendEffect {
+        match *effect {
+            Effect::DropShadow {
+                offset,
+                blur,
+                color,
+            } => FrontendEffect::DropShadow {
+                offset,
+                blur,
+                color,
+            },
+            Effect::Blur { radius } => FrontendEffect::Blur { radius },
+            Effect::Sepia { amount } => FrontendEffect::Sepia { amount },
+            Effect::Grayscale { amount } => FrontendEffect::Grayscale { amount },
+            Effect::Noise {
+                amount,
+                scale,
+                seed,
+            } => FrontendEffect::Noise {
+                amount,
+                scale,
+                seed,
+            },
+            Effect::PaperTexture {
+                amount,
+                scale,
+                color,
+                seed,
+            } => FrontendEffect::PaperTexture {
+                amount,
+                scale,
+                color,
+                seed,
+            },
+            Effect::BurnedEdges { width, color, seed } => {
+                FrontendEffect::BurnedEdges { width, color, seed }
+            }
+        }
+    }
+
+    pub fn into_effect(self) -> Effect {
+        match self {
+            FrontendEffect::DropShadow {
+                offset,
+                blur,
+                color,
+            } => Effect::DropShadow {
+                offset,
+                blur,
+                color,
+            },
+            FrontendEffect::Blur { radius } => Effect::Blur { radius },
+            FrontendEffect::Sepia { amount } => Effect::Sepia { amount },
+            FrontendEffect::Grayscale { amount } => Effect::Grayscale { amount },
+            FrontendEffect::Noise {
+                amount,
+                scale,
+                seed,
+            } => Effect::Noise {
+                amount,
+                scale,
+                seed,
+            },
+            FrontendEffect::PaperTexture {
+                amount,
+                scale,
+                color,
+                seed,
+            } => Effect::PaperTexture {
+                amount,
+                scale,
+                color,
+                seed,
+            },
+            FrontendEffect::BurnedEdges { width, color, seed } => {
+                Effect::BurnedEdges { width, color, seed }
+            }
+        }
+    }
+}
+
+impl FrontendPaint {
+    pub fn from(paint: &Paint) -> FrontendPaint {
+        let stops = |stops: &[GradientStop]| {
+            stops
+                .iter()
+                .map(|stop| FrontendGradientStop {
+                    offset: stop.offset,
+                    color: stop.color,
+                })
+                .collect()
+        };
+        match paint {
+            Paint::Solid(color) => FrontendPaint::Solid(*color),
+            Paint::LinearGradient {
+                start,
+                end,
+                stops: s,
+            } => FrontendPaint::LinearGradient {
+                start: *start,
+                end: *end,
+                stops: stops(s),
+            },
+            Paint::RadialGradient {
+                center,
+                radius,
+                stops: s,
+            } => FrontendPaint::RadialGradient {
+                center: *center,
+                radius: *radius,
+                stops: stops(s),
+            },
+            Paint::Pattern { width, height, .. } => FrontendPaint::Pattern {
+                width: *width,
+                height: *height,
+            },
+        }
+    }
+
+    pub fn into_paint(self) -> Result<Paint, PyramusError> {
+        let stops = |stops: Vec<FrontendGradientStop>| {
+            stops
+                .into_iter()
+                .map(|stop| GradientStop {
+                    offset: stop.offset,
+                    color: stop.color,
+                })
+                .collect()
+        };
+        Ok(match self {
+            FrontendPaint::Solid(color) => Paint::Solid(color),
+            FrontendPaint::LinearGradient {
+                start,
+                end,
+                stops: s,
+            } => Paint::LinearGradient {
+                start,
+                end,
+                stops: stops(s),
+            },
+            FrontendPaint::RadialGradient {
+                center,
+                radius,
+                stops: s,
+            } => Paint::RadialGradient {
+                center,
+                radius,
+                stops: stops(s),
+            },
+            FrontendPaint::Pattern { .. } => {
+                // Image patterns are set with setFillPattern, with the image data
+                return Err(PyramusError::InvalidPaint {
+                    field: "pattern",
+                    reason: InvalidReason::Unsupported,
+                });
+            }
+        })
+    }
+}
+
+impl FrontendStroke {
+    pub fn from(stroke: &Stroke) -> FrontendStroke {
+        FrontendStroke {
+            paint: FrontendPaint::from(&stroke.paint),
+            width: stroke.width,
+            dash: stroke.dash.clone(),
+            dash_offset: stroke.dash_offset,
+            line_cap: match stroke.line_cap {
+                LineCap::Butt => FrontendLineCap::Butt,
+                LineCap::Round => FrontendLineCap::Round,
+                LineCap::Square => FrontendLineCap::Square,
+            },
+            line_join: match stroke.line_join {
+                LineJoin::Miter => FrontendLineJoin::Miter,
+                LineJoin::Round => FrontendLineJoin::Round,
+                LineJoin::Bevel => FrontendLineJoin::Bevel,
+            },
+        }
+    }
+
+    pub fn into_stroke(self) -> Result<Stroke, PyramusError> {
+        Ok(Stroke {
+            paint: self.paint.into_paint()?,
+            width: self.width,
+            dash: self.dash,
+            dash_offset: self.dash_offset,
+            line_cap: match self.line_cap {
+                FrontendLineCap::Butt => LineCap::Butt,
+                FrontendLineCap::Round => LineCap::Round,
+                FrontendLineCap::Square => LineCap::Square,
+            },
+            line_join: match self.line_join {
+                FrontendLineJoin::Miter => LineJoin::Miter,
+                FrontendLineJoin::Round => LineJoin::Round,
+                FrontendLineJoin::Bevel => LineJoin::Bevel,
+            },
+        })
+    }
 }
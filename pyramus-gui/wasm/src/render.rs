@@ -3,7 +3,7 @@ use wasm_bindgen::prelude::*;
 use crate::editor::RUNTIME;
 
 #[wasm_bindgen(js_name = testRenderStringResvg)]
-pub fn test_render_string() -> Result<String, JsError> {
+pub fn test_render_string() -> Result<String, JsValue> {
     RUNTIME.with(|runtime| {
         let runtime = runtime.borrow();
         runtime
@@ -0,0 +1,150 @@
+//! Measures the latency of dragging an item on a stage with hundreds of items
+//! Each frame of a drag translates the selection and renders the stage again
+//! The GUI renders by serializing the stage to an SVG string (`render_string`), which the browser rasterizes,
+//! so the time to build and serialize the tree is reported separately from rasterizing it here with resvg.
+//! The render cache only speeds up building the tree, so the rasterize time is about the same with or without it.
+//!
+//! Run with `cargo run --release --example drag_benchmark [item count] [frames]`
+
+use std::time::{Duration, Instant};
+
+use pyramus::{
+    command::BackendCommand,
+    models::{
+        editor::{base_item::Base, stage::Stage},
+        templates::{
+            builder::ItemBuilder,
+            ids::InternalId,
+            shape::{PropItemShape, Shape},
+            transform::RelativeTransform,
+        },
+    },
+};
+use resvg::{tiny_skia, usvg::XmlOptions};
+
+fn main() -> pyramus::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let item_count = args.next().and_then(|arg| arg.parse().ok()).unwrap_or(500);
+    let frames = args.next().and_then(|arg| arg.parse().ok()).unwrap_or(60);
+
+    let (mut stage, dragged) = build_stage(item_count)?;
+    println!("{item_count} items, dragging for {frames} frames");
+
+    // The first render builds every item, as after loading a document
+    let start = Instant::now();
+    stage.to_usvg_tree()?;
+    println!("first render: {:?}", start.elapsed());
+
+    let cached = drag(&mut stage, dragged, frames, false)?;
+    let uncached = drag(&mut stage, dragged, frames, true)?;
+    report("cached", &cached);
+    report("uncached", &uncached);
+    Ok(())
+}
+
+/// A stage with a mix of shapes, text and props, spread over the canvas
+fn build_stage(item_count: usize) -> pyramus::Result<(Stage, InternalId)> {
+    let mut base = Base::build_random_prop("Benchmark", 1600, 1200, 0);
+    let mut dragged = None;
+    for i in 0..item_count {
+        let builder = match i % 4 {
+            0 => ItemBuilder::build_shape(PropItemShape::filled(
+                Shape::Ellipse {
+                    width: 40.0,
+                    height: 30.0,
+                },
+                (200, 80, 40),
+            )),
+            1 => ItemBuilder::build_shape(PropItemShape::filled(
+                Shape::RoundedRectangle {
+                    width: 50.0,
+                    height: 40.0,
+                    radius: 8.0,
+                },
+                (40, 120, 200),
+            )),
+            // Fall back to fonts that are likely installed, so the text is shaped
+            2 => ItemBuilder::build_text(
+                format!("Item {i}"),
+                "Arial, DejaVu Sans, sans-serif".to_string(),
+                12.0,
+                (0, 0, 0).into(),
+                false,
+            ),
+            _ => ItemBuilder::build_random_prop(80, 60, i as u64),
+        };
+        let transform = RelativeTransform {
+            position: ((i % 40) as f32 * 40.0, (i / 40) as f32 * 40.0),
+            ..Default::default()
+        };
+        dragged = Some(base.add_child(builder.transform(transform))?);
+    }
+
+    let mut stage = Stage::new(base);
+    let dragged = dragged.expect("the stage has at least one item");
+    stage.set_selection(vec![dragged]);
+    Ok((stage, dragged))
+}
+
+/// Time taken by each step of rendering a frame
+struct Frame {
+    build: Duration,
+    serialize: Duration,
+    rasterize: Duration,
+}
+
+/// Drag an item by a pixel each frame, returning the time taken by each frame
+fn drag(
+    stage: &mut Stage,
+    item: InternalId,
+    frames: usize,
+    clear_cache: bool,
+) -> pyramus::Result<Vec<Frame>> {
+    let (width, height) = stage.base.get_size();
+    let mut pixmap = tiny_skia::Pixmap::new(width, height).expect("stage has a valid size");
+
+    let mut timings = Vec::with_capacity(frames);
+    for _ in 0..frames {
+        if clear_cache {
+            stage.base.render_cache.clear();
+        }
+        let start = Instant::now();
+        stage.process_command(BackendCommand::TranslateGroup(vec![item], (1.0, 1.0)))?;
+        let tree = stage.to_usvg_tree()?;
+        let build = start.elapsed();
+
+        // As `render_string` does, for the GUI
+        let start = Instant::now();
+        std::hint::black_box(tree.to_string(&XmlOptions::default()));
+        let serialize = start.elapsed();
+
+        let start = Instant::now();
+        pixmap.fill(tiny_skia::Color::TRANSPARENT);
+        resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+        let rasterize = start.elapsed();
+
+        timings.push(Frame {
+            build,
+            serialize,
+            rasterize,
+        });
+    }
+    Ok(timings)
+}
+
+fn report(name: &str, timings: &[Frame]) {
+    let average = |f: fn(&Frame) -> Duration| {
+        timings.iter().map(f).sum::<Duration>() / timings.len().max(1) as u32
+    };
+    let worst = timings
+        .iter()
+        .map(|frame| frame.build)
+        .max()
+        .unwrap_or_default();
+    println!(
+        "{name}: build {:?} (worst {worst:?}), build and serialize (as the GUI renders) {:?}, rasterize {:?} per frame",
+        average(|frame| frame.build),
+        average(|frame| frame.build + frame.serialize),
+        average(|frame| frame.rasterize),
+    );
+}
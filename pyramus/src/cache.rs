@@ -22,11 +22,15 @@ impl Cache {
     }
 
     // TODO: This should actually fetch data from the internet (or some similar solution). Currently, it's just for testing.
-    pub async fn fetch(&mut self) {
+    pub async fn fetch(&mut self) -> crate::Result<()> {
         let test_id = PyramusId(111);
         let test_str = include_str!("../../res/1n.json");
-        let test_base: Base = serde_json::from_str(test_str).unwrap();
+        let (test_base, repaired) = Base::load(test_str)?;
+        for diagnostic in repaired {
+            crate::log!("Repaired fetched base: {diagnostic:?}");
+        }
         self.cache.insert(test_id, CacheItem::Base(test_base));        
+        Ok(())
     }
 
     pub fn get_base(&self, id: PyramusId) -> Option<&Base> {
@@ -1,8 +1,28 @@
 use super::FrontendCommand;
 
-use crate::models::{
-    editor::stage::Stage,
-    templates::{builder::ItemBuilder, ids::InternalId, transform::RelativeTransform},
+use crate::{
+    input::{snapping::Snapping, MouseState, Tool},
+    models::{
+        editor::{
+            item::StageItem,
+            layout::{AlignTo, Alignment, Axis},
+            stage::Stage,
+            staging::{BlendMode, HitTest, MaskMode, Staging},
+        },
+        templates::{
+            adjustment::ImageAdjustments,
+            builder::ItemBuilder,
+            effect::Effect,
+            ids::InternalId,
+            outline::PropOutline,
+            paint::{validate_style, Paint, Stroke},
+            path::{PathNode, PropItemPath},
+            prop_item::{ImageCrop, PropItem, PropItemImageData, PropItemType},
+            shape::Shape,
+            transform::RelativeTransform,
+        },
+    },
+    PyramusError,
 };
 
 /// A command that can be sent from the frontend to the backend, to
@@ -17,15 +37,99 @@ pub enum BackendCommand {
     SetSelection(Vec<InternalId>),
 
     // TODO: Should this be EditTransform?
-    /// Translate a group of items in a direction (x,y)
+    /// Translate a group of items in a direction (x,y), in screen space
     TranslateGroup(Vec<InternalId>, (f32, f32)),
 
+    /// Line up the edges (or centers) of items, relative to their bounds together, their parents, or the template
+    AlignItems(Vec<InternalId>, Alignment, AlignTo),
+
+    /// Space items out with equal gaps between them along an axis
+    DistributeItems(Vec<InternalId>, Axis),
+
+    /// Put items into a new group, which is then selected (items keep their place on screen)
+    GroupItems(Vec<InternalId>),
+
+    /// Replace groups with their items, which are then selected (items keep their place on screen)
+    UngroupItems(Vec<InternalId>),
+
+    /// Edit the items of a group on their own, rather than selecting the group as one
+    EnterGroup(InternalId),
+
+    /// Go back up from editing a group, selecting it
+    ExitGroup,
+
+    /// Edit the items of a nested prop in place, with the templates around it dimmed
+    EnterProp(InternalId),
+
+    /// Go back up from editing a nested prop to the template it is in, selecting it
+    ExitProp,
+
     /// Change the transform of an item to provided RelativeTransform
     EditTransform(InternalId, RelativeTransform),
 
     /// Rename an item
     RenameItem(InternalId, String),
 
+    /// Change the opacity of an item and its children (0.0 to 1.0)
+    SetOpacity(InternalId, f32),
+
+    /// Hide or show an item and its children
+    SetHidden(InternalId, bool),
+
+    /// Change how an item is composited onto the items below it
+    SetBlendMode(InternalId, BlendMode),
+
+    /// Add an effect to the end of an item's effects
+    AddEffect(InternalId, Effect),
+
+    /// Change the parameters of the effect at an index of an item's effects
+    EditEffect(InternalId, usize, Effect),
+
+    /// Remove the effect at an index of an item's effects
+    RemoveEffect(InternalId, usize),
+
+    /// Change how clicks are tested against an item
+    SetHitTest(InternalId, HitTest),
+
+    /// Use a text, shape or path item as a mask for its children (None to draw it normally)
+    SetMask(InternalId, Option<MaskMode>),
+
+    /// Crop an image item to a rectangle within its viewport (None to show the whole image)
+    SetCrop(InternalId, Option<ImageCrop>),
+
+    /// Change the color adjustments of a raster image item
+    SetImageAdjustments(InternalId, ImageAdjustments),
+
+    /// Clip a prop to a torn or custom outline (None for its rectangular bounds)
+    SetPropOutline(InternalId, Option<PropOutline>),
+
+    /// Replace the geometry of a shape item
+    EditShape(InternalId, Shape),
+
+    /// Change the fill of a text, shape or path item (None for no fill)
+    EditFill(InternalId, Option<Paint>),
+
+    /// Change the stroke of a text, shape or path item (None for no stroke)
+    EditStroke(InternalId, Option<Stroke>),
+
+    /// Replace the nodes and style of a path item
+    EditPath(InternalId, PropItemPath),
+
+    /// Move or reshape the node at an index of a path item
+    EditPathNode(InternalId, usize, PathNode),
+
+    /// Insert a node into a path item before the given index (or at the end, if the index is the length)
+    InsertPathNode(InternalId, usize, PathNode),
+
+    /// Remove the node at an index of a path item
+    RemovePathNode(InternalId, usize),
+
+    /// Change the active input tool
+    SetTool(Tool),
+
+    /// Change how the selection snaps while it is dragged
+    SetSnapping(Snapping),
+
     /// Delete an item
     DeleteItem(InternalId),
 }
@@ -41,40 +145,371 @@ impl Stage {
             BackendCommand::CreateItem { new_item } => {
                 let item_id = self.base.add_child(new_item)?;
                 self.set_selection(vec![item_id]);
-                vec![FrontendCommand::UpdateStage]
+                // The parent gains a child
+                let parent = self
+                    .base
+                    .get_item(item_id)
+                    .and_then(|item| item.get_parent());
+                vec![
+                    FrontendCommand::ItemsAdded { ids: vec![item_id] },
+                    FrontendCommand::ItemsChanged {
+                        ids: parent.into_iter().collect(),
+                    },
+                    FrontendCommand::SelectionChanged {
+                        ids: self.selection.clone(),
+                    },
+                ]
             }
             BackendCommand::SetSelection(selection) => {
                 self.set_selection(selection);
-                vec![FrontendCommand::UpdateStage]
+                vec![FrontendCommand::SelectionChanged {
+                    ids: self.selection.clone(),
+                }]
             }
             BackendCommand::DeleteItem(item_id) => {
+                // The parent loses a child, and the children are re-parented to the root
+                let mut changed = vec![];
+                if let Some(item) = self.base.get_item(item_id) {
+                    changed.extend(item.get_parent());
+                    changed.extend(item.get_children().iter().copied());
+                    changed.push(self.base.get_root());
+                    changed.sort();
+                    changed.dedup();
+                }
                 self.base.remove_item(item_id)?;
-                vec![FrontendCommand::UpdateStage]
+                vec![
+                    FrontendCommand::ItemsRemoved { ids: vec![item_id] },
+                    FrontendCommand::ItemsChanged { ids: changed },
+                ]
             }
             BackendCommand::EditTransform(item_id, transform) => {
+                if !transform.is_finite() {
+                    return Err(PyramusError::NonFiniteTransform(item_id));
+                }
                 self.base.edit_item_transform(item_id, |t| {
                     *t = transform;
                     Ok(())
                 })?;
-                vec![FrontendCommand::UpdateStage]
+                vec![FrontendCommand::TransformsChanged { ids: vec![item_id] }]
+            }
+            BackendCommand::AlignItems(item_ids, alignment, align_to) => {
+                let ids = self.align_items(&item_ids, alignment, align_to)?;
+                vec![FrontendCommand::TransformsChanged { ids }]
+            }
+            BackendCommand::DistributeItems(item_ids, axis) => {
+                let ids = self.distribute_items(&item_ids, axis)?;
+                vec![FrontendCommand::TransformsChanged { ids }]
+            }
+            BackendCommand::GroupItems(item_ids) => {
+                // The old parents of the items lose children
+                let mut changed: Vec<InternalId> = item_ids
+                    .iter()
+                    .filter_map(|id| self.base.get_item(*id))
+                    .filter_map(|item| item.get_parent())
+                    .collect();
+                let Some(group_id) = self.group_items(&item_ids)? else {
+                    return Ok(vec![]);
+                };
+                let group = self
+                    .base
+                    .get_item(group_id)
+                    .ok_or(PyramusError::ItemNotFound(group_id))?;
+                changed.extend(group.get_parent());
+                changed.sort();
+                changed.dedup();
+                vec![
+                    FrontendCommand::ItemsAdded {
+                        ids: vec![group_id],
+                    },
+                    FrontendCommand::ItemsChanged { ids: changed },
+                    FrontendCommand::TransformsChanged {
+                        ids: group.get_children().clone(),
+                    },
+                    FrontendCommand::SelectionChanged {
+                        ids: self.selection.clone(),
+                    },
+                ]
+            }
+            BackendCommand::UngroupItems(group_ids) => {
+                // The parents of the groups gain their children
+                let mut changed: Vec<InternalId> = group_ids
+                    .iter()
+                    .filter_map(|id| self.base.get_item(*id))
+                    .filter_map(|item| item.get_parent())
+                    .collect();
+                changed.sort();
+                changed.dedup();
+                let entered_group = self.entered_group;
+                let ungrouped = self.ungroup_items(&group_ids)?;
+
+                let mut frontend_commands = vec![
+                    FrontendCommand::ItemsRemoved { ids: group_ids },
+                    FrontendCommand::ItemsChanged { ids: changed },
+                    FrontendCommand::TransformsChanged { ids: ungrouped },
+                    FrontendCommand::SelectionChanged {
+                        ids: self.selection.clone(),
+                    },
+                ];
+                if self.entered_group != entered_group {
+                    frontend_commands.push(FrontendCommand::UpdateStage);
+                }
+                frontend_commands
+            }
+            BackendCommand::EnterGroup(group_id) => {
+                self.enter_group(group_id)?;
+                vec![
+                    FrontendCommand::UpdateStage,
+                    FrontendCommand::SelectionChanged {
+                        ids: self.selection.clone(),
+                    },
+                ]
+            }
+            BackendCommand::ExitGroup => {
+                self.exit_group()?;
+                vec![
+                    FrontendCommand::UpdateStage,
+                    FrontendCommand::SelectionChanged {
+                        ids: self.selection.clone(),
+                    },
+                ]
+            }
+            BackendCommand::EnterProp(prop_id) => {
+                self.enter_prop(prop_id)?;
+                vec![
+                    FrontendCommand::UpdateStage,
+                    FrontendCommand::SelectionChanged {
+                        ids: self.selection.clone(),
+                    },
+                ]
+            }
+            BackendCommand::ExitProp => {
+                self.exit_prop()?;
+                vec![
+                    FrontendCommand::UpdateStage,
+                    FrontendCommand::SelectionChanged {
+                        ids: self.selection.clone(),
+                    },
+                ]
             }
             BackendCommand::RenameItem(item_id, name) => {
                 self.base.edit_item(item_id, |item| {
                     item.rename(name);
                     Ok(())
                 })?;
-                vec![FrontendCommand::UpdateStage]
+                vec![FrontendCommand::ItemsChanged { ids: vec![item_id] }]
+            }
+            BackendCommand::SetOpacity(item_id, opacity) => {
+                if !(0.0..=1.0).contains(&opacity) {
+                    return Err(PyramusError::InvalidOpacity(opacity));
+                }
+                self.base.edit_item(item_id, |item| {
+                    item.get_staging_context_mut().opacity = opacity;
+                    Ok(())
+                })?;
+                vec![FrontendCommand::ItemsChanged { ids: vec![item_id] }]
+            }
+            BackendCommand::SetHidden(item_id, hidden) => {
+                self.base.edit_item(item_id, |item| {
+                    item.get_staging_context_mut().hidden = hidden;
+                    Ok(())
+                })?;
+                vec![FrontendCommand::ItemsChanged { ids: vec![item_id] }]
+            }
+            BackendCommand::SetBlendMode(item_id, blend_mode) => {
+                self.base.edit_item(item_id, |item| {
+                    item.get_staging_context_mut().blend_mode = blend_mode;
+                    Ok(())
+                })?;
+                vec![FrontendCommand::ItemsChanged { ids: vec![item_id] }]
+            }
+            BackendCommand::AddEffect(item_id, effect) => {
+                effect.validate()?;
+                self.base.edit_item(item_id, |item| {
+                    item.get_staging_context_mut().effects.push(effect);
+                    Ok(())
+                })?;
+                vec![FrontendCommand::ItemsChanged { ids: vec![item_id] }]
+            }
+            BackendCommand::EditEffect(item_id, index, effect) => {
+                effect.validate()?;
+                self.base.edit_item(item_id, |item| {
+                    let effects = &mut item.get_staging_context_mut().effects;
+                    let e = effects
+                        .get_mut(index)
+                        .ok_or(PyramusError::EffectNotFound { id: item_id, index })?;
+                    *e = effect;
+                    Ok(())
+                })?;
+                vec![FrontendCommand::ItemsChanged { ids: vec![item_id] }]
+            }
+            BackendCommand::RemoveEffect(item_id, index) => {
+                self.base.edit_item(item_id, |item| {
+                    let effects = &mut item.get_staging_context_mut().effects;
+                    if index >= effects.len() {
+                        return Err(PyramusError::EffectNotFound { id: item_id, index });
+                    }
+                    effects.remove(index);
+                    Ok(())
+                })?;
+                vec![FrontendCommand::ItemsChanged { ids: vec![item_id] }]
+            }
+            BackendCommand::SetHitTest(item_id, hit_test) => {
+                self.base.edit_item(item_id, |item| {
+                    item.get_staging_context_mut().hit_test = hit_test;
+                    Ok(())
+                })?;
+                vec![FrontendCommand::ItemsChanged { ids: vec![item_id] }]
+            }
+            BackendCommand::SetMask(item_id, mask) => {
+                self.base.edit_item(item_id, |item| match item {
+                    StageItem::PropItem(PropItem {
+                        item: PropItemType::Text(_) | PropItemType::Shape(_) | PropItemType::Path(_),
+                        staging,
+                        ..
+                    }) => {
+                        staging.mask = mask;
+                        Ok(())
+                    }
+                    _ => Err(PyramusError::WrongItemType(item_id)),
+                })?;
+                vec![FrontendCommand::ItemsChanged { ids: vec![item_id] }]
+            }
+            BackendCommand::SetCrop(item_id, crop) => {
+                if let Some(crop) = &crop {
+                    crop.validate()?;
+                }
+                self.base.edit_item(item_id, |item| match item {
+                    StageItem::PropItem(PropItem {
+                        item: PropItemType::Image(image),
+                        ..
+                    }) => {
+                        image.crop = crop;
+                        Ok(())
+                    }
+                    _ => Err(PyramusError::WrongItemType(item_id)),
+                })?;
+                vec![FrontendCommand::ItemsChanged { ids: vec![item_id] }]
+            }
+            BackendCommand::SetImageAdjustments(item_id, adjustments) => {
+                adjustments.validate()?;
+                self.base.edit_item(item_id, |item| match item {
+                    StageItem::PropItem(PropItem {
+                        item: PropItemType::Image(image),
+                        ..
+                    }) if !matches!(image.data, PropItemImageData::Svg(_)) => {
+                        image.adjustments = adjustments;
+                        Ok(())
+                    }
+                    _ => Err(PyramusError::WrongItemType(item_id)),
+                })?;
+                vec![FrontendCommand::ItemsChanged { ids: vec![item_id] }]
+            }
+            BackendCommand::SetPropOutline(item_id, outline) => {
+                if let Some(outline) = &outline {
+                    outline.validate()?;
+                }
+                self.base.edit_item(item_id, |item| match item {
+                    StageItem::Prop(prop) => {
+                        prop.outline = outline;
+                        Ok(())
+                    }
+                    _ => Err(PyramusError::WrongItemType(item_id)),
+                })?;
+                vec![FrontendCommand::ItemsChanged { ids: vec![item_id] }]
+            }
+            BackendCommand::EditShape(item_id, shape) => {
+                shape.validate()?;
+                self.base.edit_item(item_id, |item| match item {
+                    StageItem::PropItem(PropItem {
+                        item: PropItemType::Shape(s),
+                        ..
+                    }) => {
+                        s.shape = shape;
+                        Ok(())
+                    }
+                    _ => Err(PyramusError::WrongItemType(item_id)),
+                })?;
+                vec![FrontendCommand::ItemsChanged { ids: vec![item_id] }]
+            }
+            BackendCommand::EditFill(item_id, paint) => {
+                if let Some(paint) = &paint {
+                    paint.validate()?;
+                }
+                self.edit_style(item_id, |fill, _| {
+                    *fill = paint;
+                })?;
+                vec![FrontendCommand::ItemsChanged { ids: vec![item_id] }]
+            }
+            BackendCommand::EditStroke(item_id, stroke) => {
+                if let Some(stroke) = &stroke {
+                    stroke.validate()?;
+                }
+                self.edit_style(item_id, |_, s| {
+                    *s = stroke;
+                })?;
+                vec![FrontendCommand::ItemsChanged { ids: vec![item_id] }]
+            }
+            BackendCommand::EditPath(item_id, path) => {
+                validate_style(path.fill.as_ref(), path.stroke.as_ref())?;
+                self.edit_path(item_id, |p| {
+                    *p = path;
+                    Ok(())
+                })?;
+                vec![FrontendCommand::ItemsChanged { ids: vec![item_id] }]
+            }
+            BackendCommand::EditPathNode(item_id, index, node) => {
+                if !node.is_finite() {
+                    return Err(PyramusError::NonFinitePathNode { id: item_id, index });
+                }
+                self.edit_path(item_id, |p| {
+                    let existing = p
+                        .nodes
+                        .get_mut(index)
+                        .ok_or(PyramusError::PathNodeNotFound { id: item_id, index })?;
+                    *existing = node;
+                    Ok(())
+                })?;
+                vec![FrontendCommand::ItemsChanged { ids: vec![item_id] }]
+            }
+            BackendCommand::InsertPathNode(item_id, index, node) => {
+                if !node.is_finite() {
+                    return Err(PyramusError::NonFinitePathNode { id: item_id, index });
+                }
+                self.edit_path(item_id, |p| {
+                    if index > p.nodes.len() {
+                        return Err(PyramusError::PathNodeNotFound { id: item_id, index });
+                    }
+                    p.nodes.insert(index, node);
+                    Ok(())
+                })?;
+                vec![FrontendCommand::ItemsChanged { ids: vec![item_id] }]
+            }
+            BackendCommand::RemovePathNode(item_id, index) => {
+                self.edit_path(item_id, |p| {
+                    if index >= p.nodes.len() {
+                        return Err(PyramusError::PathNodeNotFound { id: item_id, index });
+                    }
+                    p.nodes.remove(index);
+                    Ok(())
+                })?;
+                vec![FrontendCommand::ItemsChanged { ids: vec![item_id] }]
+            }
+            BackendCommand::SetTool(tool) => {
+                // Abandon anything in progress with the previous tool
+                self.tool = tool;
+                self.mouse_state = MouseState::Idle;
+                vec![FrontendCommand::Rerender]
+            }
+            BackendCommand::SetSnapping(snapping) => {
+                self.snapping = snapping;
+                vec![]
             }
             BackendCommand::TranslateGroup(item_ids, (x, y)) => {
-                for item_id in item_ids {
+                for &item_id in &item_ids {
                     // TODO: This pattern of going from stage -> base -> item -> function call is quite common. Might be a smell
-                    self.base.edit_item_transform(item_id, |t| {
-                        t.position.0 += x;
-                        t.position.1 += y;
-                        Ok(())
-                    })?;
+                    self.translate_in_screen_space(item_id, (x, y))?;
                 }
-                vec![FrontendCommand::UpdateStage]
+                vec![FrontendCommand::TransformsChanged { ids: item_ids }]
             }
         };
 
@@ -89,3 +524,38 @@ impl Stage {
         }
     }
 }
+
+impl Stage {
+    /// Edit the path of a path item
+    fn edit_path(
+        &mut self,
+        item_id: InternalId,
+        f: impl FnOnce(&mut PropItemPath) -> crate::Result<()>,
+    ) -> crate::Result<()> {
+        self.base.edit_item(item_id, |item| match item {
+            StageItem::PropItem(PropItem {
+                item: PropItemType::Path(path),
+                ..
+            }) => f(path),
+            _ => Err(PyramusError::WrongItemType(item_id)),
+        })
+    }
+
+    /// Edit the fill and stroke of a text, shape or path item
+    fn edit_style(
+        &mut self,
+        item_id: InternalId,
+        f: impl FnOnce(&mut Option<Paint>, &mut Option<Stroke>),
+    ) -> crate::Result<()> {
+        self.base.edit_item(item_id, |item| {
+            let StageItem::PropItem(PropItem { item, .. }) = item else {
+                return Err(PyramusError::WrongItemType(item_id));
+            };
+            let (fill, stroke) = item
+                .get_style_mut()
+                .ok_or(PyramusError::WrongItemType(item_id))?;
+            f(fill, stroke);
+            Ok(())
+        })
+    }
+}
@@ -1,6 +1,12 @@
+use serde::Serialize;
 use strum_macros::Display;
 
-#[derive(Display)]
+use crate::models::templates::ids::InternalId;
+
+/// A notification from the backend to the frontend, sent in response to a command or input event
+/// The name of the variant identifies the notification, and its fields are sent along with it as its payload
+#[derive(Display, Debug, Clone, Serialize)]
+#[serde(untagged)]
 pub enum FrontendCommand {
     /// Do not use directly.
     /// Should rerender the display
@@ -8,8 +14,23 @@ pub enum FrontendCommand {
     /// (See the `should_rerender` method for more information)
     Rerender,
 
-    /// The stage has been updated (e.g. items have been added, removed, or transformed)
+    /// The whole stage should be fetched again (e.g. a different base has been loaded)
     UpdateStage,
+
+    /// Items have been added to the stage
+    ItemsAdded { ids: Vec<InternalId> },
+
+    /// Items have been removed from the stage
+    ItemsRemoved { ids: Vec<InternalId> },
+
+    /// Properties of items (other than their transform) have changed, including their parent or children
+    ItemsChanged { ids: Vec<InternalId> },
+
+    /// Items have been moved, rotated or scaled
+    TransformsChanged { ids: Vec<InternalId> },
+
+    /// The selection has changed to these items
+    SelectionChanged { ids: Vec<InternalId> },
 }
 
 impl FrontendCommand {
@@ -19,7 +40,13 @@ impl FrontendCommand {
             // Re-render should not trigger a re-render
             FrontendCommand::Rerender => false,
 
-            FrontendCommand::UpdateStage => true,
+            FrontendCommand::UpdateStage
+            | FrontendCommand::ItemsAdded { .. }
+            | FrontendCommand::ItemsRemoved { .. }
+            | FrontendCommand::ItemsChanged { .. }
+            | FrontendCommand::TransformsChanged { .. }
+            // Selected items are outlined
+            | FrontendCommand::SelectionChanged { .. } => true,
         }
     }
 }
@@ -1,14 +1,38 @@
 use crate::{
     command::{BackendCommand, FrontendCommand},
-    models::editor::stage::Stage,
+    models::{
+        editor::{item::StageItem, stage::Stage},
+        templates::{
+            builder::ItemBuilder,
+            paint::Stroke,
+            path::PropItemPath,
+            transform::{invert_transform, RelativeTransform},
+        },
+    },
+    PyramusError,
 };
+use snapping::DragState;
+
+pub mod snapping;
+
+/// The active tool, which decides how pointer input is interpreted
+#[derive(Debug, Clone, Default)]
+pub enum Tool {
+    /// Click to select items, and drag to move them
+    #[default]
+    Select,
+    /// Drag to draw a freehand path
+    Pencil { stroke: Stroke },
+}
 
 /// Enum for the current state of the mouse, for tracking drag events
 #[derive(Debug)]
 pub enum MouseState {
     Idle,
     MouseDown(f32, f32),
-    DraggingMovement,
+    DraggingMovement(DragState),
+    /// Drawing with the pencil tool, with the points drawn so far (screen coordinates)
+    Drawing(Vec<(f32, f32)>),
 }
 
 impl MouseState {
@@ -21,13 +45,19 @@ impl MouseState {
                 let dx = x - *start_x;
                 let dy = y - *start_y;
                 if dx * dx + dy * dy > Self::DRAG_THRESHOLD * Self::DRAG_THRESHOLD {
-                    *self = Self::DraggingMovement;
+                    *self = Self::DraggingMovement(DragState::default());
                     Ok(())
                 } else {
                     Ok(())
                 }
             }
-            Self::DraggingMovement => Ok(()),
+            Self::DraggingMovement(_) => Ok(()),
+            Self::Drawing(points) => {
+                if let Some(&(last_x, last_y)) = points.last() {
+                    points.push((last_x + x, last_y + y));
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -36,9 +66,22 @@ impl MouseState {
 /// Keyboard, mouse, etc
 #[derive(Debug)]
 pub enum InputEvent {
-    MouseDown { x: f32, y: f32 },
+    MouseDown {
+        x: f32,
+        y: f32,
+    },
     MouseUp,
-    MouseMove { delta_x: f32, delta_y: f32 },
+    MouseMove {
+        delta_x: f32,
+        delta_y: f32,
+        /// Set while the modifier key that temporarily turns off snapping is held
+        disable_snapping: bool,
+    },
+    /// A second click in quick succession (after its MouseDown and MouseUp events)
+    DoubleClick {
+        x: f32,
+        y: f32,
+    },
     // TODO: Some kind of drag event- may need to be more complex, see Graphite's FSM
     // TODO: Keyboard events
 }
@@ -47,59 +90,103 @@ impl Stage {
     /// Process an input event, and return a list of frontend commands to be executed
     pub fn process_event(&mut self, event: InputEvent) -> crate::Result<Vec<FrontendCommand>> {
         match event {
-            InputEvent::MouseDown { x, y } => {
-                self.mouse_state = MouseState::MouseDown(x, y);
-                Ok(handle_selection(self, x, y)?)
-            }
+            InputEvent::MouseDown { x, y } => match self.tool {
+                Tool::Select => {
+                    self.mouse_state = MouseState::MouseDown(x, y);
+                    Ok(handle_selection(self, x, y)?)
+                }
+                Tool::Pencil { .. } => {
+                    self.mouse_state = MouseState::Drawing(vec![(x, y)]);
+                    // The in-progress path is not part of the stage, so only the display changes
+                    Ok(vec![FrontendCommand::Rerender])
+                }
+            },
             InputEvent::MouseUp => {
                 let frontend_commands =
-                    if let MouseState::MouseDown(start_x, start_y) = self.mouse_state {
-                        handle_click(self, start_x, start_y)?
-                    } else {
-                        vec![]
+                    match std::mem::replace(&mut self.mouse_state, MouseState::Idle) {
+                        MouseState::MouseDown(start_x, start_y) => {
+                            handle_click(self, start_x, start_y)?
+                        }
+                        MouseState::Drawing(points) => handle_draw_end(self, &points)?,
+                        // Clear the guides of the finished drag
+                        MouseState::DraggingMovement(drag) if !drag.guides.is_empty() => {
+                            vec![FrontendCommand::Rerender]
+                        }
+                        _ => vec![],
                     };
-                self.mouse_state = MouseState::Idle;
                 Ok(frontend_commands)
             }
-            InputEvent::MouseMove { delta_x, delta_y } => {
+            InputEvent::MouseMove {
+                delta_x,
+                delta_y,
+                disable_snapping,
+            } => {
                 self.mouse_state.update_from_movement(delta_x, delta_y)?;
-                let frontend_commands = if let MouseState::DraggingMovement = self.mouse_state {
-                    handle_drag(self, delta_x, delta_y)?
-                } else {
-                    vec![]
+                let frontend_commands = match self.mouse_state {
+                    MouseState::DraggingMovement(_) => {
+                        handle_drag(self, delta_x, delta_y, disable_snapping)?
+                    }
+                    MouseState::Drawing(_) => vec![FrontendCommand::Rerender],
+                    _ => vec![],
                 };
                 Ok(frontend_commands)
             }
+            InputEvent::DoubleClick { x, y } => match self.tool {
+                Tool::Select => handle_double_click(self, x, y),
+                Tool::Pencil { .. } => Ok(vec![]),
+            },
         }
     }
 }
 
 /// Handle the selection of the item found at x, y (screen coordinates)
 fn handle_selection(stage: &mut Stage, x: f32, y: f32) -> crate::Result<Vec<FrontendCommand>> {
+    // Clicking outside of the entered group leaves it, and clicking outside of the template of an entered prop leaves that
+    let mut frontend_commands = vec![];
+    loop {
+        if let Some(group_id) = stage.entered_group {
+            let inside = match stage.get_front_item_at(x, y, false)? {
+                Some(item_id) => {
+                    item_id != group_id && stage.base.is_within(item_id, &[group_id])?
+                }
+                None => false,
+            };
+            if inside {
+                break;
+            }
+            frontend_commands.extend(stage.process_command(BackendCommand::ExitGroup)?);
+        } else if !stage.edit_contexts.is_empty() && stage.get_front_item_at(x, y, true)?.is_none()
+        {
+            frontend_commands.extend(stage.process_command(BackendCommand::ExitProp)?);
+        } else {
+            break;
+        }
+    }
+
     // Find item at x, y
-    let item_id = stage.get_front_item_at(x, y, false);
+    let item_id = stage.get_front_item_at(x, y, false)?;
 
     if let Some(item_id) = item_id {
-        if stage.selection.iter().any(|s| *s == item_id) {
+        if stage.selection.contains(&item_id) {
             // If we click an item, and it's already selected, do nothing (perhaps it will be dragged, etc)
-            Ok(vec![])
         } else {
             // If we click an item, and it's not already selected, select it
-            Ok(stage.process_command(BackendCommand::SetSelection(vec![item_id]))?)
+            frontend_commands
+                .extend(stage.process_command(BackendCommand::SetSelection(vec![item_id]))?);
         }
     } else if stage.get_selections_mut().is_empty() {
         // If we click nothing, and we have no selection, do nothing
         // TODO: This should do a box select
-        Ok(vec![])
     } else {
         // If we click nothing, and we have a selection, clear the selection
-        Ok(stage.process_command(BackendCommand::SetSelection(vec![]))?)
+        frontend_commands.extend(stage.process_command(BackendCommand::SetSelection(vec![]))?);
     }
+    Ok(frontend_commands)
 }
 
 /// Handle the click of the item found at x, y (screen coordinates)
 fn handle_click(stage: &mut Stage, x: f32, y: f32) -> crate::Result<Vec<FrontendCommand>> {
-    let item_id = stage.get_front_item_at(x, y, false);
+    let item_id = stage.get_front_item_at(x, y, false)?;
 
     // If we fully get through a click, and it's on an item, select it
     // This 'overrides' 'handle_selection' behaviour as we know we aren't dragging
@@ -110,14 +197,114 @@ fn handle_click(stage: &mut Stage, x: f32, y: f32) -> crate::Result<Vec<Frontend
     }
 }
 
-/// Handle the drag of the selected items by delta_x, delta_y
+/// Handle a double click at x, y (screen coordinates), entering the group or prop found there and selecting the item in it
+fn handle_double_click(stage: &mut Stage, x: f32, y: f32) -> crate::Result<Vec<FrontendCommand>> {
+    let Some(item_id) = stage.get_front_item_at(x, y, false)? else {
+        return Ok(vec![]);
+    };
+    let command = match stage.base.get_item(item_id) {
+        Some(StageItem::Group(_)) => BackendCommand::EnterGroup(item_id),
+        Some(StageItem::Prop(_)) => BackendCommand::EnterProp(item_id),
+        _ => return Ok(vec![]),
+    };
+
+    let mut frontend_commands = stage.process_command(command)?;
+    if let Some(inner_id) = stage.get_front_item_at(x, y, false)? {
+        frontend_commands
+            .extend(stage.process_command(BackendCommand::SetSelection(vec![inner_id]))?);
+    }
+    Ok(frontend_commands)
+}
+
+/// Handle the drag of the selected items by delta_x, delta_y, snapping them unless `disable_snapping` is set
 fn handle_drag(
     stage: &mut Stage,
     delta_x: f32,
     delta_y: f32,
+    disable_snapping: bool,
 ) -> crate::Result<Vec<FrontendCommand>> {
+    let MouseState::DraggingMovement(drag) = &mut stage.mouse_state else {
+        return Ok(vec![]);
+    };
+    drag.offset.0 += delta_x;
+    drag.offset.1 += delta_y;
+    let (offset, applied) = (drag.offset, drag.applied);
+
+    // Snap where the selection would be if it followed the pointer exactly, so snaps don't build up over a drag
+    let selection_bounds = match disable_snapping || !stage.snapping.enabled {
+        true => None,
+        false => stage.get_selection_bounds()?,
+    };
+    let (snap, guides) = match selection_bounds {
+        Some((x0, y0, x1, y1)) => {
+            let (dx, dy) = (offset.0 - applied.0, offset.1 - applied.1);
+            stage.snap_bounds((x0 + dx, y0 + dy, x1 + dx, y1 + dy))?
+        }
+        None => ((0.0, 0.0), vec![]),
+    };
+
+    let target = (offset.0 + snap.0, offset.1 + snap.1);
+    let delta = (target.0 - applied.0, target.1 - applied.1);
+    if let MouseState::DraggingMovement(drag) = &mut stage.mouse_state {
+        drag.applied = target;
+        drag.guides = guides;
+    }
+
+    // A snap can hold the selection in place, but the guides may still change
+    if delta == (0.0, 0.0) {
+        return Ok(vec![FrontendCommand::Rerender]);
+    }
     stage.process_command(BackendCommand::TranslateGroup(
         stage.selection.clone(),
-        (delta_x, delta_y),
+        delta,
     ))
 }
+
+/// Handle the end of a pencil stroke, creating a smoothed path item from the drawn points (screen coordinates)
+fn handle_draw_end(
+    stage: &mut Stage,
+    points: &[(f32, f32)],
+) -> crate::Result<Vec<FrontendCommand>> {
+    let Tool::Pencil { stroke } = stage.tool.clone() else {
+        return Ok(vec![]);
+    };
+
+    // Paths are drawn onto the root, so convert the points into its local space
+    let root = stage.base.get_root();
+    let root_item = stage
+        .base
+        .get_item(root)
+        .ok_or(PyramusError::ItemNotFound(root))?;
+    let to_local = invert_transform(root_item.get_screen_transform(&stage.base)?, root)?;
+    let points: Vec<(f32, f32)> = points
+        .iter()
+        .map(|&(x, y)| {
+            let point = to_local.transform_point2(glam::Vec2::new(x, y));
+            (point.x, point.y)
+        })
+        .collect();
+
+    // Place the item at the top-left of the drawn points, so it transforms around its own corner
+    let (min_x, min_y) = points
+        .iter()
+        .fold((f32::INFINITY, f32::INFINITY), |(min_x, min_y), &(x, y)| {
+            (min_x.min(x), min_y.min(y))
+        });
+    if !min_x.is_finite() || !min_y.is_finite() {
+        return Ok(vec![]);
+    }
+    let points: Vec<(f32, f32)> = points
+        .into_iter()
+        .map(|(x, y)| (x - min_x, y - min_y))
+        .collect();
+
+    let nodes = PropItemPath::smooth_nodes(&points, PropItemPath::SMOOTHING_TOLERANCE);
+    stage.process_command(BackendCommand::CreateItem {
+        new_item: ItemBuilder::build_path(PropItemPath::stroked(nodes, stroke))
+            .name("Drawing")
+            .transform(RelativeTransform {
+                position: (min_x, min_y),
+                ..Default::default()
+            }),
+    })
+}
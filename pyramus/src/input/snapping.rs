@@ -0,0 +1,269 @@
+use std::collections::HashSet;
+
+use crate::{models::editor::stage::Stage, PyramusError};
+
+/// Settings for snapping the selection while it is dragged
+#[derive(Debug, Clone)]
+pub struct Snapping {
+    pub enabled: bool,
+    /// Size of the grid cells (in screen space, from the top-left of the template), or None for no grid
+    pub grid: Option<f32>,
+    /// Snap to the edges and centers of other items
+    pub to_items: bool,
+    /// Snap to the edges and center of the template
+    pub to_template: bool,
+    /// Distance (in screen space) within which the selection snaps
+    pub threshold: f32,
+}
+
+impl Default for Snapping {
+    fn default() -> Self {
+        Snapping {
+            enabled: true,
+            grid: None,
+            to_items: true,
+            to_template: true,
+            threshold: 6.0,
+        }
+    }
+}
+
+/// A guide line shown while dragging, for a snap that was applied (screen coordinates)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Guide {
+    pub from: (f32, f32),
+    pub to: (f32, f32),
+}
+
+/// The state of a drag of the selection
+#[derive(Debug, Default)]
+pub struct DragState {
+    /// Total movement of the pointer since the drag started
+    pub offset: (f32, f32),
+    /// Total movement applied to the selection so far (the offset, adjusted by snapping)
+    pub applied: (f32, f32),
+    /// Guides for the snaps of the last movement
+    pub guides: Vec<Guide>,
+}
+
+/// A line that an edge or center of the selection can snap to
+/// `value` is its position on the snapped axis, and `span` its extent on the other axis
+#[derive(Debug, Clone, Copy)]
+struct SnapTarget {
+    value: f32,
+    span: (f32, f32),
+}
+
+impl Stage {
+    /// Find how far to move bounds (x0, y0, x1, y1, in screen space) to snap them, along with the guides to show
+    /// Each axis snaps on its own, to the closest target within the snapping threshold
+    pub fn snap_bounds(
+        &self,
+        bounds: (f32, f32, f32, f32),
+    ) -> crate::Result<((f32, f32), Vec<Guide>)> {
+        let (x0, y0, x1, y1) = bounds;
+        let root = self.base.get_root();
+        let template_bounds = self
+            .base
+            .get_item(root)
+            .ok_or(PyramusError::ItemNotFound(root))?
+            .get_bounds(&self.base)?;
+        let (tx0, ty0, tx1, ty1) = template_bounds;
+
+        let mut x_targets = vec![];
+        let mut y_targets = vec![];
+        let mut add_targets = |(x0, y0, x1, y1): (f32, f32, f32, f32)| {
+            for value in [x0, (x0 + x1) / 2.0, x1] {
+                x_targets.push(SnapTarget {
+                    value,
+                    span: (y0, y1),
+                });
+            }
+            for value in [y0, (y0 + y1) / 2.0, y1] {
+                y_targets.push(SnapTarget {
+                    value,
+                    span: (x0, x1),
+                });
+            }
+        };
+        if self.snapping.to_template {
+            add_targets(template_bounds);
+        }
+        if self.snapping.to_items {
+            // Only items with an edge or center within the threshold of the selection on one axis can be snapped to,
+            // so the spatial index is queried for a strip along each axis (across the template and the selection)
+            let threshold = self.snapping.threshold;
+            let x_strip = (x0 - threshold, y0.min(ty0), x1 + threshold, y1.max(ty1));
+            let y_strip = (x0.min(tx0), y0 - threshold, x1.max(tx1), y1 + threshold);
+            let mut candidates = self.base.get_items_in_rect(x_strip)?;
+            candidates.extend(self.base.get_items_in_rect(y_strip)?);
+
+            let mut seen = HashSet::new();
+            for id in candidates {
+                // The selection (and anything inside of it) moves, so cannot be snapped to
+                if id == root || !seen.insert(id) || self.base.is_within(id, &self.selection)? {
+                    continue;
+                }
+                let item = self
+                    .base
+                    .get_item(id)
+                    .ok_or(PyramusError::ItemNotFound(id))?;
+                if !item.is_visible(&self.base)? {
+                    continue;
+                }
+                let Some((ix0, iy0, ix1, iy1)) = self.base.get_indexed_bounds(id)? else {
+                    continue;
+                };
+                // Items with no size (such as text, which has no bounds yet) have nothing to snap to
+                if ix1 > ix0 || iy1 > iy0 {
+                    add_targets((ix0, iy0, ix1, iy1));
+                }
+            }
+        }
+
+        let grid = self.snapping.grid.filter(|size| *size > 0.0);
+        let snap_x = self.closest_snap(
+            [x0, (x0 + x1) / 2.0, x1],
+            &x_targets,
+            grid.map(|size| (tx0, size, (ty0, ty1))),
+        );
+        let snap_y = self.closest_snap(
+            [y0, (y0 + y1) / 2.0, y1],
+            &y_targets,
+            grid.map(|size| (ty0, size, (tx0, tx1))),
+        );
+
+        // Guides run between the snapped selection and what it snapped to
+        let dx = snap_x.map_or(0.0, |(delta, _)| delta);
+        let dy = snap_y.map_or(0.0, |(delta, _)| delta);
+        let mut guides = vec![];
+        if let Some((_, target)) = snap_x {
+            guides.push(Guide {
+                from: (target.value, target.span.0.min(y0 + dy)),
+                to: (target.value, target.span.1.max(y1 + dy)),
+            });
+        }
+        if let Some((_, target)) = snap_y {
+            guides.push(Guide {
+                from: (target.span.0.min(x0 + dx), target.value),
+                to: (target.span.1.max(x1 + dx), target.value),
+            });
+        }
+        Ok(((dx, dy), guides))
+    }
+
+    /// Find the closest snap of any of the `edges` of the selection on one axis, as the distance to move and its target
+    /// `grid` is the origin and size of the grid on this axis, and the span of its lines on the other axis
+    fn closest_snap(
+        &self,
+        edges: [f32; 3],
+        targets: &[SnapTarget],
+        grid: Option<(f32, f32, (f32, f32))>,
+    ) -> Option<(f32, SnapTarget)> {
+        let grid_targets = grid.into_iter().flat_map(|(origin, size, span)| {
+            edges.map(|edge| SnapTarget {
+                value: origin + ((edge - origin) / size).round() * size,
+                span,
+            })
+        });
+        targets
+            .iter()
+            .copied()
+            .chain(grid_targets)
+            .flat_map(|target| edges.map(|edge| (target.value - edge, target)))
+            .filter(|(delta, _)| delta.abs() <= self.snapping.threshold)
+            .min_by(|(a, _), (b, _)| a.abs().total_cmp(&b.abs()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        editor::base_item::Base,
+        templates::{
+            builder::ItemBuilder,
+            ids::InternalId,
+            prop::Prop,
+            shape::{PropItemShape, Shape},
+            transform::RelativeTransform,
+        },
+    };
+
+    /// A stage with a 500x500 template, snapping only as `snapping` says
+    fn build_stage(snapping: Snapping) -> Stage {
+        let base = Base::new(Prop::new(InternalId::UNCONTAINED, "Test", 500, 500).into());
+        let mut stage = Stage::new(base);
+        stage.snapping = snapping;
+        stage
+    }
+
+    /// Add a 10x10 rectangle at `position`
+    fn add_rect(stage: &mut Stage, position: (f32, f32)) -> InternalId {
+        let shape = Shape::Rectangle {
+            width: 10.0,
+            height: 10.0,
+        };
+        let builder = ItemBuilder::build_shape(PropItemShape::filled(shape, (0, 0, 0))).transform(
+            RelativeTransform {
+                position,
+                ..Default::default()
+            },
+        );
+        stage.base.add_child(builder).unwrap()
+    }
+
+    #[test]
+    fn snaps_to_grid() {
+        let stage = build_stage(Snapping {
+            grid: Some(50.0),
+            to_items: false,
+            to_template: false,
+            ..Default::default()
+        });
+        // The left edge is 2 from a grid line, and the top edge 3 (each axis snaps on its own)
+        let (delta, guides) = stage.snap_bounds((48.0, 103.0, 58.0, 113.0)).unwrap();
+        assert_eq!(delta, (2.0, -3.0));
+        assert_eq!(guides.len(), 2);
+
+        // Nothing is within the threshold of a grid line
+        let (delta, guides) = stage.snap_bounds((20.0, 120.0, 30.0, 130.0)).unwrap();
+        assert_eq!(delta, (0.0, 0.0));
+        assert!(guides.is_empty());
+    }
+
+    #[test]
+    fn snaps_to_items() {
+        let mut stage = build_stage(Snapping {
+            to_template: false,
+            ..Default::default()
+        });
+        add_rect(&mut stage, (100.0, 100.0));
+
+        // The left edge of the selection is 3 from the right edge of the item
+        let (delta, guides) = stage.snap_bounds((113.0, 300.0, 123.0, 310.0)).unwrap();
+        assert_eq!(delta, (-3.0, 0.0));
+        assert_eq!(
+            guides,
+            vec![Guide {
+                from: (110.0, 100.0),
+                to: (110.0, 310.0),
+            }]
+        );
+    }
+
+    #[test]
+    fn does_not_snap_to_selection() {
+        let mut stage = build_stage(Snapping {
+            to_template: false,
+            ..Default::default()
+        });
+        let item = add_rect(&mut stage, (100.0, 100.0));
+        stage.set_selection(vec![item]);
+
+        // The selected item moves with the drag, so it is not a target
+        let (delta, guides) = stage.snap_bounds((113.0, 300.0, 123.0, 310.0)).unwrap();
+        assert_eq!(delta, (0.0, 0.0));
+        assert!(guides.is_empty());
+    }
+}
@@ -1,5 +1,8 @@
-use std::sync::PoisonError;
+use std::{fmt, sync::PoisonError};
 
+use models::templates::ids::InternalId;
+use serde::{ser::SerializeStruct, Serialize};
+use strum_macros::IntoStaticStr;
 use thiserror::Error;
 use wasm_bindgen::JsValue;
 
@@ -9,11 +12,13 @@ pub mod input;
 pub mod logging;
 pub mod models;
 pub mod render;
-pub mod svg;
 
 pub type Result<T> = std::result::Result<T, PyramusError>;
 
-#[derive(Error, Debug)]
+/// An error in the Pyramus API.
+/// Errors are serialized to the frontend as `{ code, message, params }`, where `code` is the variant name,
+/// and `params` contains any offending IDs or values, so the frontend can display its own (localized) message.
+#[derive(Error, Debug, IntoStaticStr)]
 pub enum PyramusError {
     #[error("No runtime found!")]
     NoRuntimeFound,
@@ -27,22 +32,218 @@ pub enum PyramusError {
     #[error("Std error: {0}")]
     StdError(#[from] std::io::Error),
 
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
     #[error("Thread poison error")]
     PoisonError,
 
     #[error("JsValue error: {0}")]
     JsValue(String),
 
+    #[error("Item not found: {0:?}")]
+    ItemNotFound(InternalId),
+
+    #[error("Cannot edit or remove the root item: {0:?}")]
+    RootImmutable(InternalId),
+
+    #[error("Cycle detected in item graph at: {0:?}")]
+    CycleDetected(InternalId),
+
+    #[error("Item is listed as a child more than once: {0:?}")]
+    DuplicateChild(InternalId),
+
+    #[error("Item is not of the expected type: {0:?}")]
+    WrongItemType(InternalId),
+
+    #[error("Transform has no area and cannot be inverted, in item: {0:?}")]
+    SingularTransform(InternalId),
+
+    #[error("Path node {index} not found in item: {id:?}")]
+    PathNodeNotFound { id: InternalId, index: usize },
+
+    #[error("Transform is not finite, in item: {0:?}")]
+    NonFiniteTransform(InternalId),
+
+    #[error("Path node {index} is not finite, in item: {id:?}")]
+    NonFinitePathNode { id: InternalId, index: usize },
+
+    #[error("Invalid paint: {field} {reason}")]
+    InvalidPaint {
+        field: &'static str,
+        reason: InvalidReason,
+    },
+
+    #[error("Invalid effect: {field} {reason}")]
+    InvalidEffect {
+        field: &'static str,
+        reason: InvalidReason,
+    },
+
+    #[error("Effect {index} not found in item: {id:?}")]
+    EffectNotFound { id: InternalId, index: usize },
+
+    #[error("Invalid shape: {field} {reason}")]
+    InvalidShape {
+        field: &'static str,
+        reason: InvalidReason,
+    },
+
+    #[error("Invalid outline: {field} {reason}")]
+    InvalidOutline {
+        field: &'static str,
+        reason: InvalidReason,
+    },
+
+    #[error("Invalid image adjustment: {field} {reason}")]
+    InvalidAdjustment {
+        field: &'static str,
+        reason: InvalidReason,
+    },
+
     #[error("Invalid size: {0}, {1}")]
     InvalidSize(f32, f32),
 
+    #[error("Invalid font size: {0}")]
+    InvalidFontSize(f32),
+
+    #[error("Invalid opacity: {0}")]
+    InvalidOpacity(f32),
+
+    #[error("Unsupported image format: {0}")]
+    UnsupportedImageFormat(String),
+
     #[error("Image parsing error: {0}")]
     ImageParsingError(#[from] image::ImageError),
 
+    #[error("Version mismatch: expected at most {expected}, found {found}")]
+    VersionMismatch { expected: u32, found: u32 },
+
+    #[error("Document has {0} problems that could not be repaired")]
+    UnrepairableDocument(usize),
+
     #[error("Other error: {0}")]
     OtherError(String),
 }
 
+/// Why the `field` of an invalid paint, effect, shape, outline or image adjustment was rejected
+/// Serialized to the frontend along with the field, so it can display its own message.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum InvalidReason {
+    /// The value is NaN or infinite
+    NotFinite,
+    /// The value is less than 0 (or is not finite)
+    Negative,
+    /// The value is not greater than 0 (or is not finite)
+    NotPositive,
+    /// The value is outside of `min..=max`
+    OutOfRange { min: f32, max: f32 },
+    /// There are fewer than `min` values
+    TooFew { min: usize },
+    /// The path crosses itself
+    SelfIntersecting,
+    /// The value cannot be set this way
+    Unsupported,
+}
+
+impl fmt::Display for InvalidReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidReason::NotFinite => write!(f, "must be finite"),
+            InvalidReason::Negative => write!(f, "must not be negative"),
+            InvalidReason::NotPositive => write!(f, "must be greater than 0"),
+            InvalidReason::OutOfRange { min, max } => write!(f, "must be between {min} and {max}"),
+            InvalidReason::TooFew { min } => write!(f, "needs at least {min}"),
+            InvalidReason::SelfIntersecting => write!(f, "must not cross itself"),
+            InvalidReason::Unsupported => write!(f, "cannot be set this way"),
+        }
+    }
+}
+
+/// The parameters of a PyramusError, as serialized to the frontend
+#[derive(Serialize)]
+#[serde(untagged)]
+enum ErrorParams<'a> {
+    None,
+    Item { id: InternalId },
+    Index { id: InternalId, index: usize },
+    Size { width: f32, height: f32 },
+    FontSize { size: f32 },
+    Opacity { opacity: f32 },
+    Format { format: &'a str },
+    Invalid(InvalidField<'a>),
+    Version { expected: u32, found: u32 },
+    Count { count: usize },
+}
+
+/// The field of an invalid value, and why it was rejected
+#[derive(Serialize)]
+struct InvalidField<'a> {
+    field: &'a str,
+    reason: InvalidReason,
+}
+
+impl PyramusError {
+    /// A stable code identifying the kind of error (the variant name)
+    pub fn code(&self) -> &'static str {
+        self.into()
+    }
+
+    fn params(&self) -> ErrorParams<'_> {
+        match self {
+            PyramusError::ItemNotFound(id)
+            | PyramusError::RootImmutable(id)
+            | PyramusError::CycleDetected(id)
+            | PyramusError::DuplicateChild(id)
+            | PyramusError::WrongItemType(id)
+            | PyramusError::SingularTransform(id)
+            | PyramusError::NonFiniteTransform(id) => ErrorParams::Item { id: *id },
+            PyramusError::PathNodeNotFound { id, index }
+            | PyramusError::NonFinitePathNode { id, index }
+            | PyramusError::EffectNotFound { id, index } => ErrorParams::Index {
+                id: *id,
+                index: *index,
+            },
+            PyramusError::InvalidSize(width, height) => ErrorParams::Size {
+                width: *width,
+                height: *height,
+            },
+            PyramusError::InvalidFontSize(size) => ErrorParams::FontSize { size: *size },
+            PyramusError::InvalidOpacity(opacity) => ErrorParams::Opacity { opacity: *opacity },
+            PyramusError::UnsupportedImageFormat(format) => ErrorParams::Format { format },
+            PyramusError::InvalidPaint { field, reason }
+            | PyramusError::InvalidEffect { field, reason }
+            | PyramusError::InvalidShape { field, reason }
+            | PyramusError::InvalidOutline { field, reason }
+            | PyramusError::InvalidAdjustment { field, reason } => {
+                ErrorParams::Invalid(InvalidField {
+                    field,
+                    reason: *reason,
+                })
+            }
+            PyramusError::VersionMismatch { expected, found } => ErrorParams::Version {
+                expected: *expected,
+                found: *found,
+            },
+            PyramusError::UnrepairableDocument(count) => ErrorParams::Count { count: *count },
+            _ => ErrorParams::None,
+        }
+    }
+}
+
+impl Serialize for PyramusError {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("PyramusError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("params", &self.params())?;
+        state.end()
+    }
+}
+
 impl<T> From<PoisonError<T>> for PyramusError {
     fn from(_: PoisonError<T>) -> Self {
         PyramusError::PoisonError
@@ -54,3 +255,10 @@ impl From<JsValue> for PyramusError {
         PyramusError::JsValue(value.as_string().unwrap_or_default())
     }
 }
+
+impl From<PyramusError> for JsValue {
+    fn from(error: PyramusError) -> Self {
+        serde_wasm_bindgen::to_value(&error)
+            .unwrap_or_else(|_| JsValue::from_str(&error.to_string()))
+    }
+}
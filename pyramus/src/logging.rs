@@ -1,3 +1,4 @@
+#[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
 // TODO: Can we add tracing?
@@ -10,8 +11,15 @@ macro_rules! log {
 
 // TODO: Look into using tracing for logging on WASM
 // TODO: multiple levels of logging
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(js_namespace = console)]
     pub fn log(s: &str);
 }
+
+/// Outside of the browser (for example, in examples), log to stderr instead of the console
+#[cfg(not(target_arch = "wasm32"))]
+pub fn log(s: &str) {
+    eprintln!("{s}");
+}
@@ -1,17 +1,35 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+};
 
-use serde::{Deserialize, Serialize};
+use serde::{
+    de::{self, MapAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 
 use crate::models::templates::{
-    blueprint::Blueprint, builder::ItemBuilder, ids::InternalId, prop::Prop,
-    transform::RelativeTransform,
+    blueprint::Blueprint,
+    builder::ItemBuilder,
+    ids::{InternalId, InternalIdAllocator},
+    prop::Prop,
+    random::rng_from_seed,
+    transform::{invert_transform, RelativeTransform},
 };
 
-use super::{item::StageItem, staging::Staging};
+use super::{
+    item::StageItem, render_cache::RenderCache, spatial_index::SpatialIndex, staging::Staging,
+    validation::Diagnostic,
+};
 
 // TODO: Trait-ify this as much as you can. Currently BaseItem each variant has a BaseTemplate
 // TODO: Revisit how these fields are labelled- it's a little bit confusing. (item, inner hand, prop, etc). Ideally you shouldnt have to keep saying 'inner'
 
+/// The current version of the Base document format.
+/// Documents saved with a newer version cannot be loaded, and older ones are migrated (see `Base::migrate`).
+/// Version 2 records the parent of each item, as well as its children.
+pub const BASE_FORMAT_VERSION: u32 = 2;
+
 /// The base item that is being edited in the editor.
 /// Each item has a BaseTemplate that contains the inner items and their relationships.
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -22,23 +40,234 @@ pub struct Base {
     pub author: String,
     pub created_at: String,
     pub updated_at: String,
+
+    /// The seed this base was randomly generated from, if any
+    /// A base with a seed can be regenerated exactly with `Base::regenerate`
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    /// The version of the document format the base was saved with
+    /// (Documents from before versioning are version 1)
+    #[serde(default = "default_format_version")]
+    pub version: u32,
+
+    /// Rendered content of unchanged items, reused between renders
+    #[serde(skip)]
+    pub render_cache: RenderCache,
+
+    /// Screen-space bounds of the items, for finding items by position
+    #[serde(skip)]
+    pub spatial_index: SpatialIndex,
+
+    /// Transform from the space of the template to the screen
+    /// This is the identity, unless the base is a nested prop being edited in place (see `Stage::enter_prop`)
+    #[serde(skip)]
+    pub view_transform: glam::Affine2,
+}
+
+fn default_format_version() -> u32 {
+    1
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum BaseItem {
     Prop(Prop),
-    Blueprint(Blueprint)
+    Blueprint(Blueprint),
 }
 
 /// The base template that contains the items that make up an object.
 /// Used for an item with an internal structure of items.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BaseTemplate {
+    #[serde(
+        serialize_with = "serialize_sorted",
+        deserialize_with = "deserialize_unique"
+    )]
     pub items: HashMap<InternalId, StageItem>,
 
     // TODO: Should this crop to the total bounds of the items?
     pub size: (u32, u32),
     pub root: InternalId,
+
+    /// Allocator for the IDs of items in this template
+    #[serde(default)]
+    pub id_allocator: InternalIdAllocator,
+}
+
+/// A problem with the InternalIds of a BaseTemplate (see `BaseTemplate::validate_ids`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdIssue {
+    /// An item is stored under a key that does not match its own ID
+    MismatchedKey { key: InternalId, id: InternalId },
+    /// An item is listed as a child more than once (by the same or by different parents)
+    DuplicateChild { id: InternalId },
+    /// An ID is referenced, but no item exists for it
+    /// `referenced_by` is the item referencing it (None if it is the template root)
+    Dangling {
+        id: InternalId,
+        referenced_by: Option<InternalId>,
+    },
+}
+
+impl BaseTemplate {
+    /// Create a new template of a given size, containing only a root item
+    pub fn new(root: ItemBuilder, size: (u32, u32)) -> crate::Result<BaseTemplate> {
+        let mut template = BaseTemplate {
+            items: HashMap::new(),
+            size,
+            root: InternalId::UNCONTAINED,
+            id_allocator: InternalIdAllocator::new(),
+        };
+        let root_id = template.allocate_id();
+        template.items.insert(root_id, root.build(root_id)?);
+        template.root = root_id;
+        Ok(template)
+    }
+
+    /// Allocate a new ID that is not used by any item in this template
+    pub fn allocate_id(&mut self) -> InternalId {
+        let items = &self.items;
+        self.id_allocator.allocate(|id| items.contains_key(&id))
+    }
+
+    /// Check the IDs of the template for duplicate children and dangling references (e.g. after loading a document)
+    /// (Items with the same ID cannot be loaded at all- see `deserialize_unique`)
+    /// Returns an empty list if no issues are found
+    pub fn validate_ids(&self) -> Vec<IdIssue> {
+        let mut issues = Vec::new();
+        if !self.items.contains_key(&self.root) {
+            issues.push(IdIssue::Dangling {
+                id: self.root,
+                referenced_by: None,
+            });
+        }
+
+        // Sorted, so issues are reported in a consistent order
+        let mut keys = self.items.keys().copied().collect::<Vec<_>>();
+        keys.sort();
+
+        let mut seen_children = HashSet::new();
+        for key in keys {
+            let item = &self.items[&key];
+            if item.get_id() != key {
+                issues.push(IdIssue::MismatchedKey {
+                    key,
+                    id: item.get_id(),
+                });
+            }
+
+            let references = item
+                .get_parent()
+                .into_iter()
+                .chain(item.get_children().iter().copied());
+            for id in references {
+                if !self.items.contains_key(&id) {
+                    issues.push(IdIssue::Dangling {
+                        id,
+                        referenced_by: Some(key),
+                    });
+                }
+            }
+
+            for child in item.get_children() {
+                if !seen_children.insert(*child) {
+                    issues.push(IdIssue::DuplicateChild { id: *child });
+                }
+            }
+        }
+        issues
+    }
+
+    /// Import the subtree of `source` starting at `source_root`, as a child of `parent` in this template.
+    /// Every imported item is given a new ID from this template's allocator, and the parent/child references within the subtree are remapped.
+    /// Returns the mapping from the IDs in `source` to the new IDs in this template.
+    /// If an item of the subtree is missing, is listed as a child more than once, or is within itself, nothing is imported.
+    pub fn import_subtree(
+        &mut self,
+        source: &BaseTemplate,
+        source_root: InternalId,
+        parent: InternalId,
+    ) -> crate::Result<HashMap<InternalId, InternalId>> {
+        if !self.items.contains_key(&parent) {
+            return Err(crate::PyramusError::ItemNotFound(parent));
+        }
+
+        // Collect and check the whole subtree before changing anything, so a failed import leaves this template as it was
+        let mut subtree = vec![];
+        collect_subtree(
+            source,
+            source_root,
+            &mut HashSet::new(),
+            &mut HashSet::new(),
+            &mut subtree,
+        )?;
+
+        // Allocate a new ID for each item (the allocator never hands out the same ID twice)
+        let mut mapping = HashMap::new();
+        for &id in &subtree {
+            let new_id = self.allocate_id();
+            mapping.insert(id, new_id);
+        }
+        for &id in &subtree {
+            self.items.insert(mapping[&id], source.items[&id].clone());
+        }
+
+        // Remap the references of the imported items
+        // Any references to items outside of the subtree are dropped
+        for (old_id, new_id) in &mapping {
+            let item = self
+                .items
+                .get_mut(new_id)
+                .expect("Imported item was just inserted");
+            item.set_id(*new_id);
+            let new_parent = if *old_id == source_root {
+                Some(parent)
+            } else {
+                item.get_parent().and_then(|p| mapping.get(&p).copied())
+            };
+            item.set_parent(new_parent);
+            let children = item.get_children_mut();
+            *children = children
+                .iter()
+                .filter_map(|c| mapping.get(c).copied())
+                .collect();
+        }
+
+        let new_root = mapping[&source_root];
+        if let Some(parent) = self.items.get_mut(&parent) {
+            parent.get_children_mut().push(new_root);
+        }
+        Ok(mapping)
+    }
+}
+
+/// Collect `id` and its descendants into `subtree`, parents before their children
+/// An item within itself (one of its `ancestors`) is a cycle, while any other item `seen` before is listed twice.
+fn collect_subtree(
+    source: &BaseTemplate,
+    id: InternalId,
+    ancestors: &mut HashSet<InternalId>,
+    seen: &mut HashSet<InternalId>,
+    subtree: &mut Vec<InternalId>,
+) -> crate::Result<()> {
+    if ancestors.contains(&id) {
+        return Err(crate::PyramusError::CycleDetected(id));
+    }
+    if !seen.insert(id) {
+        return Err(crate::PyramusError::DuplicateChild(id));
+    }
+    let item = source
+        .items
+        .get(&id)
+        .ok_or(crate::PyramusError::ItemNotFound(id))?;
+    subtree.push(id);
+
+    ancestors.insert(id);
+    for &child in item.get_children() {
+        collect_subtree(source, child, ancestors, seen, subtree)?;
+    }
+    ancestors.remove(&id);
+    Ok(())
 }
 
 impl Base {
@@ -51,7 +280,77 @@ impl Base {
             author: "Unknown".to_string(),
             created_at: "Unknown".to_string(),
             updated_at: "Unknown".to_string(),
+
+            seed: None,
+            version: BASE_FORMAT_VERSION,
+            render_cache: RenderCache::default(),
+            spatial_index: SpatialIndex::default(),
+            view_transform: glam::Affine2::IDENTITY,
+        }
+    }
+
+    /// Check that the base was saved with a document format version that can be loaded
+    pub fn check_version(&self) -> crate::Result<()> {
+        if self.version > BASE_FORMAT_VERSION {
+            return Err(crate::PyramusError::VersionMismatch {
+                expected: BASE_FORMAT_VERSION,
+                found: self.version,
+            });
         }
+        Ok(())
+    }
+
+    /// Load a saved base, migrating it to the current format and repairing any problems found
+    /// Fails if the base is from a newer version, or has problems that cannot be repaired
+    /// Returns the base, along with the problems that were repaired
+    pub fn load(json: &str) -> crate::Result<(Base, Vec<Diagnostic>)> {
+        let mut base: Base = serde_json::from_str(json)?;
+        base.check_version()?;
+        base.migrate();
+        let report = base.validate_and_repair();
+        if !report.remaining.is_empty() {
+            return Err(crate::PyramusError::UnrepairableDocument(
+                report.remaining.len(),
+            ));
+        }
+        Ok((base, report.found))
+    }
+
+    /// Generate a new base containing a random prop, recording the seed it was generated from.
+    pub fn build_random_prop(name: impl ToString, width: u32, height: u32, seed: u64) -> Self {
+        let prop = Prop::build_random(
+            InternalId::UNCONTAINED,
+            name,
+            width,
+            height,
+            &mut rng_from_seed(seed),
+        );
+        Self {
+            seed: Some(seed),
+            ..Self::new(prop.into())
+        }
+    }
+
+    /// Generate a new base containing a random blueprint, recording the seed it was generated from.
+    pub fn build_random_blueprint(name: impl ToString, width: u32, height: u32, seed: u64) -> Self {
+        let blueprint = Blueprint::build_random(name, width, height, &mut rng_from_seed(seed));
+        Self {
+            seed: Some(seed),
+            ..Self::new(blueprint.into())
+        }
+    }
+
+    /// Regenerate this base from its recorded seed, with the same name and size.
+    /// None if the base was not randomly generated.
+    pub fn regenerate(&self) -> Option<Self> {
+        let seed = self.seed?;
+        let (width, height) = self.get_size();
+        Some(match &self.item {
+            BaseItem::Prop(prop) => Self::build_random_prop(&prop.name, width, height, seed),
+            BaseItem::Blueprint(blueprint) => {
+                Self::build_random_blueprint(&blueprint.name, width, height, seed)
+            }
+        })
     }
 }
 
@@ -60,7 +359,17 @@ impl Base {
     // A lot of stuff here can be traited- even if the whole thing can't be (due to issues with BackendCommand)
 
     /// Get the inner BaseTemplate field of the item, mutably.
+    /// Any item may be changed through it, so this drops everything in the render cache and spatial index.
+    /// It is meant for rewriting a whole document (such as when it is migrated or repaired)- edits to items
+    /// should use the functions editing a single item, which only invalidate what they change.
     pub fn get_template_mut(&mut self) -> &mut BaseTemplate {
+        self.render_cache.clear();
+        self.spatial_index.clear();
+        self.template_mut()
+    }
+
+    // The template, without touching the render cache or spatial index (callers invalidate what they change)
+    fn template_mut(&mut self) -> &mut BaseTemplate {
         match &mut self.item {
             BaseItem::Prop(prop) => &mut prop.template,
             BaseItem::Blueprint(blueprint) => &mut blueprint.template,
@@ -78,24 +387,29 @@ impl Base {
     // TODO: This pattern could be improved (taking in an Rc<RwLock> parent, rather than a reference to self)
     /// Add a child item to the inner structure of the item.
     pub fn add_child(&mut self, item_builder: ItemBuilder) -> crate::Result<InternalId> {
-        let template = self.get_template_mut();
+        let template = self.template_mut();
 
         // TODO: Revisit this function after blueprint refactor- move to prop?
         let parent = item_builder.parent.unwrap_or(template.root);
 
-        // Check parent exists and add child
-        let parent = template
-            .items
-            .get_mut(&parent)
-            .ok_or_else(|| crate::PyramusError::OtherError("Parent not found".to_string()))?;
+        // Check parent exists
+        if !template.items.contains_key(&parent) {
+            return Err(crate::PyramusError::ItemNotFound(parent));
+        }
 
         // Build and insert item
-        let item = item_builder.build()?;
-        let id = item.get_id();
+        let id = template.allocate_id();
+        self.spatial_index.mark_changed(id);
+        let template = self.template_mut();
+        let mut item = item_builder.build(id)?;
+        item.set_parent(Some(parent));
 
         // Insert parent and child
-        parent.get_children_mut().push(id);
+        if let Some(parent) = template.items.get_mut(&parent) {
+            parent.get_children_mut().push(id);
+        }
         template.items.insert(id, item);
+        self.invalidate_staged(id);
 
         Ok(id)
     }
@@ -107,14 +421,18 @@ impl Base {
         f: impl FnOnce(&mut StageItem) -> crate::Result<()>,
     ) -> crate::Result<()> {
         // TODO: Revisit this function after blueprint refactor- move to prop?
-        let template = self.get_template_mut();
-        if let Some(item) = template.items.get_mut(&id) {
+        self.render_cache.invalidate(id);
+        self.invalidate_staged(id);
+        self.spatial_index.mark_changed(id);
+        let template = self.template_mut();
+        let result = if let Some(item) = template.items.get_mut(&id) {
             f(item)
         } else {
-            Err(crate::PyramusError::OtherError(
-                "Item not found".to_string(),
-            ))
-        }
+            Err(crate::PyramusError::ItemNotFound(id))
+        };
+        // The edit may have given the item a new parent
+        self.invalidate_staged(id);
+        result
     }
 
     /// Get an inner item in the inner structure of the base item.
@@ -143,33 +461,34 @@ impl Base {
         id: InternalId,
         f: impl FnOnce(&mut RelativeTransform) -> crate::Result<()>,
     ) -> crate::Result<()> {
-        let template = self.get_template_mut();
+        // Transforms are applied around the cached content, so only the staged nodes are rebuilt
+        self.invalidate_staged(id);
+        self.spatial_index.mark_moved(id);
+        let template = self.template_mut();
         // TODO: Revisit this function after blueprint refactor- move to prop?
         // Cannot edit the root item
         if id == template.root {
-            return Err(crate::PyramusError::OtherError(
-                "Cannot edit the root item".to_string(),
-            ));
+            return Err(crate::PyramusError::RootImmutable(id));
         }
 
         if let Some(item) = template.items.get_mut(&id) {
             f(item.get_relative_transform_mut())
         } else {
-            Err(crate::PyramusError::OtherError(
-                "Item not found".to_string(),
-            ))
+            Err(crate::PyramusError::ItemNotFound(id))
         }
     }
 
     /// Remove an inner item in the inner structure of the base item.
     pub fn remove_item(&mut self, id: InternalId) -> crate::Result<()> {
-        let template = self.get_template_mut();
+        // Re-parented children are drawn by the root, which is invalidated along with the parents of the item
+        self.invalidate_staged(id);
+        self.render_cache.invalidate(id);
+        self.spatial_index.mark_changed(id);
+        let template = self.template_mut();
         // TODO: Revisit this function after blueprint refactor- move to prop?
         // Cannot remove the root item
         if id == template.root {
-            return Err(crate::PyramusError::OtherError(
-                "Cannot remove the root item".to_string(),
-            ));
+            return Err(crate::PyramusError::RootImmutable(id));
         }
 
         let root = template.root;
@@ -183,22 +502,220 @@ impl Base {
         if let Some(parent) = parent.and_then(|parent| template.items.get_mut(&parent)) {
             parent.get_children_mut().retain(|child| *child != id);
         }
+        // A group is as large as its children
+        let parent_is_group = parent
+            .and_then(|parent| template.items.get(&parent))
+            .is_some_and(|parent| matches!(parent, StageItem::Group(_)));
 
         // Children should be kept and re-parented to the root
         // TODO: make this optional
         // If we reparent to the root, we should also calculate the new relative transform to keep the same position
-        for child in children {
-            let Some(child) = template.items.get_mut(&child) else {
+        for &child_id in &children {
+            let Some(child) = template.items.get_mut(&child_id) else {
                 continue;
             };
             child.set_parent(Some(root));
+            if let Some(root) = template.items.get_mut(&root) {
+                root.get_children_mut().push(child_id);
+            }
         }
         template.items.remove(&id);
 
+        // Re-parented children are now positioned relative to the root
+        for child_id in children {
+            self.spatial_index.mark_moved(child_id);
+        }
+        if let Some(parent) = parent.filter(|_| parent_is_group) {
+            self.spatial_index.mark_moved(parent);
+        }
+
+        Ok(())
+    }
+
+    /// Move an item to a new parent, at `index` in its children (or at the end, if None),
+    /// changing its relative transform so it stays where it is on screen
+    pub fn move_item(
+        &mut self,
+        id: InternalId,
+        parent: InternalId,
+        index: Option<usize>,
+    ) -> crate::Result<()> {
+        let item = self
+            .get_item(id)
+            .ok_or(crate::PyramusError::ItemNotFound(id))?;
+        if id == self.get_root() {
+            return Err(crate::PyramusError::RootImmutable(id));
+        }
+        // An item cannot be moved into itself
+        if self.is_within(parent, &[id])? {
+            return Err(crate::PyramusError::CycleDetected(id));
+        }
+        let old_parent = item.get_parent();
+        let screen_transform = item.get_screen_transform(self)?;
+        let parent_transform = self
+            .get_item(parent)
+            .ok_or(crate::PyramusError::ItemNotFound(parent))?
+            .get_screen_transform(self)?;
+        // A parent scaled to nothing has no space to take the item into
+        let to_parent = invert_transform(parent_transform, parent)?;
+        let transform = RelativeTransform::from_glam_affine(to_parent * screen_transform);
+
+        self.invalidate_staged(id);
+        self.spatial_index.mark_changed(id);
+        let template = self.template_mut();
+        if let Some(old_parent) =
+            old_parent.and_then(|old_parent| template.items.get_mut(&old_parent))
+        {
+            old_parent.get_children_mut().retain(|child| *child != id);
+        }
+        if let Some(parent) = template.items.get_mut(&parent) {
+            let children = parent.get_children_mut();
+            let index = index.unwrap_or(children.len()).min(children.len());
+            children.insert(index, id);
+        }
+        if let Some(item) = template.items.get_mut(&id) {
+            item.set_parent(Some(parent));
+            *item.get_relative_transform_mut() = transform;
+        }
+        self.invalidate_staged(id);
+        Ok(())
+    }
+
+    // Drop the staged nodes of an item and its parents up to the root, which all draw the item
+    fn invalidate_staged(&mut self, id: InternalId) {
+        let mut visited = HashSet::new();
+        let mut current = Some(id);
+        while let Some(id) = current.filter(|id| visited.insert(*id)) {
+            self.render_cache.invalidate_staged(id);
+            current = self.get_item(id).and_then(|item| item.get_parent());
+        }
+    }
+
+    /// Check if an item, or one of its parents, is one of `ids`
+    pub fn is_within(&self, id: InternalId, ids: &[InternalId]) -> crate::Result<bool> {
+        let mut visited = vec![];
+        let mut current = Some(id);
+        while let Some(id) = current {
+            if ids.contains(&id) {
+                return Ok(true);
+            }
+            if visited.contains(&id) {
+                return Err(crate::PyramusError::CycleDetected(id));
+            }
+            visited.push(id);
+            current = self
+                .get_item(id)
+                .ok_or(crate::PyramusError::ItemNotFound(id))?
+                .get_parent();
+        }
+        Ok(false)
+    }
+
+    /// Get an item and all of its descendants, with parents before their children
+    pub fn get_subtree(&self, id: InternalId) -> crate::Result<Vec<InternalId>> {
+        let mut subtree = vec![];
+        let mut pending = vec![id];
+        while let Some(id) = pending.pop() {
+            if subtree.contains(&id) {
+                return Err(crate::PyramusError::CycleDetected(id));
+            }
+            subtree.push(id);
+            let item = self
+                .get_item(id)
+                .ok_or(crate::PyramusError::ItemNotFound(id))?;
+            pending.extend(item.get_children().iter().rev());
+        }
+        Ok(subtree)
+    }
+
+    /// Get the items whose screen-space bounds contain a point, from front to back
+    pub fn get_items_at(&self, x: f32, y: f32) -> crate::Result<Vec<InternalId>> {
+        self.spatial_index.query_point(self, x, y)
+    }
+
+    /// Get the items whose screen-space bounds overlap a rectangle (x0, y0, x1, y1), from back to front
+    pub fn get_items_in_rect(&self, rect: (f32, f32, f32, f32)) -> crate::Result<Vec<InternalId>> {
+        self.spatial_index.query_rect(self, rect)
+    }
+
+    /// Get the screen-space bounds of an item, as kept by the spatial index (None if it is not rendered)
+    pub fn get_indexed_bounds(
+        &self,
+        id: InternalId,
+    ) -> crate::Result<Option<(f32, f32, f32, f32)>> {
+        self.spatial_index.get_bounds(self, id)
+    }
+
+    /// Get the render order of the items, starting from the root (later items are drawn on top)
+    pub fn get_render_order(&self) -> crate::Result<Vec<InternalId>> {
+        // TODO: We need to add Z-index (render order) support, which will affect how this selects items
+        // Currently, this just uses the children order (last child is on top), which should be used as a tiebreaker
+        let mut render_order = Vec::new();
+        self.get_render_order_recursive(self.get_root(), &mut render_order, &mut HashSet::new())?;
+        Ok(render_order)
+    }
+
+    fn get_render_order_recursive(
+        &self,
+        item_id: InternalId,
+        render_order: &mut Vec<InternalId>,
+        visited: &mut HashSet<InternalId>,
+    ) -> crate::Result<()> {
+        // An item reached twice means the graph loops back on itself
+        // (or an item is listed by two parents, which would also render it twice)
+        if !visited.insert(item_id) {
+            return Err(crate::PyramusError::CycleDetected(item_id));
+        }
+
+        let item = self
+            .get_item(item_id)
+            .ok_or(crate::PyramusError::ItemNotFound(item_id))?;
+        render_order.push(item_id);
+        for child in item.get_children() {
+            self.get_render_order_recursive(*child, render_order, visited)?;
+        }
         Ok(())
     }
 }
 
+/// Serialize items in the order of their IDs, so the same template is always saved the same way
+fn serialize_sorted<S: Serializer>(
+    items: &HashMap<InternalId, StageItem>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    items
+        .iter()
+        .collect::<BTreeMap<_, _>>()
+        .serialize(serializer)
+}
+
+/// Deserialize items, rejecting any ID given to more than one item
+/// (Collecting straight into a map would silently keep only the last of them)
+fn deserialize_unique<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<InternalId, StageItem>, D::Error> {
+    struct UniqueItems;
+
+    impl<'de> Visitor<'de> for UniqueItems {
+        type Value = HashMap<InternalId, StageItem>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a map of items by their IDs")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut items = HashMap::with_capacity(map.size_hint().unwrap_or(0));
+            while let Some((id, item)) = map.next_entry::<InternalId, StageItem>()? {
+                if items.insert(id, item).is_some() {
+                    return Err(de::Error::custom(format!("duplicate item ID {}", id.0)));
+                }
+            }
+            Ok(items)
+        }
+    }
+
+    deserializer.deserialize_map(UniqueItems)
+}
 
 impl From<Prop> for BaseItem {
     fn from(prop: Prop) -> Self {
@@ -210,4 +727,4 @@ impl From<Blueprint> for BaseItem {
     fn from(blueprint: Blueprint) -> Self {
         BaseItem::Blueprint(blueprint)
     }
-}
\ No newline at end of file
+}
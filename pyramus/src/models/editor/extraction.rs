@@ -0,0 +1,108 @@
+use super::{
+    item::{bounds_of_points, StageItem},
+    stage::Stage,
+    staging::Staging,
+};
+use crate::{
+    models::templates::{
+        builder::ItemBuilder,
+        ids::InternalId,
+        prop::Prop,
+        transform::{invert_transform, RelativeTransform},
+    },
+    PyramusError,
+};
+
+impl Stage {
+    /// Replace items with an instance of a new prop made from them, and select the instance
+    /// The prop is sized to the bounds of the items, and the instance is placed where they were, so nothing moves on screen.
+    /// Returns the instance, and the prop it is an instance of (to be reused elsewhere), or None if there were no items
+    pub fn extract_prop(
+        &mut self,
+        item_ids: &[InternalId],
+        name: impl ToString,
+    ) -> crate::Result<Option<(InternalId, Prop)>> {
+        // Check that all of the items can be copied before grouping changes the stage
+        for &item_id in item_ids {
+            self.base.get_subtree(item_id)?;
+        }
+
+        // Grouping collects the items in order, in a space with no rotation or scale relative to their parent
+        let Some(group_id) = self.group_items(item_ids)? else {
+            return Ok(None);
+        };
+        let group = self
+            .base
+            .get_item(group_id)
+            .ok_or(PyramusError::ItemNotFound(group_id))?;
+        let parent_id = group
+            .get_parent()
+            .ok_or(PyramusError::RootImmutable(group_id))?;
+        let group_transform = group.get_relative_transform().clone();
+        let children = group.get_children().clone();
+
+        let subtree = self.base.get_subtree(group_id)?;
+
+        // The prop is sized to the bounds of the shown items, in the space of the group
+        let to_group = invert_transform(group.get_screen_transform(&self.base)?, group_id)?;
+        let mut corners = vec![];
+        for &id in &subtree[1..] {
+            let item = self
+                .base
+                .get_item(id)
+                .ok_or(PyramusError::ItemNotFound(id))?;
+            if matches!(item, StageItem::Group(_)) || !item.is_visible(&self.base)? {
+                continue;
+            }
+            let oriented_bounds = item.get_oriented_bounds(&self.base)?;
+            corners.extend(oriented_bounds.map(|corner| to_group.transform_point2(corner)));
+        }
+        let (x0, y0, x1, y1) = match corners.is_empty() {
+            true => (0.0, 0.0, 0.0, 0.0),
+            false => bounds_of_points(&corners),
+        };
+        let width = ((x1 - x0).ceil() as u32).max(1);
+        let height = ((y1 - y0).ceil() as u32).max(1);
+
+        // Copy the items into the template of the prop, moved so the top-left of their bounds is its origin
+        let mut prop = Prop::new(InternalId::UNCONTAINED, name, width, height);
+        let root = prop.template.root;
+        for child_id in children {
+            let mapping = prop
+                .template
+                .import_subtree(self.base.get_template(), child_id, root)?;
+            if let Some(item) = prop.template.items.get_mut(&mapping[&child_id]) {
+                let transform = item.get_relative_transform_mut();
+                transform.position.0 -= x0;
+                transform.position.1 -= y0;
+            }
+        }
+
+        // Place the instance where the group is, then remove the group and its items (children first)
+        let index = self
+            .base
+            .get_item(parent_id)
+            .ok_or(PyramusError::ItemNotFound(parent_id))?
+            .get_children()
+            .iter()
+            .position(|id| *id == group_id);
+        let instance_id = self.base.add_child(
+            ItemBuilder::build_prop_instance(prop.clone())
+                .parent(parent_id)
+                .transform(RelativeTransform {
+                    position: (
+                        group_transform.position.0 + x0,
+                        group_transform.position.1 + y0,
+                    ),
+                    ..group_transform
+                }),
+        )?;
+        self.base.move_item(instance_id, parent_id, index)?;
+        for &id in subtree.iter().rev() {
+            self.base.remove_item(id)?;
+        }
+
+        self.set_selection(vec![instance_id]);
+        Ok(Some((instance_id, prop)))
+    }
+}
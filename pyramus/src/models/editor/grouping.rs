@@ -0,0 +1,203 @@
+use super::{item::StageItem, stage::Stage, staging::Staging};
+use crate::{
+    models::templates::{
+        builder::ItemBuilder,
+        ids::InternalId,
+        transform::{invert_transform, RelativeTransform},
+    },
+    PyramusError,
+};
+use glam::Vec2;
+
+impl Stage {
+    /// Put items into a new group, and select it
+    /// The group is placed where the front-most of the items was, with its origin at the top-left of their bounds.
+    /// Items keep their place on screen, and items inside of another of the items stay inside of it.
+    /// Returns the new group, or None if there were no items to group
+    pub fn group_items(&mut self, item_ids: &[InternalId]) -> crate::Result<Option<InternalId>> {
+        let root = self.base.get_root();
+        let render_order = self.base.get_render_order()?;
+
+        let mut items = vec![];
+        for &item_id in item_ids {
+            let item = self
+                .base
+                .get_item(item_id)
+                .ok_or(PyramusError::ItemNotFound(item_id))?;
+            if item_id == root {
+                return Err(PyramusError::RootImmutable(item_id));
+            }
+            let inside_other = match item.get_parent() {
+                Some(parent_id) => self.base.is_within(parent_id, item_ids)?,
+                None => false,
+            };
+            if !inside_other && !items.contains(&item_id) {
+                items.push(item_id);
+            }
+        }
+        if items.is_empty() {
+            return Ok(None);
+        }
+        // Items keep their order (from back to front) within the group
+        items.sort_by_key(|id| render_order.iter().position(|other| other == id));
+
+        // Items with the same parent are grouped within it, otherwise the group is placed on the root
+        let parents: Vec<Option<InternalId>> = items
+            .iter()
+            .map(|id| self.base.get_item(*id).and_then(|item| item.get_parent()))
+            .collect();
+        let parent_id = match parents[0] {
+            Some(parent_id) if parents.iter().all(|parent| *parent == Some(parent_id)) => parent_id,
+            _ => root,
+        };
+        let parent = self
+            .base
+            .get_item(parent_id)
+            .ok_or(PyramusError::ItemNotFound(parent_id))?;
+
+        // The group takes the place of the front-most item, so goes before the next sibling that is not grouped
+        let siblings = parent.get_children();
+        let front_index = siblings.iter().rposition(|id| items.contains(id));
+        let next_sibling = front_index.and_then(|index| {
+            siblings[index + 1..]
+                .iter()
+                .find(|id| !items.contains(id))
+                .copied()
+        });
+
+        // The origin of the group is the top-left of the bounds of the items, in the space of the parent
+        let mut origin = Vec2::splat(f32::MAX);
+        for &item_id in &items {
+            let item = self
+                .base
+                .get_item(item_id)
+                .ok_or(PyramusError::ItemNotFound(item_id))?;
+            if let Some((x0, y0, _, _)) = item.get_subtree_bounds(&self.base)? {
+                origin = origin.min(Vec2::new(x0, y0));
+            }
+        }
+        if origin.x == f32::MAX {
+            origin = Vec2::ZERO;
+        }
+        let origin = invert_transform(parent.get_screen_transform(&self.base)?, parent_id)?
+            .transform_point2(origin);
+
+        let group = ItemBuilder::build_group()
+            .parent(parent_id)
+            .transform(RelativeTransform {
+                position: (origin.x, origin.y),
+                ..Default::default()
+            });
+        let group_id = self.base.add_child(group)?;
+        for &item_id in &items {
+            self.base.move_item(item_id, group_id, None)?;
+        }
+
+        let index = self
+            .base
+            .get_item(parent_id)
+            .ok_or(PyramusError::ItemNotFound(parent_id))?
+            .get_children()
+            .iter()
+            .position(|id| Some(*id) == next_sibling);
+        self.base.move_item(group_id, parent_id, index)?;
+
+        self.set_selection(vec![group_id]);
+        Ok(Some(group_id))
+    }
+
+    /// Replace groups with their items, and select the items
+    /// Items take the place of their group in its parent, and keep their place on screen
+    /// Returns the items that were in the groups
+    pub fn ungroup_items(&mut self, group_ids: &[InternalId]) -> crate::Result<Vec<InternalId>> {
+        let mut ungrouped = vec![];
+        for &group_id in group_ids {
+            let group = self
+                .base
+                .get_item(group_id)
+                .ok_or(PyramusError::ItemNotFound(group_id))?;
+            let StageItem::Group(_) = group else {
+                return Err(PyramusError::WrongItemType(group_id));
+            };
+            let children = group.get_children().clone();
+            let parent_id = group
+                .get_parent()
+                .ok_or(PyramusError::RootImmutable(group_id))?;
+            let index = self
+                .base
+                .get_item(parent_id)
+                .ok_or(PyramusError::ItemNotFound(parent_id))?
+                .get_children()
+                .iter()
+                .position(|id| *id == group_id);
+
+            for (offset, &child_id) in children.iter().enumerate() {
+                self.base
+                    .move_item(child_id, parent_id, index.map(|index| index + offset))?;
+            }
+            self.base.remove_item(group_id)?;
+
+            // Leave the group if it was being edited
+            if self.entered_group == Some(group_id) {
+                self.entered_group = self.get_enclosing_group(parent_id)?;
+            }
+            ungrouped.extend(children);
+        }
+
+        self.set_selection(ungrouped.clone());
+        Ok(ungrouped)
+    }
+
+    /// Edit the items of a group on their own, rather than selecting the group as one
+    pub fn enter_group(&mut self, group_id: InternalId) -> crate::Result<()> {
+        let group = self
+            .base
+            .get_item(group_id)
+            .ok_or(PyramusError::ItemNotFound(group_id))?;
+        let StageItem::Group(_) = group else {
+            return Err(PyramusError::WrongItemType(group_id));
+        };
+        self.entered_group = Some(group_id);
+        self.set_selection(vec![]);
+        Ok(())
+    }
+
+    /// Go back up from editing a group to the group it is in (or the top level), and select the group that was left
+    pub fn exit_group(&mut self) -> crate::Result<()> {
+        let Some(group_id) = self.entered_group else {
+            return Ok(());
+        };
+        let parent = self
+            .base
+            .get_item(group_id)
+            .ok_or(PyramusError::ItemNotFound(group_id))?
+            .get_parent();
+        self.entered_group = match parent {
+            Some(parent_id) => self.get_enclosing_group(parent_id)?,
+            None => None,
+        };
+        self.set_selection(vec![group_id]);
+        Ok(())
+    }
+
+    /// Get the closest group containing an item (or the item itself, if it is a group)
+    fn get_enclosing_group(&self, item_id: InternalId) -> crate::Result<Option<InternalId>> {
+        let mut visited = vec![];
+        let mut current = Some(item_id);
+        while let Some(id) = current {
+            if visited.contains(&id) {
+                return Err(PyramusError::CycleDetected(id));
+            }
+            visited.push(id);
+            let item = self
+                .base
+                .get_item(id)
+                .ok_or(PyramusError::ItemNotFound(id))?;
+            if let StageItem::Group(_) = item {
+                return Ok(Some(id));
+            }
+            current = item.get_parent();
+        }
+        Ok(None)
+    }
+}
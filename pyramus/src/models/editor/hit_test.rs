@@ -0,0 +1,144 @@
+use std::io::Cursor;
+
+use image::{io::Reader, GenericImageView};
+use resvg::usvg::{self, tiny_skia_path::Point};
+
+use crate::models::templates::path::winding_number;
+
+/// Check if a point (in the space of the node) is on something drawn by the node:
+/// the fill or stroke of a path, or a pixel of a raster image more opaque than `alpha_threshold`
+pub fn node_contains_point(node: &usvg::Node, point: Point, alpha_threshold: u8) -> bool {
+    match node {
+        usvg::Node::Group(group) => group_contains_point(group, point, alpha_threshold),
+        usvg::Node::Path(path) => path_contains_point(path, point),
+        usvg::Node::Image(image) => image_contains_point(image, point, alpha_threshold),
+        // Text is drawn through its flattened paths (text without them draws nothing)
+        usvg::Node::Text(text) => text
+            .flattened
+            .as_ref()
+            .is_some_and(|group| group_contains_point(group, point, alpha_threshold)),
+    }
+}
+
+fn group_contains_point(group: &usvg::Group, point: Point, alpha_threshold: u8) -> bool {
+    let Some(point) = inverse_map(group.transform, point) else {
+        return false;
+    };
+
+    // Anything outside of the clip path is not drawn
+    if let Some(clip_path) = &group.clip_path {
+        let clip_path = clip_path.borrow();
+        let inside = inverse_map(clip_path.transform, point)
+            .is_some_and(|point| group_contains_point(&clip_path.root, point, 0));
+        if !inside {
+            return false;
+        }
+    }
+
+    group
+        .children
+        .iter()
+        .any(|child| node_contains_point(child, point, alpha_threshold))
+}
+
+fn path_contains_point(path: &usvg::Path, point: Point) -> bool {
+    if path.visibility != usvg::Visibility::Visible {
+        return false;
+    }
+
+    let in_fill = path.fill.as_ref().is_some_and(|fill| {
+        let winding = winding_number(&path.data, point.x, point.y);
+        match fill.rule {
+            usvg::FillRule::NonZero => winding != 0,
+            usvg::FillRule::EvenOdd => winding % 2 != 0,
+        }
+    });
+
+    // The outline of a stroke is filled with the nonzero rule
+    in_fill
+        || path.stroke.as_ref().is_some_and(|stroke| {
+            path.data
+                .stroke(&stroke.to_tiny_skia(), 1.0)
+                .is_some_and(|outline| winding_number(&outline, point.x, point.y) != 0)
+        })
+}
+
+fn image_contains_point(image: &usvg::Image, point: Point, alpha_threshold: u8) -> bool {
+    if image.visibility != usvg::Visibility::Visible {
+        return false;
+    }
+
+    match &image.kind {
+        usvg::ImageKind::SVG(tree) => {
+            let Some((x, y)) = to_image_space(&image.view_box, tree.size, point) else {
+                return false;
+            };
+            let view_box = usvg::utils::view_box_to_transform(
+                tree.view_box.rect,
+                tree.view_box.aspect,
+                tree.size,
+            );
+            inverse_map(view_box, Point::from_xy(x, y))
+                .is_some_and(|point| group_contains_point(&tree.root, point, alpha_threshold))
+        }
+        usvg::ImageKind::JPEG(data) => {
+            // JPEGs have no transparency, so only the size is needed
+            raster_size(data)
+                .is_some_and(|size| to_image_space(&image.view_box, size, point).is_some())
+        }
+        usvg::ImageKind::PNG(data) | usvg::ImageKind::GIF(data) => {
+            let Some(decoded) = image::load_from_memory(data).ok() else {
+                return false;
+            };
+            let (width, height) = decoded.dimensions();
+            let Some(size) = usvg::Size::from_wh(width as f32, height as f32) else {
+                return false;
+            };
+            let Some((x, y)) = to_image_space(&image.view_box, size, point) else {
+                return false;
+            };
+            let (x, y) = ((x as u32).min(width - 1), (y as u32).min(height - 1));
+            decoded.get_pixel(x, y)[3] > alpha_threshold
+        }
+    }
+}
+
+/// The size of an encoded raster image, read from its header
+fn raster_size(data: &[u8]) -> Option<usvg::Size> {
+    let (width, height) = Reader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()?;
+    usvg::Size::from_wh(width as f32, height as f32)
+}
+
+/// Map a point in the view box of an image to a position on an image of the given size
+/// (None if the point is outside of the image), fitting the image into the view box as resvg draws it
+fn to_image_space(view_box: &usvg::ViewBox, size: usvg::Size, point: Point) -> Option<(f32, f32)> {
+    let fitted = if view_box.aspect.align == usvg::Align::None {
+        view_box.rect.size()
+    } else if view_box.aspect.slice {
+        size.expand_to(view_box.rect.size())
+    } else {
+        size.scale_to(view_box.rect.size())
+    };
+    let (x, y) = usvg::utils::aligned_pos(
+        view_box.aspect.align,
+        view_box.rect.x(),
+        view_box.rect.y(),
+        view_box.rect.width() - fitted.width(),
+        view_box.rect.height() - fitted.height(),
+    );
+
+    let x = (point.x - x) / fitted.width() * size.width();
+    let y = (point.y - y) / fitted.height() * size.height();
+    ((0.0..size.width()).contains(&x) && (0.0..size.height()).contains(&y)).then_some((x, y))
+}
+
+/// Map a point through the inverse of a transform (None if the transform cannot be inverted)
+fn inverse_map(transform: usvg::Transform, point: Point) -> Option<Point> {
+    let mut point = point;
+    transform.invert()?.map_point(&mut point);
+    Some(point)
+}
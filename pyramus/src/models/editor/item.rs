@@ -1,7 +1,19 @@
-use super::{base_item::Base, staging::Staging};
-use crate::models::templates::{ids::InternalId, prop::Prop, prop_item::PropItem};
+use super::{
+    base_item::Base,
+    hit_test::node_contains_point,
+    staging::{HitTest, Staging},
+    usvg_node::ToUsvgNode,
+};
+use crate::{
+    models::templates::{
+        group::Group, ids::InternalId, prop::Prop, prop_item::PropItem, transform::invert_transform,
+    },
+    PyramusError,
+};
 use glam::Vec2;
+use resvg::usvg::tiny_skia_path::Point;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 
 /// An item that can be placed on the stage, or as part of a BaseItem
 /// StageItem, as well as its variants, should implement the Staging trait
@@ -9,6 +21,7 @@ use serde::{Deserialize, Serialize};
 pub enum StageItem {
     PropItem(PropItem),
     Prop(Prop),
+    Group(Group),
 }
 
 impl StageItem {
@@ -20,6 +33,17 @@ impl StageItem {
         match self {
             StageItem::PropItem(item) => item.id,
             StageItem::Prop(prop) => prop.id,
+            StageItem::Group(group) => group.id,
+        }
+    }
+
+    /// Set the item id of the StageItem
+    /// The id should be allocated by the BaseTemplate the item is in
+    pub fn set_id(&mut self, id: InternalId) {
+        match self {
+            StageItem::PropItem(item) => item.id = id,
+            StageItem::Prop(prop) => prop.id = id,
+            StageItem::Group(group) => group.id = id,
         }
     }
 
@@ -28,6 +52,7 @@ impl StageItem {
         match self {
             StageItem::PropItem(item) => item.name = name,
             StageItem::Prop(prop) => prop.name = name,
+            StageItem::Group(group) => group.name = name,
         }
     }
 
@@ -36,65 +61,222 @@ impl StageItem {
         match self {
             StageItem::PropItem(item) => item.set_parent(parent),
             StageItem::Prop(prop) => prop.set_parent(parent),
+            StageItem::Group(group) => group.set_parent(parent),
         }
     }
 
     /// Get the local size of the StageItem
     pub fn get_size(&self) -> (u32, u32) {
-        let (x0, y0, x1, x2) = match self {
-            StageItem::PropItem(item) => item.get_local_bounds(),
-            StageItem::Prop(prop) => prop.get_local_bounds(),
-        };
+        let (x0, y0, x1, x2) = self.get_local_bounds();
         ((x1 - x0) as u32, (x2 - y0) as u32)
     }
 
     /// Get the local bounds of the item
+    /// Groups have no content of their own, so have empty local bounds (see `get_content_bounds`)
     pub fn get_local_bounds(&self) -> (f32, f32, f32, f32) {
         match self {
             StageItem::PropItem(item) => item.get_local_bounds(),
             StageItem::Prop(prop) => prop.get_local_bounds(),
+            StageItem::Group(_) => (0.0, 0.0, 0.0, 0.0),
+        }
+    }
+
+    /// Get the local bounds of what the item shows- its local bounds, or for a group, the bounds of its shown children
+    pub fn get_content_bounds(&self, base: &Base) -> crate::Result<(f32, f32, f32, f32)> {
+        let StageItem::Group(group) = self else {
+            return Ok(self.get_local_bounds());
+        };
+
+        let mut corners = vec![];
+        for &child_id in group.get_children() {
+            let child = base
+                .get_item(child_id)
+                .ok_or(PyramusError::ItemNotFound(child_id))?;
+            if child.get_staging_context().hidden {
+                continue;
+            }
+            let (x0, y0, x1, y1) = child.get_content_bounds(base)?;
+            let transform = child.get_relative_transform().to_glam_affine();
+            corners.extend(
+                [(x0, y0), (x1, y0), (x1, y1), (x0, y1)]
+                    .map(|(x, y)| transform.transform_point2(Vec2::new(x, y))),
+            );
         }
+        if corners.is_empty() {
+            return Ok((0.0, 0.0, 0.0, 0.0));
+        }
+        Ok(bounds_of_points(&corners))
     }
 
-    /// Check if a point in screen space is within the bounds of the item
-    pub fn contains_point(&self, x: f32, y: f32, base: &Base) -> bool {
+    /// Check if a point in screen space is on the item
+    /// (within its bounds, or on what it draws if it is hit-tested precisely)
+    /// Groups draw nothing of their own, so are only hit through their children
+    pub fn contains_point(&self, x: f32, y: f32, base: &Base) -> crate::Result<bool> {
+        if let StageItem::Group(_) = self {
+            return Ok(false);
+        }
+
         // Get transform of current item
-        let transform = self.get_screen_transform(base);
+        let transform = self.get_screen_transform(base)?;
 
         // Get the click in local space and check if it's within the bounds of the item
-        let click = transform.inverse().transform_point2(glam::Vec2::new(x, y));
+        // An item scaled to nothing covers no points
+        let Ok(to_local) = invert_transform(transform, self.get_id()) else {
+            return Ok(false);
+        };
+        let click = to_local.transform_point2(glam::Vec2::new(x, y));
         let (x0, y0, x1, y1) = self.get_local_bounds();
-        click.x >= x0 && click.x <= x1 && click.y >= y0 && click.y <= y1
+        let in_bounds = click.x >= x0 && click.x <= x1 && click.y >= y0 && click.y <= y1;
+
+        // Props with an outline are only hit inside of it, so clicks on torn-away parts fall through
+        let in_outline = match self {
+            StageItem::Prop(Prop {
+                outline: Some(outline),
+                template,
+                ..
+            }) if in_bounds => outline.contains_point(template.size, click.x, click.y),
+            _ => in_bounds,
+        };
+        if !in_outline {
+            return Ok(false);
+        }
+
+        // Precise items are only hit on what they draw
+        match self.get_staging_context().hit_test {
+            HitTest::Bounds => Ok(true),
+            HitTest::Precise { alpha_threshold } => Ok(node_contains_point(
+                &self.to_content_node(&base.render_cache)?,
+                Point::from_xy(click.x, click.y),
+                alpha_threshold,
+            )),
+        }
+    }
+
+    /// Check if the item is shown on the stage (neither it nor any of its parents are hidden)
+    pub fn is_visible(&self, base: &Base) -> crate::Result<bool> {
+        let mut visited = vec![self.get_id()];
+        let mut item = self;
+        loop {
+            if item.get_staging_context().hidden {
+                return Ok(false);
+            }
+            let Some(parent_id) = item.get_parent() else {
+                return Ok(true);
+            };
+            if visited.contains(&parent_id) {
+                return Err(PyramusError::CycleDetected(parent_id));
+            }
+            visited.push(parent_id);
+            item = base
+                .get_item(parent_id)
+                .ok_or(PyramusError::ItemNotFound(parent_id))?;
+        }
+    }
+
+    /// Get the corners of the item's content bounds in screen space
+    /// (x0, y0), (x1, y0), (x1, y1), (x0, y1)- this is the oriented box of the item, so it follows its rotation
+    pub fn get_oriented_bounds(&self, base: &Base) -> crate::Result<[Vec2; 4]> {
+        let (x0, y0, x1, y1) = self.get_content_bounds(base)?;
+        let transform = self.get_screen_transform(base)?;
+        Ok([(x0, y0), (x1, y0), (x1, y1), (x0, y1)]
+            .map(|(x, y)| transform.transform_point2(Vec2::new(x, y))))
     }
 
     /// Get the bounds of the item in screen space
     /// x0, y0, x1, y1
-    pub fn get_bounds(&self, base: &Base) -> (f32, f32, f32, f32) {
-        let (x0, y0, x1, y1) = self.get_local_bounds();
+    /// These are axis-aligned, so they contain all four corners of the oriented bounds
+    pub fn get_bounds(&self, base: &Base) -> crate::Result<(f32, f32, f32, f32)> {
+        Ok(bounds_of_points(&self.get_oriented_bounds(base)?))
+    }
+
+    /// Get the axis-aligned bounds in screen space of the item and all of its shown descendants
+    /// None if the item is hidden
+    pub fn get_subtree_bounds(&self, base: &Base) -> crate::Result<Option<(f32, f32, f32, f32)>> {
+        let mut bounds: Option<(f32, f32, f32, f32)> = None;
+        let mut visited = HashSet::new();
+        let mut pending = vec![self.get_id()];
+        while let Some(id) = pending.pop() {
+            if !visited.insert(id) {
+                return Err(PyramusError::CycleDetected(id));
+            }
+            let item = base.get_item(id).ok_or(PyramusError::ItemNotFound(id))?;
+            // Hidden items are not rendered, along with their children
+            if item.get_staging_context().hidden {
+                continue;
+            }
+            let (x0, y0, x1, y1) = item.get_bounds(base)?;
+            bounds = Some(match bounds {
+                Some((bx0, by0, bx1, by1)) => (bx0.min(x0), by0.min(y0), bx1.max(x1), by1.max(y1)),
+                None => (x0, y0, x1, y1),
+            });
+            pending.extend(item.get_children());
+        }
+        Ok(bounds)
+    }
 
-        let transform = self.get_screen_transform(base);
-        let Vec2 { x: x0, y: y0 } = transform.transform_point2(glam::Vec2::new(x0, y0));
-        let Vec2 { x: x1, y: y1 } = transform.transform_point2(glam::Vec2::new(x1, y1));
+    /// Check if the oriented bounds of the item overlap a rectangle (x0, y0, x1, y1) in screen space
+    pub fn overlaps_rect(&self, rect: (f32, f32, f32, f32), base: &Base) -> crate::Result<bool> {
+        let corners = self.get_oriented_bounds(base)?;
+        let (x0, y0, x1, y1) = rect;
+        let rect_corners = [(x0, y0), (x1, y0), (x1, y1), (x0, y1)].map(|(x, y)| Vec2::new(x, y));
 
-        (
-            f32::min(x0, x1),
-            f32::min(y0, y1),
-            f32::max(x0, x1),
-            f32::max(y0, y1),
-        )
+        // Two convex shapes overlap unless an edge normal of one of them separates them
+        let axes = [
+            Vec2::X,
+            Vec2::Y,
+            (corners[1] - corners[0]).perp(),
+            (corners[3] - corners[0]).perp(),
+        ];
+        Ok(axes.into_iter().all(|axis| {
+            let project = |points: &[Vec2; 4]| {
+                points
+                    .iter()
+                    .fold((f32::MAX, f32::MIN), |(min, max), point| {
+                        let distance = point.dot(axis);
+                        (min.min(distance), max.max(distance))
+                    })
+            };
+            let (min, max) = project(&corners);
+            let (rect_min, rect_max) = project(&rect_corners);
+            min <= rect_max && rect_min <= max
+        }))
     }
 
     /// Get the transform of the item in screen space of a container item
-    pub fn get_screen_transform(&self, base: &Base) -> glam::Affine2 {
+    pub fn get_screen_transform(&self, base: &Base) -> crate::Result<glam::Affine2> {
         // TODO: If we add 3d, this needs a projection matrix/camera and world space as an intermediate step
-        let transform = self.get_relative_transform().to_glam_affine();
-        if let Some(parent_id) = self.get_parent() {
+        let mut transform = self.get_relative_transform().to_glam_affine();
+
+        // Walk up the parents, guarding against cycles
+        let mut visited = vec![self.get_id()];
+        let mut parent = self.get_parent();
+        while let Some(parent_id) = parent {
+            if visited.contains(&parent_id) {
+                return Err(PyramusError::CycleDetected(parent_id));
+            }
+            visited.push(parent_id);
+
             let parent_item = base
                 .get_item(parent_id)
-                .expect("Parent item not found");
-            parent_item.get_screen_transform(base) * transform
-        } else {
-            transform
+                .ok_or(PyramusError::ItemNotFound(parent_id))?;
+            transform = parent_item.get_relative_transform().to_glam_affine() * transform;
+            parent = parent_item.get_parent();
         }
+        Ok(base.view_transform * transform)
     }
 }
+
+/// The axis-aligned bounds of a set of points (x0, y0, x1, y1)
+pub fn bounds_of_points(points: &[Vec2]) -> (f32, f32, f32, f32) {
+    points.iter().fold(
+        (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+        |(x0, y0, x1, y1), point| {
+            (
+                x0.min(point.x),
+                y0.min(point.y),
+                x1.max(point.x),
+                y1.max(point.y),
+            )
+        },
+    )
+}
@@ -0,0 +1,216 @@
+use super::{stage::Stage, staging::Staging};
+use crate::{
+    models::templates::{ids::InternalId, transform::invert_transform},
+    PyramusError,
+};
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Screen-space bounds (x0, y0, x1, y1)
+type Bounds = (f32, f32, f32, f32);
+
+/// Which edge (or center) of the items to line up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// What items are aligned relative to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlignTo {
+    /// The bounds of all of the aligned items together
+    Selection,
+    /// The bounds of the parent of each item
+    Parent,
+    /// The bounds of the template
+    Template,
+}
+
+/// The axis items are distributed along
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+impl Alignment {
+    /// The position of this edge of bounds (x0, y0, x1, y1), and the axis it is on
+    fn edge_of(self, (x0, y0, x1, y1): Bounds) -> (f32, Axis) {
+        match self {
+            Alignment::Left => (x0, Axis::Horizontal),
+            Alignment::Center => ((x0 + x1) / 2.0, Axis::Horizontal),
+            Alignment::Right => (x1, Axis::Horizontal),
+            Alignment::Top => (y0, Axis::Vertical),
+            Alignment::Middle => ((y0 + y1) / 2.0, Axis::Vertical),
+            Alignment::Bottom => (y1, Axis::Vertical),
+        }
+    }
+}
+
+impl Axis {
+    /// A screen-space offset of `distance` along this axis
+    fn offset(self, distance: f32) -> (f32, f32) {
+        match self {
+            Axis::Horizontal => (distance, 0.0),
+            Axis::Vertical => (0.0, distance),
+        }
+    }
+
+    /// The start and end of bounds (x0, y0, x1, y1) along this axis
+    fn range_of(self, (x0, y0, x1, y1): Bounds) -> (f32, f32) {
+        match self {
+            Axis::Horizontal => (x0, x1),
+            Axis::Vertical => (y0, y1),
+        }
+    }
+}
+
+impl Stage {
+    /// Align the edges (or centers) of items, moving them in screen space
+    /// Items are measured with their shown children, and items inside of another aligned item move with it
+    /// Returns the items that were moved
+    pub fn align_items(
+        &mut self,
+        item_ids: &[InternalId],
+        alignment: Alignment,
+        align_to: AlignTo,
+    ) -> crate::Result<Vec<InternalId>> {
+        let items = self.get_layout_bounds(item_ids)?;
+
+        let selection_bounds = items.iter().map(|(_, bounds)| *bounds).reduce(
+            |(ax0, ay0, ax1, ay1), (bx0, by0, bx1, by1)| {
+                (ax0.min(bx0), ay0.min(by0), ax1.max(bx1), ay1.max(by1))
+            },
+        );
+        let Some(selection_bounds) = selection_bounds else {
+            return Ok(vec![]);
+        };
+
+        let mut moved = vec![];
+        for (item_id, bounds) in items {
+            let target_bounds = match align_to {
+                AlignTo::Selection => selection_bounds,
+                AlignTo::Parent => {
+                    let item = self
+                        .base
+                        .get_item(item_id)
+                        .ok_or(PyramusError::ItemNotFound(item_id))?;
+                    let parent_id = item.get_parent().unwrap_or(self.base.get_root());
+                    self.base
+                        .get_item(parent_id)
+                        .ok_or(PyramusError::ItemNotFound(parent_id))?
+                        .get_bounds(&self.base)?
+                }
+                AlignTo::Template => {
+                    let root = self.base.get_root();
+                    self.base
+                        .get_item(root)
+                        .ok_or(PyramusError::ItemNotFound(root))?
+                        .get_bounds(&self.base)?
+                }
+            };
+
+            let (target, axis) = alignment.edge_of(target_bounds);
+            let (current, _) = alignment.edge_of(bounds);
+            if target != current {
+                self.translate_in_screen_space(item_id, axis.offset(target - current))?;
+                moved.push(item_id);
+            }
+        }
+        Ok(moved)
+    }
+
+    /// Space items out evenly along an axis, so the gaps between them are equal
+    /// The first and last items (by position on the axis) stay in place, so at least three items are needed
+    /// Returns the items that were moved
+    pub fn distribute_items(
+        &mut self,
+        item_ids: &[InternalId],
+        axis: Axis,
+    ) -> crate::Result<Vec<InternalId>> {
+        let mut items: Vec<(InternalId, (f32, f32))> = self
+            .get_layout_bounds(item_ids)?
+            .into_iter()
+            .map(|(item_id, bounds)| (item_id, axis.range_of(bounds)))
+            .collect();
+        if items.len() < 3 {
+            return Ok(vec![]);
+        }
+        items.sort_by(|(_, (a, _)), (_, (b, _))| a.total_cmp(b));
+
+        let start = items[0].1 .0;
+        let end = items.iter().fold(f32::MIN, |end, (_, (_, e))| end.max(*e));
+        let total_size: f32 = items.iter().map(|(_, (s, e))| e - s).sum();
+        let gap = (end - start - total_size) / (items.len() - 1) as f32;
+
+        let mut moved = vec![];
+        let mut position = start;
+        for (item_id, (s, e)) in items {
+            if position != s {
+                self.translate_in_screen_space(item_id, axis.offset(position - s))?;
+                moved.push(item_id);
+            }
+            position += e - s + gap;
+        }
+        Ok(moved)
+    }
+
+    /// Get the screen-space bounds (including shown children) of items to lay out
+    /// Hidden items, and items inside of another of the items (which move with it), are left out
+    fn get_layout_bounds(
+        &self,
+        item_ids: &[InternalId],
+    ) -> crate::Result<Vec<(InternalId, Bounds)>> {
+        let mut items = vec![];
+        for &item_id in item_ids {
+            let item = self
+                .base
+                .get_item(item_id)
+                .ok_or(PyramusError::ItemNotFound(item_id))?;
+            if let Some(parent_id) = item.get_parent() {
+                if self.base.is_within(parent_id, item_ids)? {
+                    continue;
+                }
+            }
+            if let Some(bounds) = item.get_subtree_bounds(&self.base)? {
+                items.push((item_id, bounds));
+            }
+        }
+        Ok(items)
+    }
+
+    /// Move an item by an offset in screen space, by changing the position of its relative transform
+    pub(crate) fn translate_in_screen_space(
+        &mut self,
+        item_id: InternalId,
+        (x, y): (f32, f32),
+    ) -> crate::Result<()> {
+        // The position is in the space of the parent, so the offset is taken into it
+        let item = self
+            .base
+            .get_item(item_id)
+            .ok_or(PyramusError::ItemNotFound(item_id))?;
+        let parent_id = item.get_parent().unwrap_or(item_id);
+        let parent_transform = match item.get_parent() {
+            Some(parent_id) => self
+                .base
+                .get_item(parent_id)
+                .ok_or(PyramusError::ItemNotFound(parent_id))?
+                .get_screen_transform(&self.base)?,
+            None => self.base.view_transform,
+        };
+        let offset = invert_transform(parent_transform, parent_id)?
+            .matrix2
+            .mul_vec2(Vec2::new(x, y));
+        self.base.edit_item_transform(item_id, |t| {
+            t.position.0 += offset.x;
+            t.position.1 += offset.y;
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::HashSet;
+
+use super::{
+    base_item::{Base, BaseTemplate, BASE_FORMAT_VERSION},
+    item::StageItem,
+    staging::Staging,
+};
+
+impl Base {
+    /// Bring a base saved with an older document format up to the current one, keeping its layout the same
+    /// Does nothing for a base that is already current (see `BASE_FORMAT_VERSION`)
+    pub fn migrate(&mut self) {
+        if self.version < 2 {
+            link_parents(self.get_template_mut());
+        }
+        self.version = BASE_FORMAT_VERSION;
+    }
+}
+
+/// Version 1 documents only recorded the children of each item, drawing each child within its parent
+/// Children are given the parent that lists them, so their screen transforms compose the same way they were drawn.
+/// Items listed by no parent were never drawn, so they are kept under the root, hidden.
+fn link_parents(template: &mut BaseTemplate) {
+    let mut ids = template.items.keys().copied().collect::<Vec<_>>();
+    ids.sort();
+
+    let mut listed = HashSet::new();
+    for id in &ids {
+        for child_id in template.items[id].get_children().clone() {
+            listed.insert(child_id);
+            let Some(child) = template.items.get_mut(&child_id) else {
+                continue;
+            };
+            if child.get_parent().is_none() && child_id != template.root {
+                child.set_parent(Some(*id));
+            }
+        }
+    }
+
+    let root = template.root;
+    if template.items.contains_key(&root) {
+        for id in &ids {
+            if *id == root || listed.contains(id) {
+                continue;
+            }
+            let Some(item) = template.items.get_mut(id) else {
+                continue;
+            };
+            if item.get_parent().is_some() {
+                continue;
+            }
+            item.set_parent(Some(root));
+            item.get_staging_context_mut().hidden = true;
+            if let Some(root) = template.items.get_mut(&root) {
+                root.get_children_mut().push(*id);
+            }
+        }
+    }
+
+    // Nested props were saved with the same format
+    for item in template.items.values_mut() {
+        if let StageItem::Prop(prop) = item {
+            link_parents(&mut prop.template);
+        }
+    }
+}
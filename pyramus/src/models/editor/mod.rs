@@ -1,5 +1,14 @@
 pub mod base_item;
+pub mod extraction;
+pub mod grouping;
+pub mod hit_test;
 pub mod item;
+pub mod layout;
+pub mod migration;
+pub mod nesting;
+pub mod render_cache;
+pub mod spatial_index;
 pub mod stage;
 pub mod staging;
 pub mod usvg_node;
+pub mod validation;
@@ -0,0 +1,119 @@
+use super::{
+    base_item::{Base, BaseItem, BaseTemplate},
+    item::StageItem,
+    stage::Stage,
+};
+use crate::{
+    models::templates::{builder::ItemBuilder, ids::InternalId, prop::Prop},
+    PyramusError,
+};
+
+/// An outer template around the nested prop being edited (see `Stage::enter_prop`)
+#[derive(Debug)]
+pub struct EditContext {
+    /// The outer base, which holds an empty placeholder in place of the template of the entered prop while it is edited
+    pub base: Base,
+    /// The entered prop, in the outer base
+    pub prop_id: InternalId,
+    /// The selection of the outer base, restored when the prop is exited
+    pub selection: Vec<InternalId>,
+    /// The group being edited in the outer base, restored when the prop is exited
+    pub entered_group: Option<InternalId>,
+}
+
+impl Stage {
+    /// Edit the template of a nested prop in place, so commands and hit-testing apply to its items
+    /// The prop keeps its place on screen, with the templates around it shown behind it for context
+    pub fn enter_prop(&mut self, prop_id: InternalId) -> crate::Result<()> {
+        let item = self
+            .base
+            .get_item(prop_id)
+            .ok_or(PyramusError::ItemNotFound(prop_id))?;
+        let StageItem::Prop(prop) = item else {
+            return Err(PyramusError::WrongItemType(prop_id));
+        };
+        let view_transform = item.get_screen_transform(&self.base)?;
+
+        // The template is moved out of the outer base while it is edited, so it is not drawn twice
+        // An empty group draws nothing, but the prop keeps its size, outline and the items placed on it
+        let placeholder = BaseTemplate::new(ItemBuilder::build_group(), prop.template.size)?;
+        let mut inner_prop = None;
+        self.base.edit_item(prop_id, |item| match item {
+            StageItem::Prop(prop) => {
+                let template = std::mem::replace(&mut prop.template, placeholder);
+                inner_prop = Some(Prop {
+                    template,
+                    ..prop.clone()
+                });
+                Ok(())
+            }
+            _ => Err(PyramusError::WrongItemType(prop_id)),
+        })?;
+        let inner_prop = inner_prop.ok_or(PyramusError::WrongItemType(prop_id))?;
+
+        let mut inner_base = Base::new(inner_prop.into());
+        inner_base.view_transform = view_transform;
+        let outer_base = std::mem::replace(&mut self.base, inner_base);
+        self.edit_contexts.push(EditContext {
+            base: outer_base,
+            prop_id,
+            selection: std::mem::take(&mut self.selection),
+            entered_group: self.entered_group.take(),
+        });
+        Ok(())
+    }
+
+    /// Go back up from editing a nested prop to the template it is in, and select the prop that was left
+    pub fn exit_prop(&mut self) -> crate::Result<()> {
+        let Some(context) = self.edit_contexts.pop() else {
+            return Ok(());
+        };
+        let inner_base = std::mem::replace(&mut self.base, context.base);
+        let template = match inner_base.item {
+            BaseItem::Prop(prop) => prop.template,
+            BaseItem::Blueprint(_) => return Err(PyramusError::WrongItemType(context.prop_id)),
+        };
+        restore_template(&mut self.base, context.prop_id, template)?;
+
+        self.entered_group = context.entered_group;
+        self.set_selection(vec![context.prop_id]);
+        Ok(())
+    }
+
+    /// Get the base at the top level, which sets the size of the screen
+    /// While a nested prop is edited, this holds a placeholder in place of its template (see `to_top_level_base`)
+    pub fn get_top_level_base(&self) -> &Base {
+        self.edit_contexts
+            .first()
+            .map_or(&self.base, |context| &context.base)
+    }
+
+    /// Get a copy of the base at the top level, with the templates of any entered props in place (e.g. for saving)
+    pub fn to_top_level_base(&self) -> crate::Result<Base> {
+        let mut base = self.base.clone();
+        for context in self.edit_contexts.iter().rev() {
+            let template = match base.item {
+                BaseItem::Prop(prop) => prop.template,
+                BaseItem::Blueprint(_) => return Err(PyramusError::WrongItemType(context.prop_id)),
+            };
+            base = context.base.clone();
+            restore_template(&mut base, context.prop_id, template)?;
+        }
+        Ok(base)
+    }
+}
+
+/// Put the edited template of an entered prop back in place of its placeholder in the outer base
+fn restore_template(
+    base: &mut Base,
+    prop_id: InternalId,
+    template: BaseTemplate,
+) -> crate::Result<()> {
+    base.edit_item(prop_id, |item| match item {
+        StageItem::Prop(prop) => {
+            prop.template = template;
+            Ok(())
+        }
+        _ => Err(PyramusError::WrongItemType(prop_id)),
+    })
+}
@@ -0,0 +1,85 @@
+use std::{cell::RefCell, collections::HashMap, fmt};
+
+use resvg::usvg;
+
+use crate::models::templates::ids::InternalId;
+
+/// Rendered nodes of the items of a base, so that unchanged items are not rebuilt on every render
+/// Two nodes are kept for each item:
+/// - its content (what the item itself draws), which is only rebuilt when the item is edited
+/// - its staged node (the content and children, with its transform and staging applied), which is also rebuilt
+///   when the item is moved, or any of its descendants change
+///
+/// Entries are invalidated by the `Base` whenever an item is edited, along with the staged nodes of its parents.
+#[derive(Default)]
+pub struct RenderCache {
+    content: RefCell<HashMap<InternalId, usvg::Node>>,
+    staged: RefCell<HashMap<InternalId, usvg::Node>>,
+}
+
+impl RenderCache {
+    /// Get the cached content of an item, building (and caching) it if there is none
+    pub fn get_or_build_content(
+        &self,
+        id: InternalId,
+        build: impl FnOnce() -> crate::Result<usvg::Node>,
+    ) -> crate::Result<usvg::Node> {
+        get_or_build(&self.content, id, build)
+    }
+
+    /// Get the cached staged node of an item, building (and caching) it if there is none
+    pub fn get_or_build_staged(
+        &self,
+        id: InternalId,
+        build: impl FnOnce() -> crate::Result<usvg::Node>,
+    ) -> crate::Result<usvg::Node> {
+        get_or_build(&self.staged, id, build)
+    }
+
+    /// Drop the cached content and staged node of an item, so they are rebuilt on the next render
+    pub fn invalidate(&mut self, id: InternalId) {
+        self.content.get_mut().remove(&id);
+        self.staged.get_mut().remove(&id);
+    }
+
+    /// Drop the cached staged node of an item, keeping its content
+    pub fn invalidate_staged(&mut self, id: InternalId) {
+        self.staged.get_mut().remove(&id);
+    }
+
+    /// Drop every cached node
+    pub fn clear(&mut self) {
+        self.content.get_mut().clear();
+        self.staged.get_mut().clear();
+    }
+}
+
+fn get_or_build(
+    nodes: &RefCell<HashMap<InternalId, usvg::Node>>,
+    id: InternalId,
+    build: impl FnOnce() -> crate::Result<usvg::Node>,
+) -> crate::Result<usvg::Node> {
+    if let Some(node) = nodes.borrow().get(&id) {
+        return Ok(node.clone());
+    }
+    // Building a staged node builds (and caches) its children, so the map is not borrowed meanwhile
+    let node = build()?;
+    nodes.borrow_mut().insert(id, node.clone());
+    Ok(node)
+}
+
+// A copied base may be edited independently, so it starts with an empty cache
+impl Clone for RenderCache {
+    fn clone(&self) -> Self {
+        RenderCache::default()
+    }
+}
+
+impl fmt::Debug for RenderCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RenderCache")
+            .field("content", &self.content.borrow().len())
+            .field("staged", &self.staged.borrow().len())
+            .finish()
+    }
+}
@@ -0,0 +1,372 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::models::templates::ids::InternalId;
+
+use super::{base_item::Base, item::StageItem, staging::Staging};
+
+/// A grid of the screen-space bounds of the items of a base, for finding the items at a point or in a rectangle
+/// without checking every item. Like the `RenderCache`, it is kept up to date by the `Base`:
+/// moving an item only recomputes the bounds of that item and its descendants, the next time the index is queried.
+pub struct SpatialIndex {
+    state: RefCell<IndexState>,
+}
+
+#[derive(Default)]
+struct IndexState {
+    /// Bounds (x0, y0, x1, y1) of each rendered item
+    bounds: HashMap<InternalId, (f32, f32, f32, f32)>,
+    /// Items whose bounds overlap each cell of the grid
+    cells: HashMap<(i32, i32), Vec<InternalId>>,
+    /// Items too large to be put in cells, which are checked by every query
+    large: Vec<InternalId>,
+    /// Position of each item in the render order (higher is in front)
+    order: HashMap<InternalId, usize>,
+
+    /// Items whose bounds (and the bounds of their descendants) need recomputing
+    dirty: HashSet<InternalId>,
+    /// Whether the items have been added, removed or reordered since the render order was computed
+    order_stale: bool,
+    /// Whether every item needs recomputing
+    stale: bool,
+}
+
+impl SpatialIndex {
+    // Size of the cells of the grid, in screen space
+    const CELL_SIZE: f32 = 128.0;
+    // Items overlapping more cells than this (in either direction) are kept out of the grid
+    const MAX_CELL_SPAN: i64 = 64;
+
+    /// Recompute the bounds of an item and its descendants (for example, after it has been moved)
+    pub fn mark_moved(&mut self, id: InternalId) {
+        self.state.get_mut().dirty.insert(id);
+    }
+
+    /// Recompute the bounds of an item, and the render order (the item may have been added, or its children changed)
+    pub fn mark_changed(&mut self, id: InternalId) {
+        let state = self.state.get_mut();
+        state.dirty.insert(id);
+        state.order_stale = true;
+    }
+
+    /// Recompute everything
+    pub fn clear(&mut self) {
+        self.state.get_mut().stale = true;
+    }
+
+    /// Get the items whose bounds contain a point, from front to back
+    pub fn query_point(&self, base: &Base, x: f32, y: f32) -> crate::Result<Vec<InternalId>> {
+        self.query(base, (x, y, x, y))
+            .map(|ids| ids.into_iter().rev().collect())
+    }
+
+    /// Get the items whose bounds overlap a rectangle (x0, y0, x1, y1), from back to front
+    pub fn query_rect(
+        &self,
+        base: &Base,
+        rect: (f32, f32, f32, f32),
+    ) -> crate::Result<Vec<InternalId>> {
+        self.query(base, rect)
+    }
+
+    /// Get the screen-space bounds of an item, if it is rendered
+    pub fn get_bounds(
+        &self,
+        base: &Base,
+        id: InternalId,
+    ) -> crate::Result<Option<(f32, f32, f32, f32)>> {
+        self.refresh(base)?;
+        Ok(self.state.borrow().bounds.get(&id).copied())
+    }
+
+    fn query(&self, base: &Base, rect: (f32, f32, f32, f32)) -> crate::Result<Vec<InternalId>> {
+        self.refresh(base)?;
+        let state = self.state.borrow();
+
+        let mut found = HashSet::new();
+        let candidates = match cells_of(rect) {
+            Some(cells) => cells
+                .flat_map(|cell| state.cells.get(&cell).into_iter().flatten())
+                .collect(),
+            // The query is too large for the grid to help
+            None => state.bounds.keys().collect::<Vec<_>>(),
+        };
+        for id in candidates.into_iter().chain(&state.large) {
+            if overlaps(state.bounds[id], rect) {
+                found.insert(*id);
+            }
+        }
+        let mut found: Vec<InternalId> = found.into_iter().collect();
+        found.sort_by_key(|id| state.order[id]);
+        Ok(found)
+    }
+
+    /// Bring the index up to date with the base
+    fn refresh(&self, base: &Base) -> crate::Result<()> {
+        let mut state = self.state.borrow_mut();
+        if state.stale {
+            *state = IndexState::default();
+            state.order = order_of(base)?;
+            let ids: Vec<InternalId> = state.order.keys().copied().collect();
+            for id in ids {
+                state.update(base, id)?;
+            }
+            return Ok(());
+        }
+
+        if state.order_stale {
+            state.order = order_of(base)?;
+            state.order_stale = false;
+            // Items that are no longer rendered (such as removed items) are dropped
+            let removed: Vec<InternalId> = state
+                .bounds
+                .keys()
+                .filter(|id| !state.order.contains_key(id))
+                .copied()
+                .collect();
+            for id in removed {
+                state.remove(id);
+            }
+        }
+
+        // Moving an item moves its descendants
+        let mut pending: Vec<InternalId> = state.dirty.drain().collect();
+        let mut visited = HashSet::new();
+        while let Some(id) = pending.pop() {
+            if !visited.insert(id) || !state.order.contains_key(&id) {
+                continue;
+            }
+            state.update(base, id)?;
+            if let Some(item) = base.get_item(id) {
+                pending.extend(item.get_children());
+            }
+        }
+
+        // Groups are as large as their children, so change along with them
+        let mut groups = HashSet::new();
+        for id in visited {
+            let mut parent = base.get_item(id).and_then(|item| item.get_parent());
+            while let Some(parent_id) = parent {
+                let Some(group @ StageItem::Group(_)) = base.get_item(parent_id) else {
+                    break;
+                };
+                if !groups.insert(parent_id) {
+                    break;
+                }
+                parent = group.get_parent();
+            }
+        }
+        for id in groups {
+            if state.order.contains_key(&id) {
+                state.update(base, id)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl IndexState {
+    /// Recompute the bounds of an item, and move it to the cells it now overlaps
+    fn update(&mut self, base: &Base, id: InternalId) -> crate::Result<()> {
+        self.remove(id);
+        let Some(item) = base.get_item(id) else {
+            return Ok(());
+        };
+        let bounds = item.get_bounds(base)?;
+        match cells_of(bounds) {
+            Some(cells) => {
+                for cell in cells {
+                    self.cells.entry(cell).or_default().push(id);
+                }
+            }
+            None => self.large.push(id),
+        }
+        self.bounds.insert(id, bounds);
+        Ok(())
+    }
+
+    fn remove(&mut self, id: InternalId) {
+        let Some(bounds) = self.bounds.remove(&id) else {
+            return;
+        };
+        let Some(cells) = cells_of(bounds) else {
+            self.large.retain(|other| *other != id);
+            return;
+        };
+        for cell in cells {
+            if let Some(ids) = self.cells.get_mut(&cell) {
+                ids.retain(|other| *other != id);
+                if ids.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+    }
+}
+
+/// The position of each rendered item in the render order
+fn order_of(base: &Base) -> crate::Result<HashMap<InternalId, usize>> {
+    Ok(base
+        .get_render_order()?
+        .into_iter()
+        .enumerate()
+        .map(|(index, id)| (id, index))
+        .collect())
+}
+
+/// The cells of the grid overlapped by a rectangle (None if it overlaps too many)
+fn cells_of((x0, y0, x1, y1): (f32, f32, f32, f32)) -> Option<impl Iterator<Item = (i32, i32)>> {
+    let cell = |v: f32| (v / SpatialIndex::CELL_SIZE).floor() as i32;
+    let (cx0, cy0, cx1, cy1) = (cell(x0), cell(y0), cell(x1), cell(y1));
+    let span = |a: i32, b: i32| b as i64 - a as i64;
+    if span(cx0, cx1) > SpatialIndex::MAX_CELL_SPAN || span(cy0, cy1) > SpatialIndex::MAX_CELL_SPAN
+    {
+        return None;
+    }
+    Some((cx0..=cx1).flat_map(move |cx| (cy0..=cy1).map(move |cy| (cx, cy))))
+}
+
+fn overlaps(a: (f32, f32, f32, f32), b: (f32, f32, f32, f32)) -> bool {
+    a.0 <= b.2 && b.0 <= a.2 && a.1 <= b.3 && b.1 <= a.3
+}
+
+impl Default for SpatialIndex {
+    fn default() -> Self {
+        SpatialIndex {
+            state: RefCell::new(IndexState {
+                stale: true,
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+// A copied base may be edited independently, so it starts with an empty index
+impl Clone for SpatialIndex {
+    fn clone(&self) -> Self {
+        SpatialIndex::default()
+    }
+}
+
+impl fmt::Debug for SpatialIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpatialIndex")
+            .field("indexed", &self.state.borrow().bounds.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{
+        editor::base_item::Base,
+        templates::{
+            builder::ItemBuilder,
+            ids::InternalId,
+            prop::Prop,
+            shape::{PropItemShape, Shape},
+            transform::RelativeTransform,
+        },
+    };
+
+    /// A 10x10 rectangle, placed at `position` within its parent
+    fn rect(position: (f32, f32)) -> ItemBuilder {
+        let shape = Shape::Rectangle {
+            width: 10.0,
+            height: 10.0,
+        };
+        ItemBuilder::build_shape(PropItemShape::filled(shape, (0, 0, 0))).transform(
+            RelativeTransform {
+                position,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn build_base() -> Base {
+        Base::new(Prop::new(InternalId::UNCONTAINED, "Test", 500, 500).into())
+    }
+
+    /// The items at a point, from front to back, leaving out the root (which covers the whole template)
+    fn items_at(base: &Base, x: f32, y: f32) -> Vec<InternalId> {
+        let root = base.get_root();
+        let mut items = base.get_items_at(x, y).unwrap();
+        items.retain(|id| *id != root);
+        items
+    }
+
+    fn move_to(base: &mut Base, id: InternalId, position: (f32, f32)) {
+        base.edit_item_transform(id, |transform| {
+            transform.position = position;
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn query_follows_moved_item() {
+        let mut base = build_base();
+        let a = base.add_child(rect((10.0, 10.0))).unwrap();
+        assert_eq!(items_at(&base, 15.0, 15.0), vec![a]);
+
+        move_to(&mut base, a, (200.0, 200.0));
+        assert!(items_at(&base, 15.0, 15.0).is_empty());
+        assert_eq!(items_at(&base, 205.0, 205.0), vec![a]);
+        assert_eq!(
+            base.get_indexed_bounds(a).unwrap(),
+            Some((200.0, 200.0, 210.0, 210.0))
+        );
+    }
+
+    #[test]
+    fn query_follows_children_of_moved_item() {
+        let mut base = build_base();
+        let a = base.add_child(rect((10.0, 10.0))).unwrap();
+        let b = base.add_child(rect((5.0, 5.0)).parent(a)).unwrap();
+        assert_eq!(items_at(&base, 17.0, 17.0), vec![b, a]);
+
+        // Moved into another cell of the grid, away from where it was
+        move_to(&mut base, a, (300.0, 10.0));
+        assert!(items_at(&base, 17.0, 17.0).is_empty());
+        assert_eq!(items_at(&base, 307.0, 17.0), vec![b, a]);
+        assert_eq!(
+            base.get_indexed_bounds(b).unwrap(),
+            Some((305.0, 15.0, 315.0, 25.0))
+        );
+    }
+
+    #[test]
+    fn removed_item_is_not_found() {
+        let mut base = build_base();
+        let a = base.add_child(rect((10.0, 10.0))).unwrap();
+        let b = base.add_child(rect((12.0, 12.0))).unwrap();
+        assert_eq!(items_at(&base, 15.0, 15.0), vec![b, a]);
+
+        base.remove_item(b).unwrap();
+        assert_eq!(items_at(&base, 15.0, 15.0), vec![a]);
+        assert_eq!(base.get_indexed_bounds(b).unwrap(), None);
+        assert!(base
+            .get_items_in_rect((0.0, 0.0, 500.0, 500.0))
+            .unwrap()
+            .iter()
+            .all(|id| *id != b));
+    }
+
+    #[test]
+    fn query_follows_reordered_items() {
+        let mut base = build_base();
+        let root = base.get_root();
+        let a = base.add_child(rect((10.0, 10.0))).unwrap();
+        let b = base.add_child(rect((12.0, 12.0))).unwrap();
+        assert_eq!(items_at(&base, 15.0, 15.0), vec![b, a]);
+
+        // Moving `a` to the end of the children of the root draws it in front
+        base.move_item(a, root, None).unwrap();
+        assert_eq!(items_at(&base, 15.0, 15.0), vec![a, b]);
+        let in_rect = base.get_items_in_rect((0.0, 0.0, 50.0, 50.0)).unwrap();
+        assert_eq!(in_rect, vec![root, b, a]);
+    }
+}
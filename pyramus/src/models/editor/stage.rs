@@ -1,7 +1,8 @@
-use super::{base_item::Base, item::StageItem, staging::Staging};
+use super::{base_item::Base, item::StageItem, nesting::EditContext, staging::Staging};
 use crate::{
-    input::MouseState,
-    models::templates::{blueprint::Blueprint, ids::InternalId, prop::Prop},
+    input::{snapping::Snapping, MouseState, Tool},
+    models::templates::ids::InternalId,
+    PyramusError,
 };
 
 /// The stage is the main area where items are placed and manipulated.
@@ -16,6 +17,14 @@ pub struct Stage {
 
     // TODO: Should be exported to some other 'state'-type mechansms
     pub mouse_state: MouseState,
+    pub tool: Tool,
+    pub snapping: Snapping,
+
+    /// The group being edited, whose items are selected on their own rather than as the group (None for the top level)
+    pub entered_group: Option<InternalId>,
+
+    /// The templates around the nested prop being edited, outermost first (empty when editing the base itself)
+    pub edit_contexts: Vec<EditContext>,
 }
 
 // TODO: Move these functions to separate modules/files
@@ -25,11 +34,16 @@ impl Stage {
 
     // TODO: This should become trait again, with one build()
     /// Create a new stage from a base
+    /// Loaded bases should be checked and repaired first (see `Base::load`)
     pub fn new(base: Base) -> Stage {
         Stage {
             base,
             selection: Vec::new(),
             mouse_state: MouseState::Idle,
+            tool: Tool::default(),
+            snapping: Snapping::default(),
+            entered_group: None,
+            edit_contexts: Vec::new(),
         }
     }
 
@@ -56,61 +70,123 @@ impl Stage {
             .collect()
     }
 
+    /// Get the axis-aligned bounds of the selected items in screen space (x0, y0, x1, y1)
+    /// None if nothing is selected
+    pub fn get_selection_bounds(&self) -> crate::Result<Option<(f32, f32, f32, f32)>> {
+        let mut bounds: Option<(f32, f32, f32, f32)> = None;
+        for item in self.get_selections() {
+            let (x0, y0, x1, y1) = item.get_bounds(&self.base)?;
+            bounds = Some(match bounds {
+                Some((bx0, by0, bx1, by1)) => (bx0.min(x0), by0.min(y0), bx1.max(x1), by1.max(y1)),
+                None => (x0, y0, x1, y1),
+            });
+        }
+        Ok(bounds)
+    }
+
+    /// Get the item that is selected for an item- the outermost group it is in (inside of the entered group), or the item itself
+    pub fn get_selectable_item(&self, item_id: InternalId) -> crate::Result<InternalId> {
+        let mut selectable = item_id;
+        let mut visited = vec![item_id];
+        let mut parent = self
+            .base
+            .get_item(item_id)
+            .ok_or(PyramusError::ItemNotFound(item_id))?
+            .get_parent();
+        while let Some(parent_id) = parent {
+            if Some(parent_id) == self.entered_group {
+                break;
+            }
+            if visited.contains(&parent_id) {
+                return Err(PyramusError::CycleDetected(parent_id));
+            }
+            visited.push(parent_id);
+
+            let parent_item = self
+                .base
+                .get_item(parent_id)
+                .ok_or(PyramusError::ItemNotFound(parent_id))?;
+            if let StageItem::Group(_) = parent_item {
+                selectable = parent_id;
+            }
+            parent = parent_item.get_parent();
+        }
+        Ok(selectable)
+    }
+
     /// Get the front-most item at the given x,y (in screen coordinates)
+    /// Items in a group give the group (see `get_selectable_item`)
     /// None if no item is found
-    pub fn get_front_item_at(&self, x: f32, y: f32, include_root: bool) -> Option<InternalId> {
+    pub fn get_front_item_at(
+        &self,
+        x: f32,
+        y: f32,
+        include_root: bool,
+    ) -> crate::Result<Option<InternalId>> {
         // TODO: We need to add Z-index (render order) support, which will affect how this selects items
         // Currently, this just uses the children order (last child is on top), which should be used as a tiebreaker
-        // TODO: Caching will help this
-        let render_ordered = self.get_render_order();
-        for item_id in render_ordered.into_iter().rev() {
+        // Only items whose bounds contain the point need checking
+        for item_id in self.base.get_items_at(x, y)? {
             if !include_root && item_id == self.base.get_root() {
                 continue;
             }
 
-            let item: &StageItem = self.base.get_item(item_id).unwrap(); // TODO: unwrap
-            if item.contains_point(x, y, &self.base) {
-                return Some(item_id);
+            let item: &StageItem = self
+                .base
+                .get_item(item_id)
+                .ok_or(PyramusError::ItemNotFound(item_id))?;
+            // Hidden items cannot be clicked
+            if item.is_visible(&self.base)? && item.contains_point(x, y, &self.base)? {
+                return Ok(Some(self.get_selectable_item(item_id)?));
             }
         }
-        None
+        Ok(None)
     }
 
-    /// Get the render order of the items in the stage
-    pub fn get_render_order(&self) -> Vec<InternalId> {
-        // TODO: We need to add Z-index (render order) support, which will affect how this selects items
-        // Currently, this just uses the children order (last child is on top), which should be used as a tiebreaker
-        // TODO: Maybe we should use a BTreeMap here, to keep the order sorted, or a VecDeque to keep the order, or PartialEq implemntation, or something
-        // TODO: Caching will help this
-        let mut render_order = Vec::new();
-
-        render_order.extend(Self::get_render_order_recursive(self, self.base.get_root()));
-
-        render_order
+    /// Get the items overlapping a rectangle (x0, y0, x1, y1, in screen coordinates), from back to front
+    /// Items are tested against their oriented bounds, so rotated items are only found where they are
+    /// Items in a group give the group (see `get_selectable_item`)
+    /// Hidden items (and the root, unless `include_root` is set) are left out
+    pub fn get_items_in_rect(
+        &self,
+        rect: (f32, f32, f32, f32),
+        include_root: bool,
+    ) -> crate::Result<Vec<InternalId>> {
+        let mut items = vec![];
+        for item_id in self.base.get_items_in_rect(rect)? {
+            if !include_root && item_id == self.base.get_root() {
+                continue;
+            }
+            let item = self
+                .base
+                .get_item(item_id)
+                .ok_or(PyramusError::ItemNotFound(item_id))?;
+            if item.is_visible(&self.base)? && item.overlaps_rect(rect, &self.base)? {
+                let item_id = self.get_selectable_item(item_id)?;
+                if !items.contains(&item_id) {
+                    items.push(item_id);
+                }
+            }
+        }
+        Ok(items)
     }
 
-    fn get_render_order_recursive(stage: &Stage, item_id: InternalId) -> Vec<InternalId> {
-        let item = stage.base.get_item(item_id).unwrap(); // TODO: Handle this unwrap properly
-        let mut render_order = vec![item_id];
-        for child in item.get_children() {
-            render_order.extend(Self::get_render_order_recursive(stage, *child));
-        }
-        render_order
+    /// Get the render order of the items in the stage
+    pub fn get_render_order(&self) -> crate::Result<Vec<InternalId>> {
+        self.base.get_render_order()
     }
 }
 
 // TODO: Remove, this is just for testing of WASM rendering before other features are implemented
-pub fn example_stage_prop() -> crate::Result<Stage> {
-    let prop = Prop::build_random("Test", 800, 600);
-    let base = Base::new(prop.into());
+pub fn example_stage_prop(seed: u64) -> crate::Result<Stage> {
+    let base = Base::build_random_prop("Test", 800, 600, seed);
     let stage = Stage::new(base);
     Ok(stage)
 }
 
 // TODO: Remove, this is just for testing of WASM rendering before other features are implemented
-pub fn example_stage_blueprint() -> crate::Result<Stage> {
-    let blueprint = Blueprint::build_random("Test", 800, 600);
-    let base = Base::new(blueprint.into());
+pub fn example_stage_blueprint(seed: u64) -> crate::Result<Stage> {
+    let base = Base::build_random_blueprint("Test", 800, 600, seed);
     let stage = Stage::new(base);
     Ok(stage)
 }
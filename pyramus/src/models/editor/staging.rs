@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
 
 use crate::models::templates::{
-    ids::InternalId, prop::Prop, prop_item::PropItem, transform::RelativeTransform,
+    effect::Effect, group::Group, ids::InternalId, prop::Prop, prop_item::PropItem,
+    transform::RelativeTransform,
 };
 
 use super::item::StageItem;
@@ -28,6 +29,12 @@ pub trait Staging {
 
     /// Get the relative transform of the item in the staging context, mutably
     fn get_relative_transform_mut(&mut self) -> &mut RelativeTransform;
+
+    /// Get the staging context of the item (for its opacity, visibility and blend mode)
+    fn get_staging_context(&self) -> &StagingContext;
+
+    /// Get the staging context of the item, mutably
+    fn get_staging_context_mut(&mut self) -> &mut StagingContext;
 }
 
 /// A struct that stores the staging information for an item, used by the Staging trait
@@ -36,6 +43,70 @@ pub struct StagingContext {
     pub parent: Option<InternalId>,
     pub children: Vec<InternalId>,
     pub transform: RelativeTransform,
+    /// Opacity of the item and its children (0.0 to 1.0)
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+    /// Hidden items (and their children) are not rendered, and cannot be clicked
+    #[serde(default)]
+    pub hidden: bool,
+    /// How the item is composited onto the items below it
+    #[serde(default)]
+    pub blend_mode: BlendMode,
+    /// Effects applied to the item and its children, in order
+    #[serde(default)]
+    pub effects: Vec<Effect>,
+    /// If set, the item is not drawn, but masks its children instead (text, shape and path items only)
+    #[serde(default)]
+    pub mask: Option<MaskMode>,
+    /// How clicks are tested against the item
+    #[serde(default)]
+    pub hit_test: HitTest,
+}
+
+fn default_opacity() -> f32 {
+    1.0
+}
+
+/// How an item is composited onto the items below it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+}
+
+/// How an item masks its children
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MaskMode {
+    /// Children are only drawn inside the filled area of the item
+    Clip,
+    /// Children are drawn with the opacity of the item (including its stroke and any gradients)
+    Alpha,
+}
+
+/// How clicks are tested against an item
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HitTest {
+    /// Anywhere within the bounds of the item
+    #[default]
+    Bounds,
+    /// Only on what is drawn: the fill and stroke of vectors, and the pixels of raster images
+    /// more opaque than `alpha_threshold` (0 to 255)
+    Precise { alpha_threshold: u8 },
 }
 
 impl Default for StagingContext {
@@ -50,6 +121,12 @@ impl StagingContext {
             parent: None,
             children: vec![],
             transform: RelativeTransform::default(),
+            opacity: 1.0,
+            hidden: false,
+            blend_mode: BlendMode::default(),
+            effects: vec![],
+            mask: None,
+            hit_test: HitTest::default(),
         }
     }
 }
@@ -60,6 +137,7 @@ impl Staging for StageItem {
         match self {
             StageItem::PropItem(item) => item.get_parent(),
             StageItem::Prop(prop) => prop.get_parent(),
+            StageItem::Group(group) => group.get_parent(),
         }
     }
 
@@ -67,6 +145,7 @@ impl Staging for StageItem {
         match self {
             StageItem::PropItem(item) => item.set_parent(parent),
             StageItem::Prop(prop) => prop.set_parent(parent),
+            StageItem::Group(group) => group.set_parent(parent),
         }
     }
 
@@ -74,6 +153,7 @@ impl Staging for StageItem {
         match self {
             StageItem::PropItem(item) => item.get_children(),
             StageItem::Prop(prop) => prop.get_children(),
+            StageItem::Group(group) => group.get_children(),
         }
     }
 
@@ -81,6 +161,7 @@ impl Staging for StageItem {
         match self {
             StageItem::PropItem(item) => item.get_children_mut(),
             StageItem::Prop(prop) => prop.get_children_mut(),
+            StageItem::Group(group) => group.get_children_mut(),
         }
     }
 
@@ -88,6 +169,7 @@ impl Staging for StageItem {
         match self {
             StageItem::PropItem(item) => item.get_relative_transform(),
             StageItem::Prop(prop) => prop.get_relative_transform(),
+            StageItem::Group(group) => group.get_relative_transform(),
         }
     }
 
@@ -95,6 +177,23 @@ impl Staging for StageItem {
         match self {
             StageItem::PropItem(item) => item.get_relative_transform_mut(),
             StageItem::Prop(prop) => prop.get_relative_transform_mut(),
+            StageItem::Group(group) => group.get_relative_transform_mut(),
+        }
+    }
+
+    fn get_staging_context(&self) -> &StagingContext {
+        match self {
+            StageItem::PropItem(item) => item.get_staging_context(),
+            StageItem::Prop(prop) => prop.get_staging_context(),
+            StageItem::Group(group) => group.get_staging_context(),
+        }
+    }
+
+    fn get_staging_context_mut(&mut self) -> &mut StagingContext {
+        match self {
+            StageItem::PropItem(item) => item.get_staging_context_mut(),
+            StageItem::Prop(prop) => prop.get_staging_context_mut(),
+            StageItem::Group(group) => group.get_staging_context_mut(),
         }
     }
 }
@@ -123,6 +222,48 @@ impl Staging for Prop {
     fn get_relative_transform_mut(&mut self) -> &mut RelativeTransform {
         &mut self.staging.transform
     }
+
+    fn get_staging_context(&self) -> &StagingContext {
+        &self.staging
+    }
+
+    fn get_staging_context_mut(&mut self) -> &mut StagingContext {
+        &mut self.staging
+    }
+}
+
+impl Staging for Group {
+    fn get_parent(&self) -> Option<InternalId> {
+        self.staging.parent
+    }
+
+    fn set_parent(&mut self, parent: Option<InternalId>) {
+        self.staging.parent = parent;
+    }
+
+    fn get_children(&self) -> &Vec<InternalId> {
+        &self.staging.children
+    }
+
+    fn get_children_mut(&mut self) -> &mut Vec<InternalId> {
+        &mut self.staging.children
+    }
+
+    fn get_relative_transform(&self) -> &RelativeTransform {
+        &self.staging.transform
+    }
+
+    fn get_relative_transform_mut(&mut self) -> &mut RelativeTransform {
+        &mut self.staging.transform
+    }
+
+    fn get_staging_context(&self) -> &StagingContext {
+        &self.staging
+    }
+
+    fn get_staging_context_mut(&mut self) -> &mut StagingContext {
+        &mut self.staging
+    }
 }
 
 impl Staging for PropItem {
@@ -149,4 +290,12 @@ impl Staging for PropItem {
     fn get_relative_transform_mut(&mut self) -> &mut RelativeTransform {
         &mut self.staging.transform
     }
+
+    fn get_staging_context(&self) -> &StagingContext {
+        &self.staging
+    }
+
+    fn get_staging_context_mut(&mut self) -> &mut StagingContext {
+        &mut self.staging
+    }
 }
@@ -1,28 +1,60 @@
 use crate::{
     models::templates::{
+        group::Group,
+        ids::InternalId,
+        paint::{Paint, Stroke},
+        path::{PathNode, PropItemPath},
         prop::Prop,
-        prop_item::{PropItem, PropItemImage},
+        prop_item::{PropItem, PropItemType},
     },
-    svg, PyramusError,
+    render::effects_to_usvg_filter,
+    PyramusError,
 };
 use glam::Affine2;
-use resvg::usvg::{self, Transform};
+use resvg::usvg::{self, tiny_skia_path};
+use std::{cell::RefCell, rc::Rc};
 
-use super::{base_item::Base, item::StageItem, staging::Staging};
+use super::{
+    base_item::{Base, BaseTemplate},
+    item::StageItem,
+    render_cache::RenderCache,
+    staging::{MaskMode, Staging, StagingContext},
+};
 
 /// Trait for converting items to usvg nodes
 pub trait ToUsvgNode {
-    /// Convert the item to a usvg node
-    fn to_usvg_node(&self, base: &Base) -> crate::Result<usvg::Node>;
+    /// Convert the item to a usvg node, drawn with its transform, staging and children from `template`
+    /// (This is cached in `cache`, as the staged node of the item)
+    fn to_usvg_node(
+        &self,
+        template: &BaseTemplate,
+        cache: &RenderCache,
+    ) -> crate::Result<usvg::Node>;
+    /// Convert what the item itself draws to a usvg node, without its transform, staging or children
+    /// (This is cached in `cache`, as the content of the item)
+    fn to_content_node(&self, cache: &RenderCache) -> crate::Result<usvg::Node>;
     /// Convert the item to the outline of a usvg node (for example, for displaying selection)
     fn to_outline_svg_node(&self, base: &Base) -> crate::Result<usvg::Node>;
 }
 
 impl ToUsvgNode for StageItem {
-    fn to_usvg_node(&self, outer_base_item: &Base) -> crate::Result<usvg::Node> {
+    fn to_usvg_node(
+        &self,
+        template: &BaseTemplate,
+        cache: &RenderCache,
+    ) -> crate::Result<usvg::Node> {
         match self {
-            StageItem::PropItem(item) => item.to_usvg_node(outer_base_item),
-            StageItem::Prop(prop) => prop.to_usvg_node(outer_base_item),
+            StageItem::PropItem(item) => item.to_usvg_node(template, cache),
+            StageItem::Prop(prop) => prop.to_usvg_node(template, cache),
+            StageItem::Group(group) => group.to_usvg_node(template, cache),
+        }
+    }
+
+    fn to_content_node(&self, cache: &RenderCache) -> crate::Result<usvg::Node> {
+        match self {
+            StageItem::PropItem(item) => item.to_content_node(cache),
+            StageItem::Prop(prop) => prop.to_content_node(cache),
+            StageItem::Group(group) => group.to_content_node(cache),
         }
     }
 
@@ -30,143 +62,289 @@ impl ToUsvgNode for StageItem {
         match self {
             StageItem::PropItem(item) => item.to_outline_svg_node(base),
             StageItem::Prop(prop) => prop.to_outline_svg_node(base),
+            StageItem::Group(group) => group.to_outline_svg_node(base),
         }
     }
 }
 
 impl ToUsvgNode for Prop {
     // TODO: This may be able to be abstracted in the way that to_outline_svg_node is done
-    fn to_usvg_node(&self, outer_base_item: &Base) -> crate::Result<usvg::Node> {
-        // TODO: Transforming is not done yet- doesnt inheret from parents, and also scaling seems to move the object
-        let transform = to_transform(self.get_relative_transform().to_glam_affine());
+    fn to_usvg_node(
+        &self,
+        template: &BaseTemplate,
+        cache: &RenderCache,
+    ) -> crate::Result<usvg::Node> {
+        cache.get_or_build_staged(self.id, || {
+            // Hidden items are not rendered, along with their children
+            if self.staging.hidden {
+                return Ok(empty_group());
+            }
+
+            // TODO: Transforming is not done yet- doesnt inheret from parents, and also scaling seems to move the object
+            let transform = to_transform(self.get_relative_transform().to_glam_affine());
+
+            let content = self.to_content_node(cache)?;
+            let mut children = vec![content];
 
+            // Children in scene other props, if any
+            children.extend(children_to_usvg_nodes(
+                self.get_children(),
+                template,
+                cache,
+            )?);
+
+            Ok(staged_group(&self.staging, transform, children))
+        })
+    }
+
+    fn to_content_node(&self, cache: &RenderCache) -> crate::Result<usvg::Node> {
         // Recursively add children to the root node
         // TODO: A slotmap may improve this, as we no longer need to hold a lock on the root node
         let root_id = self.template.root;
-        let root: &StageItem = self.template.items.get(&root_id).ok_or_else(|| {
-            PyramusError::OtherError("Root item not found in template".to_string())
-        })?;
+        let root: &StageItem = self
+            .template
+            .items
+            .get(&root_id)
+            .ok_or(PyramusError::ItemNotFound(root_id))?;
 
         // Create object- recursive, creates propitems within props
-        // We use this as our base item for the internal prop item recursion
-        // TODO: no clone, use reference, traits
-        let base = Base::new(self.clone().into());
-        let mut children = vec![root.to_usvg_node(&base)?];
-
-        // Children in scene other props, if any
-        for child in self.get_children() {
-            // simplify
-            let child = outer_base_item.get_items().get(child).ok_or_else(|| {
-                PyramusError::OtherError(format!("Child prop item not found in prop: {:?}", child))
-                // TODO: Not found could be abstracted
-            })?;
-            children.push(child.to_usvg_node(outer_base_item)?);
-        }
+        // The template only needs rebuilding when the prop is edited, so it is cached on the outer base,
+        // and its own items are only cached while it is built
+        cache.get_or_build_content(self.id, || {
+            let content = root.to_usvg_node(&self.template, &RenderCache::default())?;
 
-        Ok(usvg::Node::Group(Box::new(usvg::Group {
-            transform,
-            children,
-            ..Default::default()
-        })))
+            // The outline clips the template, but not the props placed on it
+            Ok(
+                match self
+                    .outline
+                    .as_ref()
+                    .and_then(|outline| outline.to_path(self.template.size))
+                {
+                    Some(path) => clipped_group(path, content),
+                    None => content,
+                },
+            )
+        })
     }
 
     fn to_outline_svg_node(&self, base: &Base) -> crate::Result<usvg::Node> {
-        let staged_item = base.get_item(self.id).unwrap(); // todo: shouldnt get a second one
-        let bounds = staged_item.get_local_bounds();
-        create_outline_svg(staged_item, base, bounds)
+        let staged_item = base
+            .get_item(self.id)
+            .ok_or(PyramusError::ItemNotFound(self.id))?; // todo: shouldnt get a second one
+        create_outline_svg(staged_item, base)
     }
 
     // Get bounds of node
 }
 
 impl ToUsvgNode for PropItem {
-    fn to_usvg_node(&self, outer_base_item: &Base) -> crate::Result<usvg::Node> {
-        crate::log!(
-            "Creating prop item node, with children: {:?}",
-            self.get_children()
-        );
-        // TODO: Transforming is not done yet- doesnt inheret from parents, and also scaling seems to move the object
-        let transform = to_transform(self.get_relative_transform().to_glam_affine());
-
-        // All nodes are contained in a group node, so we can apply the transform to the group node, and then apply the transform to the children nodes
-        // TODO: Is this needed?
-        let mut children = vec![self.item.to_usvg_node()?];
-        for child in self.get_children() {
-            // simplify
-            let child = outer_base_item.get_items().get(child).ok_or_else(|| {
-                PyramusError::OtherError("Child item not found in stage".to_string())
-            })?;
-            children.push(child.to_usvg_node(outer_base_item)?);
-        }
+    fn to_usvg_node(
+        &self,
+        template: &BaseTemplate,
+        cache: &RenderCache,
+    ) -> crate::Result<usvg::Node> {
+        cache.get_or_build_staged(self.id, || {
+            if self.staging.hidden {
+                return Ok(empty_group());
+            }
 
-        Ok(usvg::Node::Group(Box::new(usvg::Group {
-            transform,
-            children,
-            ..Default::default()
-        })))
+            // TODO: Transforming is not done yet- doesnt inheret from parents, and also scaling seems to move the object
+            let transform = to_transform(self.get_relative_transform().to_glam_affine());
+
+            // All nodes are contained in a group node, so we can apply the transform to the group node, and then apply the transform to the children nodes
+            // TODO: Is this needed?
+            let content = self.to_content_node(cache)?;
+            let children = children_to_usvg_nodes(self.get_children(), template, cache)?;
+
+            // A masking item is drawn only as the mask of its children
+            let children = match (self.staging.mask, &self.item) {
+                (
+                    Some(mode),
+                    PropItemType::Text(_) | PropItemType::Shape(_) | PropItemType::Path(_),
+                ) => {
+                    vec![masked_group(mode, content, children)]
+                }
+                _ => std::iter::once(content).chain(children).collect(),
+            };
+
+            Ok(staged_group(&self.staging, transform, children))
+        })
+    }
+
+    fn to_content_node(&self, cache: &RenderCache) -> crate::Result<usvg::Node> {
+        cache.get_or_build_content(self.id, || self.item.to_usvg_node())
+    }
+
+    fn to_outline_svg_node(&self, base: &Base) -> crate::Result<usvg::Node> {
+        let staged_item = base
+            .get_item(self.id)
+            .ok_or(PyramusError::ItemNotFound(self.id))?; // todo: shouldnt get a second one
+        create_outline_svg(staged_item, base)
+    }
+}
+
+impl ToUsvgNode for Group {
+    fn to_usvg_node(
+        &self,
+        template: &BaseTemplate,
+        cache: &RenderCache,
+    ) -> crate::Result<usvg::Node> {
+        cache.get_or_build_staged(self.id, || {
+            if self.staging.hidden {
+                return Ok(empty_group());
+            }
+
+            let transform = to_transform(self.get_relative_transform().to_glam_affine());
+            let children = children_to_usvg_nodes(self.get_children(), template, cache)?;
+            Ok(staged_group(&self.staging, transform, children))
+        })
+    }
+
+    fn to_content_node(&self, _cache: &RenderCache) -> crate::Result<usvg::Node> {
+        // A group is drawn only as its children
+        Ok(empty_group())
     }
 
     fn to_outline_svg_node(&self, base: &Base) -> crate::Result<usvg::Node> {
-        let staged_item = base.get_item(self.id).unwrap(); // todo: shouldnt get a second one
-        let bounds = staged_item.get_local_bounds();
-        create_outline_svg(staged_item, base, bounds)
+        let staged_item = base
+            .get_item(self.id)
+            .ok_or(PyramusError::ItemNotFound(self.id))?;
+        create_outline_svg(staged_item, base)
     }
 }
 
-fn to_transform(transform: Affine2) -> usvg::Transform {
+/// The staged nodes of the children of an item, in the order they are drawn
+fn children_to_usvg_nodes(
+    children: &[InternalId],
+    template: &BaseTemplate,
+    cache: &RenderCache,
+) -> crate::Result<Vec<usvg::Node>> {
+    children
+        .iter()
+        .map(|child| {
+            template
+                .items
+                .get(child)
+                .ok_or(PyramusError::ItemNotFound(*child))?
+                .to_usvg_node(template, cache)
+        })
+        .collect()
+}
+
+/// A group applying the opacity, blend mode and effects of a staged item to its children
+fn staged_group(
+    staging: &StagingContext,
+    transform: usvg::Transform,
+    children: Vec<usvg::Node>,
+) -> usvg::Node {
+    usvg::Node::Group(Box::new(usvg::Group {
+        transform,
+        opacity: usvg::Opacity::new_clamped(staging.opacity),
+        blend_mode: staging.blend_mode.into(),
+        filters: effects_to_usvg_filter(&staging.effects)
+            .into_iter()
+            .collect(),
+        children,
+        ..Default::default()
+    }))
+}
+
+/// A group of `children`, masked by `mask`
+fn masked_group(mode: MaskMode, mask: usvg::Node, children: Vec<usvg::Node>) -> usvg::Node {
+    let root = usvg::Group {
+        children: vec![mask],
+        ..Default::default()
+    };
+    let mut group = usvg::Group {
+        children,
+        ..Default::default()
+    };
+    match mode {
+        MaskMode::Clip => {
+            group.clip_path = Some(Rc::new(RefCell::new(usvg::ClipPath {
+                root,
+                ..Default::default()
+            })));
+        }
+        MaskMode::Alpha => {
+            group.mask = Some(Rc::new(RefCell::new(usvg::Mask {
+                id: String::new(),
+                // The mask region extends past the children by half their size on each side
+                units: usvg::Units::ObjectBoundingBox,
+                content_units: usvg::Units::UserSpaceOnUse,
+                rect: usvg::NonZeroRect::from_xywh(-0.5, -0.5, 2.0, 2.0)
+                    .expect("mask region is not a NonZeroRect"),
+                kind: usvg::MaskType::Alpha,
+                mask: None,
+                root,
+            })));
+        }
+    }
+    usvg::Node::Group(Box::new(group))
+}
+
+/// A group of `child`, clipped to the inside of `path`
+fn clipped_group(path: tiny_skia_path::Path, child: usvg::Node) -> usvg::Node {
+    let mut clip = usvg::Path::new(Rc::new(path));
+    // The same rule as `PropOutline::contains_point`, so clicks only hit what is drawn
+    clip.fill = Some(usvg::Fill {
+        rule: usvg::FillRule::EvenOdd,
+        ..Default::default()
+    });
+    let clip_path = usvg::ClipPath {
+        root: usvg::Group {
+            children: vec![usvg::Node::Path(Box::new(clip))],
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    usvg::Node::Group(Box::new(usvg::Group {
+        clip_path: Some(Rc::new(RefCell::new(clip_path))),
+        children: vec![child],
+        ..Default::default()
+    }))
+}
+
+fn empty_group() -> usvg::Node {
+    usvg::Node::Group(Box::default())
+}
+
+pub(crate) fn to_transform(transform: Affine2) -> usvg::Transform {
     let cols = transform.to_cols_array();
     usvg::Transform::from_row(cols[0], cols[1], cols[2], cols[3], cols[4], cols[5])
 }
 
-fn create_outline_svg(
-    staged_item: &StageItem,
-    base: &Base,
-    (x0, y0, x1, y1): (f32, f32, f32, f32),
-) -> crate::Result<usvg::Node> {
+fn create_outline_svg(staged_item: &StageItem, base: &Base) -> crate::Result<usvg::Node> {
     let outline_size = 20.0;
-    // Get bounds of node
-    // TODO: NEed consistency between x1x2 and xywh formats
 
-    // TODO: This is terrible, and should be done via traits
-    let transform = to_transform(staged_item.get_screen_transform(base));
+    let (x0, y0, x1, y1) = staged_item.get_content_bounds(base)?;
+    if x1 < x0 || y1 < y0 {
+        crate::log!("Invalid size to_outline_svg_node: {x0}, {y0}, {x1}, {y1}");
+        return Err(PyramusError::InvalidSize(x1 - x0, y1 - y0));
+    }
+
+    // The outline is drawn in screen space around the oriented bounds, so it follows the rotation of the item
+    // but keeps the same width however the item is scaled
+    let [c0, c1, c2, c3] = staged_item.get_oriented_bounds(base)?;
+    let along = (c1 - c0).normalize_or_zero();
+    let across = (c3 - c0).normalize_or_zero();
 
-    let x0 = x0 - outline_size;
-    let y0 = y0 - outline_size;
-    let x1 = x1 + outline_size;
-    let y1 = y1 + outline_size;
+    // The stroke is centered on the path, so it is pushed out by half its width to sit just outside the bounds
+    let inset = outline_size / 2.0;
+    let nodes = [
+        c0 - (along + across) * inset,
+        c1 + (along - across) * inset,
+        c2 + (along + across) * inset,
+        c3 - (along - across) * inset,
+    ]
+    .map(|corner| PathNode::corner(corner.x, corner.y))
+    .to_vec();
 
-    crate::log!(
-        "Outline for : {x0} {y0}, {x1} {y1}, width: {w}, height: {h}",
-        w = x1 - x0,
-        h = y1 - y0
+    let mut outline = PropItemPath::stroked(
+        nodes,
+        Stroke::solid(Paint::Solid((0, 0, 255, 255)), outline_size),
     );
-    let image = usvg::Node::Image(Box::new(usvg::Image {
-        id: String::new(),
-        abs_transform: Transform::identity(), // Set on postprocessing, not here
-        bounding_box: None,
-        visibility: usvg::Visibility::Visible,
-        view_box: usvg::ViewBox {
-            rect: usvg::NonZeroRect::from_ltrb(x0, y0, x1, y1).ok_or_else(|| {
-                crate::log!("Invalid size to_outline_svg_node: {x0}, {y0}, {x1}, {y1}");
-                PyramusError::InvalidSize(x1 - x0, y1 - y0)
-            })?,
-            aspect: usvg::AspectRatio::default(),
-        },
-        rendering_mode: usvg::ImageRendering::OptimizeSpeed,
-        kind: PropItemImage::from_svg_string(&svg::build_svg_rect(
-            (x1 - x0) as u32,
-            (y1 - y0) as u32,
-            "blue",
-            Some(outline_size as u32),
-            0.5,
-        ))?
-        .data
-        .into(),
-    }));
-
-    Ok(usvg::Node::Group(Box::new(usvg::Group {
-        transform,
-        children: vec![image],
-        ..Default::default()
-    })))
+    outline.closed = true;
+    outline.fill = Some(Paint::Solid((0, 0, 255, 128)));
+    Ok(outline.to_usvg_node())
 }
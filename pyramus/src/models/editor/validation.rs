@@ -0,0 +1,702 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::Cursor,
+};
+
+use image::io::Reader as ImageReader;
+
+use crate::models::templates::{
+    adjustment::ImageAdjustments,
+    ids::InternalId,
+    paint::validate_style,
+    prop_item::{PropItemImage, PropItemImageData, PropItemType},
+    transform::RelativeTransform,
+};
+
+use super::{
+    base_item::{Base, BaseTemplate, IdIssue},
+    item::StageItem,
+    staging::Staging,
+};
+
+/// A problem found when validating a Base (see `Base::validate`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// The chain of nested props leading to the template containing the problem
+    /// Empty if the problem is in the template of the Base itself
+    pub path: Vec<InternalId>,
+    pub kind: DiagnosticKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiagnosticKind {
+    /// The root item of the template does not exist
+    MissingRoot { root: InternalId },
+    /// The root item of the template has a parent
+    RootHasParent {
+        root: InternalId,
+        parent: InternalId,
+    },
+    /// A duplicate, dangling or mismatched ID
+    Id(IdIssue),
+    /// An item is listed as a child of `listed_by`, but its own parent is `parent`
+    ParentMismatch {
+        id: InternalId,
+        listed_by: InternalId,
+        parent: Option<InternalId>,
+    },
+    /// An item has a parent, but is not listed among that parent's children
+    NotInParentChildren { id: InternalId, parent: InternalId },
+    /// Following children from this item leads back to the item itself
+    Cycle { id: InternalId },
+    /// The item cannot be reached from the root of the template
+    Orphan { id: InternalId },
+    /// The transform of the item contains a NaN or infinite value
+    NonFiniteTransform { id: InternalId },
+    /// The viewport of an image item is not a finite, positive size
+    InvalidImageViewport {
+        id: InternalId,
+        width: f32,
+        height: f32,
+    },
+    /// The data of an image item is empty or does not match its format
+    InvalidImageData { id: InternalId },
+    /// The crop of an image item is not finite, or has no area
+    InvalidImageCrop { id: InternalId },
+    /// The color adjustments of an image item are out of range (see `ImageAdjustments::validate`)
+    InvalidImageAdjustments { id: InternalId },
+    /// The fill or stroke of an item cannot be rendered (see `Paint::validate`)
+    InvalidPaint { id: InternalId },
+    /// An effect of an item cannot be rendered (see `Effect::validate`)
+    InvalidEffect { id: InternalId, index: usize },
+    /// The outline of a prop cannot be used (see `PropOutline::validate`)
+    InvalidOutline { id: InternalId },
+}
+
+/// The result of `Base::validate_and_repair`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepairReport {
+    /// The problems found before repairing (the same as `Base::validate`)
+    pub found: Vec<Diagnostic>,
+    /// The problems still found after repairing (empty if the repair succeeded)
+    pub remaining: Vec<Diagnostic>,
+}
+
+impl Base {
+    /// Check the integrity of the item graph of the base (and any nested props).
+    /// Returns an empty list if no problems are found.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        validate_template(self.get_template(), &[], &mut diagnostics);
+        diagnostics
+    }
+
+    /// Check the integrity of the item graph, and repair any problems that can be repaired:
+    /// - the tree is rebuilt from the children lists, dropping dangling, duplicate and cyclic links
+    /// - orphaned items are re-attached to the root
+    /// - non-finite transforms are reset, and image viewports are restored from the image data
+    /// - image data labelled with the wrong format is relabelled, and invalid crops and adjustments are removed
+    /// - fills, strokes and effects that cannot be rendered are removed
+    /// - invalid prop outlines are removed
+    ///
+    /// The base is validated again after repairing, to report anything that is still wrong.
+    /// Missing roots with no replacement and unreadable image data cannot be repaired.
+    pub fn validate_and_repair(&mut self) -> RepairReport {
+        let found = self.validate();
+        if found.is_empty() {
+            return RepairReport {
+                found,
+                remaining: Vec::new(),
+            };
+        }
+        repair_template(self.get_template_mut());
+        RepairReport {
+            found,
+            remaining: self.validate(),
+        }
+    }
+}
+
+fn validate_template(template: &BaseTemplate, path: &[InternalId], out: &mut Vec<Diagnostic>) {
+    let mut push = |kind| {
+        out.push(Diagnostic {
+            path: path.to_vec(),
+            kind,
+        })
+    };
+
+    match template.items.get(&template.root) {
+        None => push(DiagnosticKind::MissingRoot {
+            root: template.root,
+        }),
+        Some(root) => {
+            if let Some(parent) = root.get_parent() {
+                push(DiagnosticKind::RootHasParent {
+                    root: template.root,
+                    parent,
+                });
+            }
+        }
+    }
+
+    // The missing root is already reported above
+    for issue in template.validate_ids() {
+        if !matches!(
+            issue,
+            IdIssue::Dangling {
+                referenced_by: None,
+                ..
+            }
+        ) {
+            push(DiagnosticKind::Id(issue));
+        }
+    }
+
+    // Sorted, so diagnostics are reported in a consistent order
+    let mut ids = template.items.keys().copied().collect::<Vec<_>>();
+    ids.sort();
+
+    for id in &ids {
+        let item = &template.items[id];
+        for child_id in item.get_children() {
+            let Some(child) = template.items.get(child_id) else {
+                continue;
+            };
+            if child.get_parent() != Some(*id) {
+                push(DiagnosticKind::ParentMismatch {
+                    id: *child_id,
+                    listed_by: *id,
+                    parent: child.get_parent(),
+                });
+            }
+        }
+
+        if let Some(parent_id) = item.get_parent() {
+            if let Some(parent) = template.items.get(&parent_id) {
+                if !parent.get_children().contains(id) {
+                    push(DiagnosticKind::NotInParentChildren {
+                        id: *id,
+                        parent: parent_id,
+                    });
+                }
+            }
+        }
+
+        if !item.get_relative_transform().is_finite() {
+            push(DiagnosticKind::NonFiniteTransform { id: *id });
+        }
+
+        for (index, effect) in item.get_staging_context().effects.iter().enumerate() {
+            if effect.validate().is_err() {
+                push(DiagnosticKind::InvalidEffect { id: *id, index });
+            }
+        }
+
+        if let StageItem::PropItem(prop_item) = item {
+            if let PropItemType::Image(image) = &prop_item.item {
+                let (width, height) = (image.viewport_width, image.viewport_height);
+                if !(width.is_finite() && height.is_finite() && width > 0.0 && height > 0.0) {
+                    push(DiagnosticKind::InvalidImageViewport {
+                        id: *id,
+                        width,
+                        height,
+                    });
+                }
+                if !has_valid_data(image) {
+                    push(DiagnosticKind::InvalidImageData { id: *id });
+                }
+                if image.crop.is_some_and(|crop| crop.validate().is_err()) {
+                    push(DiagnosticKind::InvalidImageCrop { id: *id });
+                }
+                if image.adjustments.validate().is_err() {
+                    push(DiagnosticKind::InvalidImageAdjustments { id: *id });
+                }
+            }
+            if let Some((fill, stroke)) = prop_item.item.get_style() {
+                if validate_style(fill, stroke).is_err() {
+                    push(DiagnosticKind::InvalidPaint { id: *id });
+                }
+            }
+        }
+
+        if let StageItem::Prop(prop) = item {
+            if prop
+                .outline
+                .as_ref()
+                .is_some_and(|outline| outline.validate().is_err())
+            {
+                push(DiagnosticKind::InvalidOutline { id: *id });
+            }
+        }
+    }
+
+    for id in find_cycles(template, &ids) {
+        push(DiagnosticKind::Cycle { id });
+    }
+
+    let reachable = reachable_from_root(template);
+    for id in &ids {
+        if !reachable.contains(id) {
+            push(DiagnosticKind::Orphan { id: *id });
+        }
+    }
+
+    // Nested props have templates of their own
+    for id in &ids {
+        if let StageItem::Prop(prop) = &template.items[id] {
+            let path = path.iter().copied().chain([*id]).collect::<Vec<_>>();
+            validate_template(&prop.template, &path, out);
+        }
+    }
+}
+
+fn repair_template(template: &mut BaseTemplate) {
+    // Items are referenced by the key they are stored under
+    for (key, item) in template.items.iter_mut() {
+        item.set_id(*key);
+    }
+
+    let mut ids = template.items.keys().copied().collect::<Vec<_>>();
+    ids.sort();
+
+    // Replace a missing root with the first item without a parent
+    if !template.items.contains_key(&template.root) {
+        let replacement = ids.iter().copied().find(|id| {
+            let parent = template.items[id].get_parent();
+            !parent.is_some_and(|parent| template.items.contains_key(&parent))
+        });
+        match replacement {
+            Some(root) => template.root = root,
+            None => return,
+        }
+    }
+
+    // Rebuild the parents from the children lists, walking down from the root.
+    // Each item is kept only under the first parent that reaches it, which drops dangling, duplicate and cyclic links.
+    let original_parents = ids
+        .iter()
+        .map(|id| (*id, template.items[id].get_parent()))
+        .collect::<HashMap<_, _>>();
+    let mut visited = HashSet::new();
+    relink_subtree(template, template.root, None, &mut visited);
+
+    // Re-attach orphans to the root, keeping orphaned subtrees together where possible
+    let root = template.root;
+    let (subtree_roots, rest): (Vec<_>, Vec<_>) = ids
+        .iter()
+        .copied()
+        .filter(|id| !visited.contains(id))
+        .partition(|id| {
+            !original_parents[id].is_some_and(|parent| {
+                !visited.contains(&parent) && template.items.contains_key(&parent)
+            })
+        });
+    for id in subtree_roots.into_iter().chain(rest) {
+        if visited.contains(&id) {
+            continue;
+        }
+        relink_subtree(template, id, Some(root), &mut visited);
+        if let Some(root) = template.items.get_mut(&root) {
+            root.get_children_mut().push(id);
+        }
+    }
+
+    for item in template.items.values_mut() {
+        if !item.get_relative_transform().is_finite() {
+            *item.get_relative_transform_mut() = RelativeTransform::default();
+        }
+        item.get_staging_context_mut()
+            .effects
+            .retain(|effect| effect.validate().is_ok());
+
+        match item {
+            StageItem::PropItem(prop_item) => {
+                if let PropItemType::Image(image) = &mut prop_item.item {
+                    let (width, height) = (image.viewport_width, image.viewport_height);
+                    let valid =
+                        width.is_finite() && height.is_finite() && width > 0.0 && height > 0.0;
+                    if let Some((width, height)) = data_size(&image.data).filter(|_| !valid) {
+                        image.viewport_width = width;
+                        image.viewport_height = height;
+                    }
+                    relabel_data(&mut image.data);
+                    if image.crop.is_some_and(|crop| crop.validate().is_err()) {
+                        image.crop = None;
+                    }
+                    if image.adjustments.validate().is_err() {
+                        image.adjustments = ImageAdjustments::default();
+                    }
+                }
+                if let Some((fill, stroke)) = prop_item.item.get_style_mut() {
+                    if fill.as_ref().is_some_and(|fill| fill.validate().is_err()) {
+                        *fill = None;
+                    }
+                    if stroke
+                        .as_ref()
+                        .is_some_and(|stroke| stroke.validate().is_err())
+                    {
+                        *stroke = None;
+                    }
+                }
+            }
+            StageItem::Prop(prop) => {
+                if prop
+                    .outline
+                    .as_ref()
+                    .is_some_and(|outline| outline.validate().is_err())
+                {
+                    prop.outline = None;
+                }
+                repair_template(&mut prop.template)
+            }
+            // Groups have nothing to repair beyond their staging
+            StageItem::Group(_) => {}
+        }
+    }
+}
+
+/// Set the parent of `id`, then recursively keep only the children that exist and have not yet been visited
+fn relink_subtree(
+    template: &mut BaseTemplate,
+    id: InternalId,
+    parent: Option<InternalId>,
+    visited: &mut HashSet<InternalId>,
+) {
+    visited.insert(id);
+    let Some(item) = template.items.get_mut(&id) else {
+        return;
+    };
+    item.set_parent(parent);
+
+    let mut children = Vec::new();
+    for child in item.get_children().clone() {
+        if template.items.contains_key(&child) && !visited.contains(&child) {
+            relink_subtree(template, child, Some(id), visited);
+            children.push(child);
+        }
+    }
+    if let Some(item) = template.items.get_mut(&id) {
+        *item.get_children_mut() = children;
+    }
+}
+
+/// Find items that lead back to themselves by following children
+/// Each cycle is reported once, at the item where it is first closed
+fn find_cycles(template: &BaseTemplate, ids: &[InternalId]) -> Vec<InternalId> {
+    fn visit(
+        template: &BaseTemplate,
+        id: InternalId,
+        in_progress: &mut HashSet<InternalId>,
+        done: &mut HashSet<InternalId>,
+        cycles: &mut Vec<InternalId>,
+    ) {
+        in_progress.insert(id);
+        if let Some(item) = template.items.get(&id) {
+            for child in item.get_children() {
+                if in_progress.contains(child) {
+                    cycles.push(*child);
+                } else if !done.contains(child) {
+                    visit(template, *child, in_progress, done, cycles);
+                }
+            }
+        }
+        in_progress.remove(&id);
+        done.insert(id);
+    }
+
+    let mut cycles = Vec::new();
+    let mut done = HashSet::new();
+    for id in ids {
+        if !done.contains(id) {
+            visit(template, *id, &mut HashSet::new(), &mut done, &mut cycles);
+        }
+    }
+    cycles
+}
+
+fn reachable_from_root(template: &BaseTemplate) -> HashSet<InternalId> {
+    let mut reachable = HashSet::new();
+    let mut to_visit = vec![template.root];
+    while let Some(id) = to_visit.pop() {
+        let Some(item) = template.items.get(&id) else {
+            continue;
+        };
+        if reachable.insert(id) {
+            to_visit.extend(item.get_children().iter().copied());
+        }
+    }
+    reachable
+}
+
+/// Whether the raster bytes of an image are non-empty and match their declared format
+fn has_valid_data(image: &PropItemImage) -> bool {
+    let (bytes, expected) = match &image.data {
+        PropItemImageData::Png(bytes) => (bytes, image::ImageFormat::Png),
+        PropItemImageData::Jpeg(bytes) => (bytes, image::ImageFormat::Jpeg),
+        PropItemImageData::Gif(bytes) => (bytes, image::ImageFormat::Gif),
+        // Svg trees are parsed (and so validated) when deserialized
+        PropItemImageData::Svg(_) => return true,
+    };
+    image::guess_format(bytes).is_ok_and(|format| format == expected)
+}
+
+/// Relabel raster bytes whose declared format does not match their actual (supported) format
+fn relabel_data(data: &mut PropItemImageData) {
+    let bytes = match data {
+        PropItemImageData::Png(bytes)
+        | PropItemImageData::Jpeg(bytes)
+        | PropItemImageData::Gif(bytes) => bytes.clone(),
+        PropItemImageData::Svg(_) => return,
+    };
+    *data = match image::guess_format(&bytes) {
+        Ok(image::ImageFormat::Png) => PropItemImageData::Png(bytes),
+        Ok(image::ImageFormat::Jpeg) => PropItemImageData::Jpeg(bytes),
+        Ok(image::ImageFormat::Gif) => PropItemImageData::Gif(bytes),
+        _ => return,
+    };
+}
+
+/// The size of the image described by the image data, if it can be read
+fn data_size(data: &PropItemImageData) -> Option<(f32, f32)> {
+    let bytes = match data {
+        PropItemImageData::Png(bytes)
+        | PropItemImageData::Jpeg(bytes)
+        | PropItemImageData::Gif(bytes) => bytes,
+        PropItemImageData::Svg(tree) => {
+            return Some((tree.size.width(), tree.size.height()));
+        }
+    };
+    let (width, height) = ImageReader::new(Cursor::new(bytes.as_slice()))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()?;
+    Some((width as f32, height as f32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::editor::base_item::BASE_FORMAT_VERSION;
+    use crate::models::templates::{
+        builder::ItemBuilder,
+        prop::Prop,
+        shape::{PropItemShape, Shape},
+    };
+
+    fn rect(position: (f32, f32)) -> ItemBuilder {
+        let shape = Shape::Rectangle {
+            width: 10.0,
+            height: 10.0,
+        };
+        ItemBuilder::build_shape(PropItemShape::filled(shape, (0, 0, 0))).transform(
+            RelativeTransform {
+                position,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// A base with two items, `b` inside of `a`
+    fn build_base() -> (Base, InternalId, InternalId) {
+        let mut base = Base::new(Prop::new(InternalId::UNCONTAINED, "Test", 100, 100).into());
+        let a = base.add_child(rect((10.0, 10.0))).unwrap();
+        let b = base.add_child(rect((5.0, 5.0)).parent(a)).unwrap();
+        (base, a, b)
+    }
+
+    fn found(base: &Base, kind: DiagnosticKind) -> bool {
+        base.validate()
+            .iter()
+            .any(|diagnostic| diagnostic.kind == kind)
+    }
+
+    fn children(base: &Base, id: InternalId) -> Vec<InternalId> {
+        base.get_item(id).unwrap().get_children().clone()
+    }
+
+    #[test]
+    fn valid_base_has_nothing_to_repair() {
+        let (mut base, _, _) = build_base();
+        let report = base.validate_and_repair();
+        assert!(report.found.is_empty());
+        assert!(report.remaining.is_empty());
+    }
+
+    #[test]
+    fn dangling_child_is_dropped() {
+        let (mut base, a, _) = build_base();
+        let root = base.get_root();
+        let missing = InternalId(99);
+        base.get_template_mut()
+            .items
+            .get_mut(&root)
+            .unwrap()
+            .get_children_mut()
+            .push(missing);
+        assert!(found(
+            &base,
+            DiagnosticKind::Id(IdIssue::Dangling {
+                id: missing,
+                referenced_by: Some(root),
+            })
+        ));
+
+        let report = base.validate_and_repair();
+        assert!(report.remaining.is_empty());
+        assert_eq!(children(&base, root), [a]);
+    }
+
+    #[test]
+    fn duplicate_child_is_kept_under_first_parent() {
+        let (mut base, a, b) = build_base();
+        let root = base.get_root();
+        base.get_template_mut()
+            .items
+            .get_mut(&root)
+            .unwrap()
+            .get_children_mut()
+            .push(b);
+        assert!(found(
+            &base,
+            DiagnosticKind::Id(IdIssue::DuplicateChild { id: b })
+        ));
+
+        let report = base.validate_and_repair();
+        assert!(report.remaining.is_empty());
+        assert_eq!(children(&base, root), [a]);
+        assert_eq!(children(&base, a), [b]);
+        assert_eq!(base.get_item(b).unwrap().get_parent(), Some(a));
+    }
+
+    #[test]
+    fn cycle_is_broken() {
+        let (mut base, a, b) = build_base();
+        base.get_template_mut()
+            .items
+            .get_mut(&b)
+            .unwrap()
+            .get_children_mut()
+            .push(a);
+        assert!(found(&base, DiagnosticKind::Cycle { id: a }));
+
+        let report = base.validate_and_repair();
+        assert!(report.remaining.is_empty());
+        assert_eq!(children(&base, a), [b]);
+        assert!(children(&base, b).is_empty());
+        assert!(base.get_render_order().is_ok());
+    }
+
+    #[test]
+    fn missing_root_is_replaced() {
+        let (mut base, a, b) = build_base();
+        let root = base.get_root();
+        base.get_template_mut().items.remove(&root);
+        assert!(found(&base, DiagnosticKind::MissingRoot { root }));
+
+        let report = base.validate_and_repair();
+        assert!(report.remaining.is_empty());
+        assert_eq!(base.get_root(), a);
+        assert_eq!(base.get_item(a).unwrap().get_parent(), None);
+        assert_eq!(children(&base, a), [b]);
+    }
+
+    #[test]
+    fn missing_root_with_no_replacement_remains() {
+        let (mut base, _, _) = build_base();
+        let root = base.get_root();
+        base.get_template_mut().items.clear();
+
+        let report = base.validate_and_repair();
+        assert_eq!(
+            report.remaining,
+            [Diagnostic {
+                path: vec![],
+                kind: DiagnosticKind::MissingRoot { root },
+            }]
+        );
+    }
+
+    #[test]
+    fn non_finite_transform_is_reset() {
+        let (mut base, a, _) = build_base();
+        base.get_template_mut()
+            .items
+            .get_mut(&a)
+            .unwrap()
+            .get_relative_transform_mut()
+            .position
+            .0 = f32::NAN;
+        assert!(found(&base, DiagnosticKind::NonFiniteTransform { id: a }));
+
+        let report = base.validate_and_repair();
+        assert!(report.remaining.is_empty());
+        let transform = base.get_item(a).unwrap().get_relative_transform();
+        assert_eq!(transform.position, (0.0, 0.0));
+    }
+
+    // Version 1 documents only recorded children, so their parents are None
+    #[test]
+    fn legacy_parent_links_keep_their_layout() {
+        let (mut base, a, b) = build_base();
+        let root = base.get_root();
+        let unlisted = base.add_child(rect((0.0, 0.0))).unwrap();
+        let template = base.get_template_mut();
+        for item in template.items.values_mut() {
+            item.set_parent(None);
+        }
+        template
+            .items
+            .get_mut(&root)
+            .unwrap()
+            .get_children_mut()
+            .retain(|id| *id != unlisted);
+        base.version = 1;
+        let json = serde_json::to_string(&base).unwrap();
+
+        let (base, repaired) = Base::load(&json).unwrap();
+        assert!(repaired.is_empty());
+        assert_eq!(base.version, BASE_FORMAT_VERSION);
+        assert_eq!(base.get_item(b).unwrap().get_parent(), Some(a));
+        let screen = base
+            .get_item(b)
+            .unwrap()
+            .get_screen_transform(&base)
+            .unwrap();
+        assert_eq!(screen.translation, glam::Vec2::new(15.0, 15.0));
+
+        // Unlisted items were never drawn, so they are kept, hidden
+        let unlisted = base.get_item(unlisted).unwrap();
+        assert_eq!(unlisted.get_parent(), Some(root));
+        assert!(!unlisted.is_visible(&base).unwrap());
+    }
+
+    #[test]
+    fn shipped_legacy_document_loads() {
+        let json = include_str!("../../../../res/1n.json");
+        let (base, repaired) = Base::load(json).unwrap();
+        assert!(repaired.is_empty());
+        assert!(base.validate().is_empty());
+
+        let root = base.get_root();
+        let drawn = base.get_item(InternalId(1797196509)).unwrap();
+        assert_eq!(drawn.get_parent(), Some(root));
+        assert!(drawn.is_visible(&base).unwrap());
+        let unlisted = base.get_item(InternalId(994355459)).unwrap();
+        assert!(!unlisted.is_visible(&base).unwrap());
+    }
+
+    #[test]
+    fn duplicate_item_ids_are_rejected() {
+        let (base, a, _) = build_base();
+        let root = base.get_root();
+        let item = |id: InternalId| serde_json::to_string(base.get_item(id).unwrap()).unwrap();
+        let (root_json, a_json) = (item(root), item(a));
+        let json = format!(
+            r#"{{"items":{{"{}":{root_json},"{}":{a_json},"{}":{a_json}}},"size":[100,100],"root":{}}}"#,
+            root.0, a.0, a.0, root.0
+        );
+        let error = serde_json::from_str::<BaseTemplate>(&json).unwrap_err();
+        assert!(error.to_string().contains("duplicate item ID"));
+    }
+}
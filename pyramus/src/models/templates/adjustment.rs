@@ -0,0 +1,229 @@
+use std::{cell::RefCell, io::Cursor, sync::Arc};
+
+use image::{DynamicImage, ImageFormat, Rgba};
+use serde::{Deserialize, Serialize};
+
+use crate::InvalidReason;
+
+/// Non-destructive color adjustments of a raster image
+/// The image data is kept as imported- adjustments are applied when rendering, in the order of the fields
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageAdjustments {
+    /// Between -1.0 (black) and 1.0 (white)
+    pub brightness: f32,
+    /// Between -1.0 (flat gray) and 1.0 (double contrast)
+    pub contrast: f32,
+    /// Between -1.0 (grayscale) and 1.0 (double saturation)
+    pub saturation: f32,
+    /// Rotation of the hue, in degrees
+    pub hue_shift: f32,
+    /// Recolor the image with a single color, keeping its lightness
+    pub tint: Option<Tint>,
+    /// Make pixels lighter than this (between 0.0 and 1.0) white, and the rest black
+    pub threshold: Option<f32>,
+    pub invert: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Tint {
+    pub color: (u8, u8, u8),
+    /// Between 0.0 (no tint) and 1.0 (fully tinted)
+    pub amount: f32,
+}
+
+impl Default for ImageAdjustments {
+    fn default() -> Self {
+        ImageAdjustments {
+            brightness: 0.0,
+            contrast: 0.0,
+            saturation: 0.0,
+            hue_shift: 0.0,
+            tint: None,
+            threshold: None,
+            invert: false,
+        }
+    }
+}
+
+impl ImageAdjustments {
+    /// Check that the adjustments are within their ranges
+    pub fn validate(&self) -> crate::Result<()> {
+        let ranges = [
+            (self.brightness, "brightness"),
+            (self.contrast, "contrast"),
+            (self.saturation, "saturation"),
+        ];
+        for (value, name) in ranges {
+            if !(-1.0..=1.0).contains(&value) {
+                return invalid_adjustment(
+                    name,
+                    InvalidReason::OutOfRange {
+                        min: -1.0,
+                        max: 1.0,
+                    },
+                );
+            }
+        }
+        if !self.hue_shift.is_finite() {
+            return invalid_adjustment("hue_shift", InvalidReason::NotFinite);
+        }
+        if self
+            .tint
+            .is_some_and(|tint| !(0.0..=1.0).contains(&tint.amount))
+        {
+            return invalid_adjustment(
+                "tint_amount",
+                InvalidReason::OutOfRange { min: 0.0, max: 1.0 },
+            );
+        }
+        if self
+            .threshold
+            .is_some_and(|threshold| !(0.0..=1.0).contains(&threshold))
+        {
+            return invalid_adjustment(
+                "threshold",
+                InvalidReason::OutOfRange { min: 0.0, max: 1.0 },
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether the adjustments leave the image unchanged
+    pub fn is_identity(&self) -> bool {
+        *self == ImageAdjustments::default()
+    }
+
+    /// Adjust an encoded image, returning the result encoded as PNG
+    /// The latest result for each image is cached, so rendering an unchanged image again does not reprocess it
+    pub fn apply(&self, data: &Arc<Vec<u8>>) -> crate::Result<Arc<Vec<u8>>> {
+        let cached = ADJUSTED.with(|adjusted| {
+            adjusted
+                .borrow()
+                .iter()
+                .find(|entry| Arc::ptr_eq(&entry.source, data) && entry.adjustments == *self)
+                .map(|entry| entry.result.clone())
+        });
+        if let Some(result) = cached {
+            return Ok(result);
+        }
+
+        let mut image = image::load_from_memory(data)?.into_rgba8();
+        let matrix = self.color_matrix();
+        for pixel in image.pixels_mut() {
+            self.adjust_pixel(&matrix, pixel);
+        }
+        let mut bytes = Vec::new();
+        DynamicImage::from(image).write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+        let result = Arc::new(bytes);
+
+        ADJUSTED.with(|adjusted| {
+            let mut adjusted = adjusted.borrow_mut();
+            // Only the latest adjustments of an image are kept, so dragging a slider does not keep every value tried
+            // Images that are no longer held by anything but the cache will never be looked up again
+            adjusted.retain(|entry| {
+                !Arc::ptr_eq(&entry.source, data) && Arc::strong_count(&entry.source) > 1
+            });
+            adjusted.push(AdjustedImage {
+                source: data.clone(),
+                adjustments: *self,
+                result: result.clone(),
+            });
+        });
+        Ok(result)
+    }
+
+    /// The brightness, contrast, saturation and hue shift combined into one affine color transform
+    /// (a 3x3 matrix on the rgb channels, and an offset)
+    fn color_matrix(&self) -> ([[f32; 3]; 3], f32) {
+        let [lr, lg, lb] = LUMA;
+
+        let s = 1.0 + self.saturation;
+        #[rustfmt::skip]
+        let saturate = [
+            [lr + (1.0 - lr) * s, lg - lg * s, lb - lb * s],
+            [lr - lr * s, lg + (1.0 - lg) * s, lb - lb * s],
+            [lr - lr * s, lg - lg * s, lb + (1.0 - lb) * s],
+        ];
+
+        // The hueRotate matrix of feColorMatrix
+        let (sin, cos) = self.hue_shift.to_radians().sin_cos();
+        #[rustfmt::skip]
+        let rotate = [
+            [0.213 + cos * 0.787 - sin * 0.213, 0.715 - cos * 0.715 - sin * 0.715, 0.072 - cos * 0.072 + sin * 0.928],
+            [0.213 - cos * 0.213 + sin * 0.143, 0.715 + cos * 0.285 + sin * 0.140, 0.072 - cos * 0.072 - sin * 0.283],
+            [0.213 - cos * 0.213 - sin * 0.787, 0.715 - cos * 0.715 + sin * 0.715, 0.072 + cos * 0.928 + sin * 0.072],
+        ];
+
+        let mut matrix = [[0.0; 3]; 3];
+        for (row, out) in matrix.iter_mut().enumerate() {
+            for (col, value) in out.iter_mut().enumerate() {
+                *value = (0..3).map(|k| rotate[row][k] * saturate[k][col]).sum();
+            }
+        }
+
+        // Brightness and contrast are applied before the matrix, so fold them in:
+        // c' = (c + brightness - 0.5) * contrast + 0.5, and the matrix preserves gray so it preserves the offset
+        let contrast = 1.0 + self.contrast;
+        let offset = (self.brightness - 0.5) * contrast + 0.5;
+        for row in matrix.iter_mut() {
+            for value in row.iter_mut() {
+                *value *= contrast;
+            }
+        }
+        (matrix, offset)
+    }
+
+    fn adjust_pixel(&self, (matrix, offset): &([[f32; 3]; 3], f32), pixel: &mut Rgba<u8>) {
+        let input = [0, 1, 2].map(|i| pixel[i] as f32 / 255.0);
+        let mut rgb = [0, 1, 2].map(|row| {
+            let value: f32 = (0..3).map(|col| matrix[row][col] * input[col]).sum();
+            (value + offset).clamp(0.0, 1.0)
+        });
+
+        if let Some(Tint { color, amount }) = self.tint {
+            let luma = luma(rgb);
+            let color = [color.0, color.1, color.2].map(|c| c as f32 / 255.0);
+            for (value, color) in rgb.iter_mut().zip(color) {
+                *value += (luma * color - *value) * amount;
+            }
+        }
+        if let Some(threshold) = self.threshold {
+            rgb = [if luma(rgb) >= threshold { 1.0 } else { 0.0 }; 3];
+        }
+        if self.invert {
+            rgb = rgb.map(|value| 1.0 - value);
+        }
+
+        for (i, value) in rgb.iter().enumerate() {
+            pixel[i] = (value * 255.0).round() as u8;
+        }
+    }
+}
+
+// Luminance weights of the red, green and blue channels, as used by feColorMatrix
+const LUMA: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+fn luma(rgb: [f32; 3]) -> f32 {
+    rgb.iter()
+        .zip(LUMA)
+        .map(|(value, weight)| value * weight)
+        .sum()
+}
+
+fn invalid_adjustment(field: &'static str, reason: InvalidReason) -> crate::Result<()> {
+    Err(crate::PyramusError::InvalidAdjustment { field, reason })
+}
+
+/// A cached result of adjusting an image
+struct AdjustedImage {
+    source: Arc<Vec<u8>>,
+    adjustments: ImageAdjustments,
+    result: Arc<Vec<u8>>,
+}
+
+thread_local! {
+    // Keyed by the identity of the image data (which is shared between copies of an item, and replaced when edited),
+    // with at most one entry for each
+    static ADJUSTED: RefCell<Vec<AdjustedImage>> = const { RefCell::new(Vec::new()) };
+}
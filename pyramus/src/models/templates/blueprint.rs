@@ -1,13 +1,9 @@
-use js_sys::Math::random;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
-use crate::models::editor::{
-    base_item::{Base, BaseItem, BaseTemplate},
-    item::StageItem,
-};
+use crate::models::editor::base_item::{Base, BaseItem, BaseTemplate};
 
-use super::{builder::ItemBuilder, prop::Prop};
-use std::collections::HashMap;
+use super::builder::ItemBuilder;
 
 /// A blueprint
 /// A structure that contains one or more props, easily modifable to fit a variety of contexts
@@ -30,30 +26,37 @@ impl Blueprint {
         )
     }
 
-    pub fn build_random(name: impl ToString, width: u32, height: u32) -> Blueprint {
-        let mut items = HashMap::new();
-        let root = Prop::new("root", width, height);
-        let id = root.id;
-        items.insert(id, StageItem::Prop(root));
+    /// Generate a new blueprint with random props
+    /// The same generator state will always produce the same structure
+    pub fn build_random(
+        name: impl ToString,
+        width: u32,
+        height: u32,
+        rng: &mut impl Rng,
+    ) -> Blueprint {
+        let template = BaseTemplate::new(
+            ItemBuilder::build_empty_prop(width, height).name("root"),
+            (width, height),
+        )
+        .unwrap(); // TODO: Handle error
 
         // building a "new object"" so we use a baseitem as if it were in a stage
         // Todo: is this the way to do?
-        let mut blueprint = Base::new(Blueprint {
-            name: name.to_string(),
-            template: BaseTemplate {
-                items,
-                root: id,
-                size: (width, height),
-            },
-        }.into());
+        let mut blueprint = Base::new(
+            Blueprint {
+                name: name.to_string(),
+                template,
+            }
+            .into(),
+        );
 
         // Add 3 random props
         for _ in 0..3 {
             // tODO: standardize f64 and f32 across codebase
-            let w = (width as f64 * random()) as u32;
-            let h = (height as f64 * random()) as u32;
+            let w = (width as f64 * rng.gen::<f64>()) as u32;
+            let h = (height as f64 * rng.gen::<f64>()) as u32;
             blueprint
-                .add_child(ItemBuilder::build_random_prop(w, h))
+                .add_child(ItemBuilder::build_random_prop(w, h, rng.gen()))
                 .unwrap(); // TODO: Handle error
         }
 
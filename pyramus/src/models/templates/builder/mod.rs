@@ -1,8 +1,11 @@
-use crate::models::editor::{item::StageItem, staging::StagingContext};
+use crate::models::editor::{
+    item::StageItem,
+    staging::{Staging, StagingContext},
+};
 
 use self::{prop_builder::PropTypeBuilder, prop_item_builder::PropItemTypeBuilder};
 
-use super::{ids::InternalId, prop_item::PropItem, transform::RelativeTransform};
+use super::{group::Group, ids::InternalId, prop_item::PropItem, transform::RelativeTransform};
 
 pub mod prop_builder;
 pub mod prop_item_builder;
@@ -22,27 +25,46 @@ pub struct ItemBuilder {
 pub enum BuilderType {
     PropItem(PropItemTypeBuilder),
     Prop(PropTypeBuilder),
+    Group,
 }
 
 impl ItemBuilder {
-    /// Finish and build the item
-    pub fn build(self) -> crate::Result<StageItem> {
-        match self.builder {
+    /// Create a builder for an empty group (items are moved into it once it is placed)
+    pub fn build_group() -> ItemBuilder {
+        ItemBuilder {
+            name: "group".to_string(),
+            builder: BuilderType::Group,
+            parent: None,
+            transform: Default::default(),
+        }
+    }
+
+    /// Finish and build the item, with an ID allocated by the template it will be placed in
+    /// The item is placed by the transform of the builder, relative to its parent (see `ItemBuilder::transform`)
+    pub fn build(self, id: InternalId) -> crate::Result<StageItem> {
+        let mut item = match self.builder {
             BuilderType::PropItem(item) => {
                 let item = item.build()?;
                 // TODO: remove from here
                 let prop_item = PropItem {
                     name: self.name,
-                    id: InternalId::new(),
+                    id,
                     staging: StagingContext::new(),
                     item,
                 };
-                Ok(StageItem::PropItem(prop_item))
+                StageItem::PropItem(prop_item)
             }
             BuilderType::Prop(prop) => {
-                let prop = prop.build(self.name);
-                Ok(StageItem::Prop(prop))
+                let prop = prop.build(id, self.name);
+                StageItem::Prop(prop)
             }
-        }
+            BuilderType::Group => StageItem::Group(Group {
+                id,
+                name: self.name,
+                staging: StagingContext::new(),
+            }),
+        };
+        *item.get_relative_transform_mut() = self.transform;
+        Ok(item)
     }
 }
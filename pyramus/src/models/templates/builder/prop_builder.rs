@@ -1,4 +1,4 @@
-use crate::models::templates::prop::Prop;
+use crate::models::templates::{ids::InternalId, prop::Prop, random::rng_from_seed};
 
 use super::{BuilderType, ItemBuilder};
 
@@ -7,16 +7,24 @@ use super::{BuilderType, ItemBuilder};
 pub enum PropTypeBuilder {
     /// Prop with random items within, varying on size, etc.
     /// Can be created with `ItemBuilder::build_random_prop`
-    Random { width: u32, height: u32 },
+    /// The same seed will always produce the same prop
+    Random { width: u32, height: u32, seed: u64 },
     /// Empty prop
     Empty { width: u32, height: u32 },
+    /// Instance of an existing prop (such as one made from items with `Stage::extract_prop`)
+    Instance(Box<Prop>),
 }
 
 impl PropTypeBuilder {
-    pub fn build(self, name: String) -> Prop {
+    pub fn build(self, id: InternalId, name: String) -> Prop {
         match self {
-            PropTypeBuilder::Random { width, height } => Prop::build_random(name, width, height),
-            PropTypeBuilder::Empty { width, height } => Prop::new(name, width, height),
+            PropTypeBuilder::Random {
+                width,
+                height,
+                seed,
+            } => Prop::build_random(id, name, width, height, &mut rng_from_seed(seed)),
+            PropTypeBuilder::Empty { width, height } => Prop::new(id, name, width, height),
+            PropTypeBuilder::Instance(prop) => Prop { id, name, ..*prop },
         }
     }
 }
@@ -27,10 +35,35 @@ impl PropTypeBuilder {
 
 impl ItemBuilder {
     /// Create a new prop with random items within, varying on size, etc
-    pub fn build_random_prop(width: u32, height: u32) -> ItemBuilder {
+    /// The structure is generated from the given seed
+    pub fn build_random_prop(width: u32, height: u32, seed: u64) -> ItemBuilder {
         ItemBuilder {
             name: "random".to_string(),
-            builder: BuilderType::Prop(PropTypeBuilder::Random { width, height }),
+            builder: BuilderType::Prop(PropTypeBuilder::Random {
+                width,
+                height,
+                seed,
+            }),
+            parent: None,
+            transform: Default::default(),
+        }
+    }
+
+    /// Create a new empty prop
+    pub fn build_empty_prop(width: u32, height: u32) -> ItemBuilder {
+        ItemBuilder {
+            name: "prop".to_string(),
+            builder: BuilderType::Prop(PropTypeBuilder::Empty { width, height }),
+            parent: None,
+            transform: Default::default(),
+        }
+    }
+
+    /// Create an instance of an existing prop, with the same name
+    pub fn build_prop_instance(prop: Prop) -> ItemBuilder {
+        ItemBuilder {
+            name: prop.name.clone(),
+            builder: BuilderType::Prop(PropTypeBuilder::Instance(Box::new(prop))),
             parent: None,
             transform: Default::default(),
         }
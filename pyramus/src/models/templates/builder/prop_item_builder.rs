@@ -1,13 +1,26 @@
 use crate::{
     models::templates::{
+        adjustment::ImageAdjustments,
         ids::InternalId,
-        prop_item::{PropItemImage, PropItemImageData, PropItemText, PropItemType},
+        paint::{validate_style, Paint},
+        path::PropItemPath,
+        prop_item::{
+            ImageOriginalSize, PropItemImage, PropItemImageData, PropItemText, PropItemType,
+        },
+        shape::PropItemShape,
         transform::RelativeTransform,
     },
-    svg,
+    render::parse_svg,
+};
+use image::{
+    codecs::{gif::GifDecoder, jpeg::JpegEncoder},
+    error::{ParameterError, ParameterErrorKind},
+    imageops::FilterType,
+    io::Reader as ImageReader,
+    AnimationDecoder, DynamicImage, GenericImageView, ImageError, ImageFormat, RgbaImage,
 };
-use image::io::Reader as ImageReader;
 use resvg::usvg::{self, NonZeroPositiveF32};
+use serde::{Deserialize, Serialize};
 use std::{io::Cursor, rc::Rc, sync::Arc};
 
 use super::{BuilderType, ItemBuilder};
@@ -16,7 +29,7 @@ use super::{BuilderType, ItemBuilder};
 impl ItemBuilder {
     /// Creates an ItemBuilder with a basic text item (12pt Arial, black, not italicized)
     pub fn build_text_basic(text: impl ToString) -> ItemBuilder {
-        Self::build_text(text, "Arial".to_string(), 12.0, (0, 0, 0), false)
+        Self::build_text(text, "Arial".to_string(), 12.0, (0, 0, 0).into(), false)
     }
 
     /// Creates an ItemBuilder with a text item
@@ -24,7 +37,7 @@ impl ItemBuilder {
         text: impl ToString,
         font_family: String,
         font_size: f32,
-        color: (u8, u8, u8),
+        fill: Paint,
         italic: bool,
     ) -> ItemBuilder {
         ItemBuilder {
@@ -33,7 +46,7 @@ impl ItemBuilder {
                 text: text.to_string(),
                 font_family,
                 font_size,
-                color,
+                fill,
                 italic,
             }),
             parent: None,
@@ -51,30 +64,35 @@ impl ItemBuilder {
         }
     }
 
-    /// Creates an ItemBuilder with an image item from bytes
-    pub fn build_image_from_bytes(bytes: Vec<u8>, ext: impl ToString) -> ItemBuilder {
+    /// Creates an ItemBuilder with an image item from the bytes of an image file (the format is detected from the data)
+    /// Images beyond the limits are downscaled and recompressed
+    pub fn build_image_from_bytes(bytes: Vec<u8>, limits: ImageImportLimits) -> ItemBuilder {
         ItemBuilder {
             name: "image".to_string(),
-            builder: BuilderType::PropItem(PropItemTypeBuilder::ImageFromBytes {
-                bytes,
-                ext: ext.to_string(),
-            }),
+            builder: BuilderType::PropItem(PropItemTypeBuilder::ImageFromBytes { bytes, limits }),
             parent: None,
             transform: Default::default(),
         }
     }
 
-    /// Creates a simple SVG tree with a rectangle
-    // TODO: This is for testing purposes only
-    /// Alpha is a value between 0.0 and 1.0
-    pub fn build_image_from_rect(
-        w: u32,
-        h: u32,
-        color: &str,
-        stroke: Option<u32>,
-        alpha: f32,
-    ) -> ItemBuilder {
-        ItemBuilder::build_image_from_svg(svg::build_svg_rect(w, h, color, stroke, alpha))
+    /// Creates an ItemBuilder with a shape item
+    pub fn build_shape(shape: PropItemShape) -> ItemBuilder {
+        ItemBuilder {
+            name: "shape".to_string(),
+            builder: BuilderType::PropItem(PropItemTypeBuilder::Shape(shape)),
+            parent: None,
+            transform: Default::default(),
+        }
+    }
+
+    /// Creates an ItemBuilder with a path item
+    pub fn build_path(path: PropItemPath) -> ItemBuilder {
+        ItemBuilder {
+            name: "path".to_string(),
+            builder: BuilderType::PropItem(PropItemTypeBuilder::Path(path)),
+            parent: None,
+            transform: Default::default(),
+        }
     }
 
     /// Adds a parent to the ItemBuilder
@@ -83,7 +101,7 @@ impl ItemBuilder {
         self
     }
 
-    /// Adds a transform to the ItemBuilder
+    /// Adds a transform to the ItemBuilder, which places the built item relative to its parent
     pub fn transform(mut self, transform: RelativeTransform) -> Self {
         self.transform = transform;
         self
@@ -103,14 +121,16 @@ pub enum PropItemTypeBuilder {
         text: String,
         font_family: String,
         font_size: f32,
-        color: (u8, u8, u8),
+        fill: Paint,
         italic: bool,
     },
     ImageFromSvg(String),
     ImageFromBytes {
         bytes: Vec<u8>,
-        ext: String,
+        limits: ImageImportLimits,
     },
+    Shape(PropItemShape),
+    Path(PropItemPath),
 }
 
 impl PropItemTypeBuilder {
@@ -120,83 +140,153 @@ impl PropItemTypeBuilder {
                 text,
                 font_family,
                 font_size,
-                color,
+                fill,
                 italic,
-            } => PropItemType::Text(PropItemText {
-                text,
-                font_family,
-                font_size: NonZeroPositiveF32::new(font_size).ok_or_else(|| {
-                    crate::PyramusError::OtherError("Font size must be greater than 0".to_string())
-                })?,
-                color,
-                italic,
-            }),
+            } => {
+                fill.validate()?;
+                PropItemType::Text(PropItemText {
+                    text,
+                    font_family,
+                    font_size: NonZeroPositiveF32::new(font_size)
+                        .ok_or(crate::PyramusError::InvalidFontSize(font_size))?,
+                    fill: Some(fill),
+                    stroke: None,
+                    italic,
+                })
+            }
             PropItemTypeBuilder::ImageFromSvg(svg) => {
                 let image = PropItemImage::from_svg_string(&svg)?;
                 PropItemType::Image(image)
             }
-            PropItemTypeBuilder::ImageFromBytes { bytes, ext } => {
-                let image = PropItemImage::from_bytes(bytes, &ext)?.ok_or_else(|| {
-                    crate::PyramusError::OtherError("Could not parse image".to_string())
-                })?;
-                PropItemType::Image(image)
+            PropItemTypeBuilder::ImageFromBytes { bytes, limits } => {
+                PropItemType::Image(PropItemImage::from_bytes(bytes, &limits)?)
+            }
+            PropItemTypeBuilder::Shape(shape) => {
+                shape.shape.validate()?;
+                validate_style(shape.fill.as_ref(), shape.stroke.as_ref())?;
+                PropItemType::Shape(shape)
+            }
+            PropItemTypeBuilder::Path(path) => {
+                validate_style(path.fill.as_ref(), path.stroke.as_ref())?;
+                PropItemType::Path(path)
             }
         })
     }
 }
 
+/// Limits on the size of imported raster images, so documents stay small enough to share
+/// Images beyond the limits are downscaled and recompressed on import (SVGs are not limited)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ImageImportLimits {
+    pub max_width: u32,
+    pub max_height: u32,
+    /// The most bytes of encoded image data to keep
+    /// Images that cannot be compressed this far are kept at the smallest size tried
+    pub max_bytes: usize,
+}
+
+impl Default for ImageImportLimits {
+    fn default() -> Self {
+        ImageImportLimits {
+            max_width: 2048,
+            max_height: 2048,
+            max_bytes: 2 * 1024 * 1024,
+        }
+    }
+}
+
 impl PropItemImage {
-    /// Create a PropItemImage from bytes (guessing the format from the extension)
-    pub fn from_bytes(
-        bytes: Vec<u8>,
-        ext: &str, // TODO: Might not need this with with_guessed_format
-    ) -> crate::Result<Option<PropItemImage>> {
-        // TODO: find a different way to get height/width (especially given that files will be dynamically uploaded- can get size through that)
-        // TODO: This may only apply to right now when we are using include_bytes! to load the image
-        // This is very awkward, but it seems like we cant get the bytes back out without rewriting it.
-        // TODO: It might be good to just straight-up use DynamicImage in ItemImage
-        let img = ImageReader::new(Cursor::new(bytes))
-            .with_guessed_format()?
-            .decode()?;
-        let viewport_width = img.width() as f32;
-        let viewport_height = img.height() as f32;
-
-        let mut bytes: Vec<u8> = Vec::new();
-        img.write_to(&mut Cursor::new(&mut bytes), image::ImageOutputFormat::Png)?;
-
-        let data = match ext {
-            "png" => Some(PropItemImageData::Png(Arc::new(bytes))),
-            "jpg" | "jpeg" => Some(PropItemImageData::Jpeg(Arc::new(bytes))),
-            "gif" => Some(PropItemImageData::Gif(Arc::new(bytes))),
-            "svg" => {
-                let tree = match usvg::Tree::from_data(&bytes, &usvg::Options::default()) {
-                    Ok(tree) => tree,
-                    Err(err) => {
-                        // TODO: Should this be an error?
-                        crate::log!("Could not parsing SVG: {err}");
-                        return Ok(None);
-                    }
+    /// Create a PropItemImage from encoded image bytes, detecting the format from the data itself
+    /// PNG, JPEG and GIF bytes are kept as they are (if within the limits), and SVG is parsed into a tree.
+    /// Formats that cannot be embedded in a render as-is are re-encoded as PNG:
+    /// WebP images, and animated GIFs (which only keep their first frame).
+    pub fn from_bytes(bytes: Vec<u8>, limits: &ImageImportLimits) -> crate::Result<PropItemImage> {
+        let Ok(format) = image::guess_format(&bytes) else {
+            // SVG is text, and has no signature to detect
+            return PropItemImage::from_svg_bytes(&bytes);
+        };
+
+        // Decoded (rather than just reading the header) so broken images are rejected on import
+        let original_bytes = bytes.len();
+        let (data, image) = match format {
+            ImageFormat::Png | ImageFormat::Jpeg => {
+                let image = ImageReader::with_format(Cursor::new(&bytes), format).decode()?;
+                let data = match format {
+                    ImageFormat::Png => PropItemImageData::Png(Arc::new(bytes)),
+                    _ => PropItemImageData::Jpeg(Arc::new(bytes)),
                 };
-                Some(PropItemImageData::Svg(Rc::new(tree)))
+                (data, image)
+            }
+            ImageFormat::Gif => {
+                let (first, animated) = first_gif_frame(&bytes)?;
+                let image = DynamicImage::from(first);
+                if animated {
+                    (encode_png(&image)?, image)
+                } else {
+                    (PropItemImageData::Gif(Arc::new(bytes)), image)
+                }
+            }
+            ImageFormat::WebP => {
+                let image = ImageReader::with_format(Cursor::new(&bytes), format).decode()?;
+                (encode_png(&image)?, image)
+            }
+            format => {
+                let name = format.extensions_str().first().unwrap_or(&"unknown");
+                return Err(crate::PyramusError::UnsupportedImageFormat(
+                    name.to_string(),
+                ));
             }
-            _ => None,
         };
-        Ok(data.map(|data| PropItemImage {
+
+        let (width, height) = image.dimensions();
+        let fits = width <= limits.max_width
+            && height <= limits.max_height
+            && encoded_len(&data) <= limits.max_bytes;
+        let (data, (viewport_width, viewport_height), original_size) = if fits {
+            (data, (width, height), None)
+        } else {
+            let (data, size) = fit_to_limits(image, limits)?;
+            let original = ImageOriginalSize {
+                width,
+                height,
+                bytes: original_bytes,
+            };
+            (data, size, Some(original))
+        };
+
+        Ok(PropItemImage {
             data,
-            viewport_width,
-            viewport_height,
-        }))
+            viewport_width: viewport_width as f32,
+            viewport_height: viewport_height as f32,
+            crop: None,
+            original_size,
+            adjustments: ImageAdjustments::default(),
+        })
+    }
+
+    /// Create a PropItemImage from the bytes of an SVG file
+    fn from_svg_bytes(bytes: &[u8]) -> crate::Result<PropItemImage> {
+        let svg = std::str::from_utf8(bytes)
+            .ok()
+            .filter(|svg| svg.contains("<svg"))
+            .ok_or(crate::PyramusError::UnsupportedImageFormat(
+                "unknown".to_string(),
+            ))?;
+        Ok(PropItemImage::from_svg_string(svg)?)
     }
 
     /// Create a PropItemImage from an SVG string
     pub fn from_svg_string(svg: &str) -> Result<PropItemImage, usvg::Error> {
-        let tree = resvg::usvg::Tree::from_str(svg, &resvg::usvg::Options::default())?;
+        let tree = parse_svg(svg)?;
         let tree_height = tree.size.height();
         let tree_width = tree.size.width();
         Ok(PropItemImage {
             data: PropItemImageData::Svg(Rc::new(tree)),
             viewport_width: tree_width,
             viewport_height: tree_height,
+            crop: None,
+            original_size: None,
+            adjustments: ImageAdjustments::default(),
         })
     }
 }
@@ -209,8 +299,78 @@ impl PropItemText {
             text,
             font_family: "Arial".to_string(),
             font_size: NonZeroPositiveF32::new(12.0).expect("12.0 is not a NonZeroPositiveF32"),
-            color: (255, 255, 255), // White
+            fill: Some((255, 255, 255).into()), // White
+            stroke: None,
             italic: false,
         }
     }
 }
+
+/// Decode the first frame of a GIF, and whether there are any frames after it
+fn first_gif_frame(bytes: &[u8]) -> crate::Result<(RgbaImage, bool)> {
+    let mut frames = GifDecoder::new(Cursor::new(bytes))?.into_frames();
+    let first = frames
+        .next()
+        .ok_or(ImageError::Parameter(ParameterError::from_kind(
+            ParameterErrorKind::NoMoreData,
+        )))??;
+    Ok((first.into_buffer(), frames.next().is_some()))
+}
+
+/// Downscale an image to fit within the maximum dimensions (keeping its aspect ratio), then recompress it,
+/// shrinking it further until it also fits within the maximum bytes
+fn fit_to_limits(
+    mut image: DynamicImage,
+    limits: &ImageImportLimits,
+) -> crate::Result<(PropItemImageData, (u32, u32))> {
+    let (max_width, max_height) = (limits.max_width.max(1), limits.max_height.max(1));
+    if image.width() > max_width || image.height() > max_height {
+        image = image.resize(max_width, max_height, FilterType::Lanczos3);
+    }
+
+    // Transparency is kept by PNG, otherwise JPEG compresses photos much better
+    let opaque = !image.color().has_alpha() || image.to_rgba8().pixels().all(|p| p[3] == 255);
+    loop {
+        let data = if opaque {
+            encode_jpeg(&image)?
+        } else {
+            encode_png(&image)?
+        };
+        let (width, height) = image.dimensions();
+        if encoded_len(&data) <= limits.max_bytes || width.max(height) <= 1 {
+            return Ok((data, (width, height)));
+        }
+        image = image.resize(
+            (width * 3 / 4).max(1),
+            (height * 3 / 4).max(1),
+            FilterType::Lanczos3,
+        );
+    }
+}
+
+/// The number of bytes of encoded image data (SVG trees are not encoded, and count as empty)
+fn encoded_len(data: &PropItemImageData) -> usize {
+    match data {
+        PropItemImageData::Png(bytes)
+        | PropItemImageData::Jpeg(bytes)
+        | PropItemImageData::Gif(bytes) => bytes.len(),
+        PropItemImageData::Svg(_) => 0,
+    }
+}
+
+/// Re-encode a decoded image as JPEG (dropping any transparency)
+fn encode_jpeg(image: &DynamicImage) -> crate::Result<PropItemImageData> {
+    let mut bytes = Vec::new();
+    JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY).encode_image(&image.to_rgb8())?;
+    Ok(PropItemImageData::Jpeg(Arc::new(bytes)))
+}
+
+// Quality of recompressed JPEGs, from 1 to 100
+const JPEG_QUALITY: u8 = 85;
+
+/// Re-encode a decoded image as PNG
+fn encode_png(image: &DynamicImage) -> crate::Result<PropItemImageData> {
+    let mut bytes = Vec::new();
+    image.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+    Ok(PropItemImageData::Png(Arc::new(bytes)))
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+use crate::InvalidReason;
+
+/// A visual effect applied to an item and its children
+/// Effects are applied in order, each to the result of the previous one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Effect {
+    /// A blurred, offset copy of the item drawn behind it
+    DropShadow {
+        offset: (f32, f32),
+        blur: f32,
+        /// Red, green, blue, alpha
+        color: (u8, u8, u8, u8),
+    },
+    /// A gaussian blur
+    Blur { radius: f32 },
+    /// Tint the item brown, like an old photograph
+    /// `amount` is between 0.0 (no change) and 1.0 (fully sepia)
+    Sepia { amount: f32 },
+    /// Remove the color of the item
+    /// `amount` is between 0.0 (no change) and 1.0 (fully gray)
+    Grayscale { amount: f32 },
+    /// Fine grain over the item
+    /// `amount` is between 0.0 and 1.0, and `scale` is the size of the grain
+    Noise { amount: f32, scale: f32, seed: i32 },
+    /// A blotchy paper texture, tinted with `color`, multiplied over the item
+    /// `amount` is between 0.0 and 1.0, and `scale` is the size of the blotches
+    PaperTexture {
+        amount: f32,
+        scale: f32,
+        color: (u8, u8, u8),
+        seed: i32,
+    },
+    /// Ragged, scorched edges, darkened with `color` up to `width` in from the edge
+    BurnedEdges {
+        width: f32,
+        color: (u8, u8, u8),
+        seed: i32,
+    },
+}
+
+impl Effect {
+    /// Check that the effect can be rendered
+    pub fn validate(&self) -> crate::Result<()> {
+        match self {
+            Effect::DropShadow { offset, blur, .. } => {
+                if !offset.0.is_finite() || !offset.1.is_finite() {
+                    return invalid_effect("shadow_offset", InvalidReason::NotFinite);
+                }
+                validate_length(*blur, "shadow_blur")
+            }
+            Effect::Blur { radius } => validate_length(*radius, "blur_radius"),
+            Effect::Sepia { amount } | Effect::Grayscale { amount } => validate_amount(*amount),
+            Effect::Noise { amount, scale, .. } | Effect::PaperTexture { amount, scale, .. } => {
+                validate_amount(*amount)?;
+                if !(scale.is_finite() && *scale > 0.0) {
+                    return invalid_effect("texture_scale", InvalidReason::NotPositive);
+                }
+                Ok(())
+            }
+            Effect::BurnedEdges { width, .. } => validate_length(*width, "burn_width"),
+        }
+    }
+}
+
+fn invalid_effect(field: &'static str, reason: InvalidReason) -> crate::Result<()> {
+    Err(crate::PyramusError::InvalidEffect { field, reason })
+}
+
+fn validate_length(length: f32, field: &'static str) -> crate::Result<()> {
+    if !(length.is_finite() && length >= 0.0) {
+        return invalid_effect(field, InvalidReason::Negative);
+    }
+    Ok(())
+}
+
+fn validate_amount(amount: f32) -> crate::Result<()> {
+    if !(0.0..=1.0).contains(&amount) {
+        return invalid_effect("amount", InvalidReason::OutOfRange { min: 0.0, max: 1.0 });
+    }
+    Ok(())
+}
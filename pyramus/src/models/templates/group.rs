@@ -0,0 +1,14 @@
+use crate::models::editor::staging::StagingContext;
+use serde::{Deserialize, Serialize};
+
+use super::ids::InternalId;
+
+/// A group of items, which are selected and moved as one
+/// A group has no content of its own- it is drawn as its children, and its bounds are the bounds of its children
+#[derive(Debug, Clone, Serialize, Deserialize)] // TODO: remove clone
+pub struct Group {
+    pub id: InternalId,
+    pub name: String,
+
+    pub staging: StagingContext,
+}
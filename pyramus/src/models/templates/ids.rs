@@ -1,4 +1,4 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
 use js_sys::Math::random;
 use serde::{Deserialize, Serialize};
@@ -41,6 +41,12 @@ impl<'de> Deserialize<'de> for PyramusId {
     }
 }
 
+impl fmt::Display for PyramusId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", encode(self.0))
+    }
+}
+
 impl FromStr for PyramusId {
     type Err = base62::DecodeError;
 
@@ -54,20 +60,42 @@ impl FromStr for PyramusId {
 
 /// InternalId is a unique identifier for an item within any any private context.
 /// Two different props may have items with the same internal ID, but two items within the same prop will not.
-/// (No collision checking is done)
-/// TODO: Checks to ensure uniqueness
+/// IDs should be allocated through the InternalIdAllocator of the BaseTemplate they belong to.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct InternalId(pub u32);
 impl InternalId {
-    pub fn new() -> InternalId {
-        // TODO: Generate a unique ID
-        let random_id = random() * u32::MAX as f64;
-        InternalId(random_id as u32)
-    }
+    /// The ID of an item that is not contained within any template (e.g. the prop of a Base)
+    /// Never handed out by an InternalIdAllocator
+    pub const UNCONTAINED: InternalId = InternalId(0);
+}
+
+/// Allocates InternalIds that are unique within a single BaseTemplate.
+/// IDs are handed out sequentially, skipping any that are already in use (e.g. from a loaded document).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InternalIdAllocator {
+    next: u32,
 }
-impl Default for InternalId {
+
+impl Default for InternalIdAllocator {
     fn default() -> Self {
         Self::new()
     }
 }
+
+impl InternalIdAllocator {
+    pub fn new() -> InternalIdAllocator {
+        InternalIdAllocator { next: 1 }
+    }
+
+    /// Allocate a new ID, for which `is_used` returns false
+    pub fn allocate(&mut self, is_used: impl Fn(InternalId) -> bool) -> InternalId {
+        loop {
+            let id = InternalId(self.next);
+            self.next = self.next.wrapping_add(1);
+            if id != InternalId::UNCONTAINED && !is_used(id) {
+                return id;
+            }
+        }
+    }
+}
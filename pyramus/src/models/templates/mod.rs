@@ -1,6 +1,14 @@
+pub mod adjustment;
 pub mod blueprint;
 pub mod builder;
+pub mod effect;
+pub mod group;
 pub mod ids;
+pub mod outline;
+pub mod paint;
+pub mod path;
 pub mod prop;
 pub mod prop_item;
+pub mod random;
+pub mod shape;
 pub mod transform;
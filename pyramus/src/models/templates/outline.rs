@@ -0,0 +1,126 @@
+use rand::Rng;
+use resvg::usvg::tiny_skia_path::Path;
+use serde::{Deserialize, Serialize};
+
+use crate::InvalidReason;
+
+use super::{
+    path::{is_self_intersecting, winding_number, PathNode, PropItemPath},
+    random::rng_from_seed,
+};
+
+/// An irregular outline for a prop, which clips everything in its template
+/// Clicks outside of the outline fall through to the items underneath
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PropOutline {
+    /// Torn edges around the bounds of the prop, like a torn note
+    /// `roughness` is the deepest a tear can cut into the prop, and the same seed always tears the same way
+    Torn { seed: u32, roughness: f32 },
+    /// A closed path (in the local space of the prop)
+    Path(Vec<PathNode>),
+}
+
+impl PropOutline {
+    // Distance between the points of a torn edge
+    const TEAR_SPACING: f32 = 8.0;
+
+    /// Check that the outline can be used
+    pub fn validate(&self) -> crate::Result<()> {
+        match self {
+            PropOutline::Torn { roughness, .. } => {
+                if !(roughness.is_finite() && *roughness >= 0.0) {
+                    return invalid_outline("roughness", InvalidReason::Negative);
+                }
+            }
+            PropOutline::Path(nodes) => {
+                if nodes.len() < 3 {
+                    return invalid_outline("nodes", InvalidReason::TooFew { min: 3 });
+                }
+                let finite = nodes.iter().all(|node| {
+                    [node.position, node.handle_in, node.handle_out]
+                        .iter()
+                        .all(|(x, y)| x.is_finite() && y.is_finite())
+                });
+                if !finite {
+                    return invalid_outline("nodes", InvalidReason::NotFinite);
+                }
+                if self
+                    .to_path((0, 0))
+                    .is_some_and(|path| is_self_intersecting(&path))
+                {
+                    return invalid_outline("nodes", InvalidReason::SelfIntersecting);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The nodes of the outline, for a prop of the given size
+    pub fn get_nodes(&self, (width, height): (u32, u32)) -> Vec<PathNode> {
+        match self {
+            PropOutline::Torn { seed, roughness } => {
+                torn_nodes(width as f32, height as f32, *seed, *roughness)
+            }
+            PropOutline::Path(nodes) => nodes.clone(),
+        }
+    }
+
+    /// The outline as a closed path, for a prop of the given size
+    pub fn to_path(&self, size: (u32, u32)) -> Option<Path> {
+        PropItemPath {
+            nodes: self.get_nodes(size),
+            closed: true,
+            fill: None,
+            stroke: None,
+        }
+        .to_path()
+    }
+
+    /// Check if a point in the local space of the prop is inside the outline
+    /// Uses the even-odd rule, as the outline is clipped with (torn outlines may cross themselves at the corners)
+    pub fn contains_point(&self, size: (u32, u32), x: f32, y: f32) -> bool {
+        self.to_path(size)
+            .is_some_and(|path| winding_number(&path, x, y) % 2 != 0)
+    }
+}
+
+fn invalid_outline(field: &'static str, reason: InvalidReason) -> crate::Result<()> {
+    Err(crate::PyramusError::InvalidOutline { field, reason })
+}
+
+/// Walk clockwise around the edges of the rectangle, cutting in by a random amount at each point
+fn torn_nodes(width: f32, height: f32, seed: u32, roughness: f32) -> Vec<PathNode> {
+    let mut rng = rng_from_seed(seed as u64);
+    // The depth wanders (rather than jumping) so tears have a mix of long and short features
+    let mut depth = 0.5;
+    let mut cut = |rng: &mut _| {
+        depth = (depth + rng_step(rng)).clamp(0.0, 1.0);
+        // Small jitter on top for the fibrous edge of the paper
+        let jitter = rng_step(rng) * 0.3;
+        (depth + jitter).clamp(0.0, 1.0) * roughness.min(width / 2.0).min(height / 2.0)
+    };
+
+    let steps = |length: f32| ((length / PropOutline::TEAR_SPACING).ceil() as usize).max(1);
+    let mut nodes = Vec::new();
+    for i in 0..steps(width) {
+        let x = width * i as f32 / steps(width) as f32;
+        nodes.push(PathNode::corner(x, cut(&mut rng)));
+    }
+    for i in 0..steps(height) {
+        let y = height * i as f32 / steps(height) as f32;
+        nodes.push(PathNode::corner(width - cut(&mut rng), y));
+    }
+    for i in 0..steps(width) {
+        let x = width - width * i as f32 / steps(width) as f32;
+        nodes.push(PathNode::corner(x, height - cut(&mut rng)));
+    }
+    for i in 0..steps(height) {
+        let y = height - height * i as f32 / steps(height) as f32;
+        nodes.push(PathNode::corner(cut(&mut rng), y));
+    }
+    nodes
+}
+
+fn rng_step(rng: &mut impl Rng) -> f32 {
+    rng.gen::<f32>() - 0.5
+}
@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+
+use crate::InvalidReason;
+
+use super::prop_item::PropItemImageData;
+
+/// A paint used to fill or stroke text, shapes and paths
+#[derive(Debug, Clone, Serialize, Deserialize)] // TODO: remove clone
+pub enum Paint {
+    /// A single color (red, green, blue, alpha)
+    Solid((u8, u8, u8, u8)),
+    /// A gradient along the line from `start` to `end`
+    /// Points are relative to the bounding box of the painted item (0.0 to 1.0)
+    LinearGradient {
+        start: (f32, f32),
+        end: (f32, f32),
+        stops: Vec<GradientStop>,
+    },
+    /// A gradient outwards from `center`
+    /// The center and radius are relative to the bounding box of the painted item (0.0 to 1.0)
+    RadialGradient {
+        center: (f32, f32),
+        radius: f32,
+        stops: Vec<GradientStop>,
+    },
+    /// An image, tiled in cells of `width` x `height` (in the local space of the painted item)
+    Pattern {
+        image: PropItemImageData,
+        width: f32,
+        height: f32,
+    },
+}
+
+/// A color at a position along a gradient
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct GradientStop {
+    /// Position along the gradient (0.0 to 1.0)
+    pub offset: f32,
+    /// Red, green, blue, alpha
+    pub color: (u8, u8, u8, u8),
+}
+
+/// The outline style of text, shapes and paths
+#[derive(Debug, Clone, Serialize, Deserialize)] // TODO: remove clone
+pub struct Stroke {
+    pub paint: Paint,
+    pub width: f32,
+    /// Alternating lengths of dashes and gaps, or empty for a solid line
+    #[serde(default)]
+    pub dash: Vec<f32>,
+    #[serde(default)]
+    pub dash_offset: f32,
+    #[serde(default)]
+    pub line_cap: LineCap,
+    #[serde(default)]
+    pub line_join: LineJoin,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum LineCap {
+    Butt,
+    #[default]
+    Round,
+    Square,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum LineJoin {
+    Miter,
+    #[default]
+    Round,
+    Bevel,
+}
+
+impl From<(u8, u8, u8)> for Paint {
+    fn from((red, green, blue): (u8, u8, u8)) -> Self {
+        Paint::Solid((red, green, blue, 255))
+    }
+}
+
+impl Paint {
+    /// Check that the paint can be rendered
+    pub fn validate(&self) -> crate::Result<()> {
+        match self {
+            Paint::Solid(_) => Ok(()),
+            Paint::LinearGradient { start, end, stops } => {
+                if ![start.0, start.1, end.0, end.1]
+                    .iter()
+                    .all(|v| v.is_finite())
+                {
+                    return invalid_paint("gradient_points", InvalidReason::NotFinite);
+                }
+                validate_stops(stops)
+            }
+            Paint::RadialGradient {
+                center,
+                radius,
+                stops,
+            } => {
+                if !center.0.is_finite() || !center.1.is_finite() {
+                    return invalid_paint("gradient_center", InvalidReason::NotFinite);
+                }
+                if !(radius.is_finite() && *radius > 0.0) {
+                    return invalid_paint("gradient_radius", InvalidReason::NotPositive);
+                }
+                validate_stops(stops)
+            }
+            Paint::Pattern { width, height, .. } => {
+                if !(width.is_finite() && *width > 0.0 && height.is_finite() && *height > 0.0) {
+                    return invalid_paint("pattern_size", InvalidReason::NotPositive);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Check that an optional fill and stroke can be rendered
+pub fn validate_style(fill: Option<&Paint>, stroke: Option<&Stroke>) -> crate::Result<()> {
+    if let Some(fill) = fill {
+        fill.validate()?;
+    }
+    if let Some(stroke) = stroke {
+        stroke.validate()?;
+    }
+    Ok(())
+}
+
+fn invalid_paint(field: &'static str, reason: InvalidReason) -> crate::Result<()> {
+    Err(crate::PyramusError::InvalidPaint { field, reason })
+}
+
+fn validate_stops(stops: &[GradientStop]) -> crate::Result<()> {
+    if stops.is_empty() {
+        return invalid_paint("gradient_stops", InvalidReason::TooFew { min: 1 });
+    }
+    if stops.iter().any(|stop| !(0.0..=1.0).contains(&stop.offset)) {
+        return invalid_paint(
+            "gradient_stop_offset",
+            InvalidReason::OutOfRange { min: 0.0, max: 1.0 },
+        );
+    }
+    Ok(())
+}
+
+impl Stroke {
+    /// Create a solid stroke with the default caps and joins
+    pub fn solid(paint: impl Into<Paint>, width: f32) -> Stroke {
+        Stroke {
+            paint: paint.into(),
+            width,
+            dash: Vec::new(),
+            dash_offset: 0.0,
+            line_cap: LineCap::default(),
+            line_join: LineJoin::default(),
+        }
+    }
+
+    /// Check that the stroke can be rendered
+    pub fn validate(&self) -> crate::Result<()> {
+        if !(self.width.is_finite() && self.width >= 0.0) {
+            return invalid_paint("stroke_width", InvalidReason::Negative);
+        }
+        if self.dash.iter().any(|d| !(d.is_finite() && *d >= 0.0)) {
+            return invalid_paint("dash", InvalidReason::Negative);
+        }
+        self.paint.validate()
+    }
+}
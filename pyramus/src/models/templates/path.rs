@@ -0,0 +1,283 @@
+use resvg::usvg::tiny_skia_path::{Path, PathBuilder, PathSegment, Point};
+use serde::{Deserialize, Serialize};
+
+use super::paint::{Paint, Stroke};
+
+/// A freehand or node-edited vector path prop item, made of cubic bezier segments
+#[derive(Debug, Clone, Serialize, Deserialize)] // TODO: remove clone
+pub struct PropItemPath {
+    pub nodes: Vec<PathNode>,
+    /// Whether the last node connects back to the first
+    pub closed: bool,
+
+    /// Fill, or None for no fill (only closed paths are filled)
+    pub fill: Option<Paint>,
+    /// Stroke, or None for no stroke
+    pub stroke: Option<Stroke>,
+}
+
+/// An anchor point on a path, with the bezier handles of the segments on either side
+/// Handles are relative to the position of the node, so a node with zero handles is a sharp corner
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PathNode {
+    pub position: (f32, f32),
+    /// Control point of the segment coming into this node
+    pub handle_in: (f32, f32),
+    /// Control point of the segment going out of this node
+    pub handle_out: (f32, f32),
+}
+
+impl PathNode {
+    /// Create a sharp corner node (with no handles)
+    pub fn corner(x: f32, y: f32) -> PathNode {
+        PathNode {
+            position: (x, y),
+            handle_in: (0.0, 0.0),
+            handle_out: (0.0, 0.0),
+        }
+    }
+
+    /// Whether the position and handles of the node are finite (not NaN or infinite)
+    pub fn is_finite(&self) -> bool {
+        [self.position, self.handle_in, self.handle_out]
+            .iter()
+            .all(|(x, y)| x.is_finite() && y.is_finite())
+    }
+}
+
+impl PropItemPath {
+    // Tolerance (in local units) used to drop points from a freehand stroke before smoothing
+    pub const SMOOTHING_TOLERANCE: f32 = 2.0;
+
+    /// Create an open, stroked path from a sequence of nodes
+    pub fn stroked(nodes: Vec<PathNode>, stroke: Stroke) -> PropItemPath {
+        PropItemPath {
+            nodes,
+            closed: false,
+            fill: None,
+            stroke: Some(stroke),
+        }
+    }
+
+    /// Create a smoothed sequence of nodes from a freehand sequence of points (such as pointer positions)
+    /// Points closer than `tolerance` to the simplified line are dropped, and the remaining points
+    /// are joined with a Catmull-Rom spline.
+    pub fn smooth_nodes(points: &[(f32, f32)], tolerance: f32) -> Vec<PathNode> {
+        // Drop repeated points, which would produce zero-length tangents
+        let mut deduped: Vec<(f32, f32)> = Vec::with_capacity(points.len());
+        for &point in points {
+            if deduped.last() != Some(&point) {
+                deduped.push(point);
+            }
+        }
+        if deduped.len() <= 2 {
+            return deduped
+                .into_iter()
+                .map(|(x, y)| PathNode::corner(x, y))
+                .collect();
+        }
+
+        let simplified = simplify(&deduped, tolerance);
+        let mut nodes: Vec<PathNode> = simplified
+            .iter()
+            .map(|&(x, y)| PathNode::corner(x, y))
+            .collect();
+
+        // Catmull-Rom tangents for inner nodes- the end nodes are left as corners
+        for i in 1..simplified.len().saturating_sub(1) {
+            let (x0, y0) = simplified[i - 1];
+            let (x2, y2) = simplified[i + 1];
+            let tangent = ((x2 - x0) / 6.0, (y2 - y0) / 6.0);
+            nodes[i].handle_in = (-tangent.0, -tangent.1);
+            nodes[i].handle_out = tangent;
+        }
+        nodes
+    }
+
+    // x0, y0, x1, y1
+    // Includes the width of the stroke
+    pub fn get_local_bounds(&self) -> (f32, f32, f32, f32) {
+        let Some(bounds) = self.to_path().and_then(|path| path.compute_tight_bounds()) else {
+            return (0.0, 0.0, 0.0, 0.0);
+        };
+        let margin = self
+            .stroke
+            .as_ref()
+            .map_or(0.0, |stroke| stroke.width.max(0.0) / 2.0);
+        (
+            bounds.left() - margin,
+            bounds.top() - margin,
+            bounds.right() + margin,
+            bounds.bottom() + margin,
+        )
+    }
+
+    /// Build the path in local space
+    /// None if the path has no nodes
+    pub fn to_path(&self) -> Option<Path> {
+        let first = self.nodes.first()?;
+        let mut builder = PathBuilder::new();
+        builder.move_to(first.position.0, first.position.1);
+
+        if self.nodes.len() == 1 {
+            // A single node is drawn as a dot (through the line cap)
+            builder.line_to(first.position.0, first.position.1);
+            return builder.finish();
+        }
+
+        for pair in self.nodes.windows(2) {
+            push_segment(&mut builder, &pair[0], &pair[1]);
+        }
+        if self.closed {
+            if let Some(last) = self.nodes.last() {
+                push_segment(&mut builder, last, first);
+            }
+            builder.close();
+        }
+        builder.finish()
+    }
+}
+
+/// The winding number of a path around a point (nonzero if the point is inside the filled path)
+/// Subpaths are closed, as they would be when filled, and curves are flattened into lines
+pub fn winding_number(path: &Path, x: f32, y: f32) -> i32 {
+    // Count signed crossings of a ray to the right of the point
+    let mut winding = 0;
+    for_each_line(path, |a, b| {
+        if (a.y > y) != (b.y > y) && x < a.x + (y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            winding += if b.y > a.y { 1 } else { -1 };
+        }
+    });
+    winding
+}
+
+/// Check if any two edges of a path cross each other (edges that only touch do not count)
+/// Subpaths are closed, and curves are flattened into lines, as for `winding_number`
+pub fn is_self_intersecting(path: &Path) -> bool {
+    let mut lines = vec![];
+    for_each_line(path, |a, b| {
+        if a != b {
+            lines.push((a, b));
+        }
+    });
+    // The sign of the turn from a to b to c (0 if they are in a line)
+    let turn = |a: Point, b: Point, c: Point| {
+        ((b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)).signum()
+    };
+    let crosses = |(a, b): (Point, Point), (c, d): (Point, Point)| {
+        turn(a, b, c) * turn(a, b, d) < 0.0 && turn(c, d, a) * turn(c, d, b) < 0.0
+    };
+    lines
+        .iter()
+        .enumerate()
+        .any(|(i, &line)| lines[i + 1..].iter().any(|&other| crosses(line, other)))
+}
+
+/// Call `line` with each edge of a path, closing subpaths and flattening curves into lines
+fn for_each_line(path: &Path, mut line: impl FnMut(Point, Point)) {
+    let (mut start, mut last) = (Point::zero(), Point::zero());
+    for segment in path.segments() {
+        match segment {
+            PathSegment::MoveTo(p) => {
+                line(last, start);
+                start = p;
+                last = p;
+            }
+            PathSegment::LineTo(p) => {
+                line(last, p);
+                last = p;
+            }
+            PathSegment::QuadTo(c, p) => {
+                let mut prev = last;
+                for i in 1..=CURVE_STEPS {
+                    let t = i as f32 / CURVE_STEPS as f32;
+                    let next = lerp(lerp(last, c, t), lerp(c, p, t), t);
+                    line(prev, next);
+                    prev = next;
+                }
+                last = p;
+            }
+            PathSegment::CubicTo(c1, c2, p) => {
+                let mut prev = last;
+                for i in 1..=CURVE_STEPS {
+                    let t = i as f32 / CURVE_STEPS as f32;
+                    let (a, b, c) = (lerp(last, c1, t), lerp(c1, c2, t), lerp(c2, p, t));
+                    let next = lerp(lerp(a, b, t), lerp(b, c, t), t);
+                    line(prev, next);
+                    prev = next;
+                }
+                last = p;
+            }
+            PathSegment::Close => {
+                line(last, start);
+                last = start;
+            }
+        }
+    }
+    line(last, start);
+}
+
+// Number of lines each curve is split into when hit-testing, or checking for crossings
+const CURVE_STEPS: usize = 16;
+
+fn lerp(a: Point, b: Point, t: f32) -> Point {
+    Point::from_xy(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t)
+}
+
+fn push_segment(builder: &mut PathBuilder, from: &PathNode, to: &PathNode) {
+    let (x0, y0) = from.position;
+    let (x1, y1) = to.position;
+    if from.handle_out == (0.0, 0.0) && to.handle_in == (0.0, 0.0) {
+        builder.line_to(x1, y1);
+    } else {
+        builder.cubic_to(
+            x0 + from.handle_out.0,
+            y0 + from.handle_out.1,
+            x1 + to.handle_in.0,
+            y1 + to.handle_in.1,
+            x1,
+            y1,
+        );
+    }
+}
+
+/// Simplify a polyline with the Ramer-Douglas-Peucker algorithm, always keeping the end points
+fn simplify(points: &[(f32, f32)], tolerance: f32) -> Vec<(f32, f32)> {
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    let mut ranges = vec![(0, points.len() - 1)];
+    while let Some((start, end)) = ranges.pop() {
+        let mut furthest = (0.0, start);
+        for i in start + 1..end {
+            let distance = distance_to_segment(points[i], points[start], points[end]);
+            if distance > furthest.0 {
+                furthest = (distance, i);
+            }
+        }
+        if furthest.0 > tolerance {
+            keep[furthest.1] = true;
+            ranges.push((start, furthest.1));
+            ranges.push((furthest.1, end));
+        }
+    }
+
+    points
+        .iter()
+        .zip(keep)
+        .filter_map(|(point, keep)| keep.then_some(*point))
+        .collect()
+}
+
+fn distance_to_segment((px, py): (f32, f32), (ax, ay): (f32, f32), (bx, by): (f32, f32)) -> f32 {
+    let (dx, dy) = (bx - ax, by - ay);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared == 0.0 {
+        0.0
+    } else {
+        (((px - ax) * dx + (py - ay) * dy) / length_squared).clamp(0.0, 1.0)
+    };
+    let (cx, cy) = (ax + t * dx, ay + t * dy);
+    ((px - cx).powi(2) + (py - cy).powi(2)).sqrt()
+}
@@ -3,10 +3,16 @@ use crate::models::editor::{
     staging::StagingContext,
 };
 
-use super::{builder::ItemBuilder, ids::InternalId, transform::RelativeTransform};
-use js_sys::Math::random;
+use super::{
+    builder::{prop_item_builder::ImageImportLimits, ItemBuilder},
+    ids::InternalId,
+    outline::PropOutline,
+    paint::Paint,
+    shape::{PropItemShape, Shape},
+    transform::RelativeTransform,
+};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 
 // TODO: remove clone
 
@@ -19,6 +25,10 @@ pub struct Prop {
 
     pub template: BaseTemplate,
     pub staging: StagingContext,
+
+    /// An irregular outline clipping the template, if not rectangular
+    #[serde(default)]
+    pub outline: Option<PropOutline>,
 }
 
 impl Prop {
@@ -29,52 +39,65 @@ impl Prop {
     }
 
     /// Generate a new, empty prop  with a white translucent background
-    pub fn new(name: impl ToString, width: u32, height: u32) -> Prop {
-        let mut items = HashMap::new();
-        let root_builder = ItemBuilder::build_image_from_rect(width, height, "white", None, 0.1);
-        let root = root_builder.build().unwrap(); // TODO: Handle error
-        let id = root.get_id();
-        items.insert(root.get_id(), root);
-
-        let template = BaseTemplate {
-            items,
-            root: id,
-            size: (width, height),
+    /// The id is the ID of the prop within its containing template (InternalId::UNCONTAINED if none)
+    pub fn new(id: InternalId, name: impl ToString, width: u32, height: u32) -> Prop {
+        let background = Shape::Rectangle {
+            width: width as f32,
+            height: height as f32,
         };
+        let root_builder = ItemBuilder::build_shape(PropItemShape::filled(
+            background,
+            Paint::Solid((255, 255, 255, 26)),
+        ));
+        let template = BaseTemplate::new(root_builder, (width, height)).unwrap(); // TODO: Handle error
 
         Prop {
-            id: InternalId::new(),
+            id,
             name: name.to_string(),
             template,
             staging: StagingContext::new(),
+            outline: None,
         }
     }
 
     // TODO: Remove, this is just to generate random props for testing
     /// Generate a new prop with a random structure
-    pub fn build_random(name: impl ToString, width: u32, height: u32) -> Prop {
+    /// The same generator state will always produce the same structure
+    pub fn build_random(
+        id: InternalId,
+        name: impl ToString,
+        width: u32,
+        height: u32,
+        rng: &mut impl Rng,
+    ) -> Prop {
         let center_x = (width / 2) as i32;
         let center_y = (height / 2) as i32;
 
         // TODO: commentate
-        let prop = Prop::new(name, width, height);
+        let prop = Prop::new(id, name, width, height);
         let mut prop = Base::new(prop.into());
         // building a "new object"" so we use a baseitem as if it were in a stage
 
         // Add a randomly sized translucent rectangle as the background
-        let rect_width = 100 + (random() * 200.0) as u32;
-        let rect_height = 100 + (random() * 200.0) as u32;
+        let rect_width = 100 + (rng.gen::<f64>() * 200.0) as u32;
+        let rect_height = 100 + (rng.gen::<f64>() * 200.0) as u32;
         let rect_dx = center_x - (rect_width as i32 / 2);
         let rect_dy = center_y - (rect_height as i32 / 2);
 
         let rect = prop
             .add_child(
-                ItemBuilder::build_image_from_rect(rect_width, rect_height, "blue", None, 0.5)
-                    .name("Rectangle")
-                    .transform(RelativeTransform {
-                        position: (rect_dx as f32, rect_dy as f32),
-                        ..Default::default()
-                    }),
+                ItemBuilder::build_shape(PropItemShape::filled(
+                    Shape::Rectangle {
+                        width: rect_width as f32,
+                        height: rect_height as f32,
+                    },
+                    Paint::Solid((0, 0, 255, 128)),
+                ))
+                .name("Rectangle")
+                .transform(RelativeTransform {
+                    position: (rect_dx as f32, rect_dy as f32),
+                    ..Default::default()
+                }),
             )
             .unwrap(); // TODO: Handle error
 
@@ -83,11 +106,11 @@ impl Prop {
             .add_child(
                 ItemBuilder::build_image_from_bytes(
                     include_bytes!("../../../../res/testimg.jpg").to_vec(),
-                    "jpg",
+                    ImageImportLimits::default(),
                 )
                 .parent(rect)
                 .name("Image")
-                .transform(RelativeTransform::build_random(100)),
+                .transform(RelativeTransform::build_random(100, rng)),
             )
             .unwrap(); // TODO: Handle error
 
@@ -96,7 +119,7 @@ impl Prop {
             ItemBuilder::build_text_basic("Hello, world!")
                 .name("Text")
                 .parent(image)
-                .transform(RelativeTransform::build_random(100)),
+                .transform(RelativeTransform::build_random(100, rng)),
         )
         .unwrap(); // TODO: Handle error
 
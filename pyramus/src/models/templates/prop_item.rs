@@ -1,5 +1,11 @@
-use super::ids::InternalId;
-use crate::models::editor::staging::StagingContext;
+use super::{
+    adjustment::ImageAdjustments,
+    ids::InternalId,
+    paint::{Paint, Stroke},
+    path::PropItemPath,
+    shape::PropItemShape,
+};
+use crate::{models::editor::staging::StagingContext, render::parse_svg};
 use resvg::usvg::{self, NonZeroPositiveF32};
 use serde::{Deserialize, Serialize};
 use std::{rc::Rc, sync::Arc};
@@ -32,6 +38,8 @@ impl PropItem {
 pub enum PropItemType {
     Image(PropItemImage),
     Text(PropItemText),
+    Shape(PropItemShape),
+    Path(PropItemPath),
 }
 
 impl From<PropItemImage> for PropItemType {
@@ -44,6 +52,16 @@ impl From<PropItemText> for PropItemType {
         PropItemType::Text(item)
     }
 }
+impl From<PropItemShape> for PropItemType {
+    fn from(item: PropItemShape) -> Self {
+        PropItemType::Shape(item)
+    }
+}
+impl From<PropItemPath> for PropItemType {
+    fn from(item: PropItemPath) -> Self {
+        PropItemType::Path(item)
+    }
+}
 
 impl PropItemType {
     // x0, y0, x1, y1
@@ -51,7 +69,29 @@ impl PropItemType {
         match self {
             // TODO: Text bounds
             PropItemType::Text(_) => (0.0, 0.0, 0.0, 0.0),
-            PropItemType::Image(image) => (0.0, 0.0, image.viewport_width, image.viewport_height),
+            PropItemType::Image(image) => image.get_local_bounds(),
+            PropItemType::Shape(shape) => shape.get_local_bounds(),
+            PropItemType::Path(path) => path.get_local_bounds(),
+        }
+    }
+
+    /// The fill and stroke of the item, if it has them (images do not)
+    pub fn get_style(&self) -> Option<(Option<&Paint>, Option<&Stroke>)> {
+        match self {
+            PropItemType::Text(text) => Some((text.fill.as_ref(), text.stroke.as_ref())),
+            PropItemType::Shape(shape) => Some((shape.fill.as_ref(), shape.stroke.as_ref())),
+            PropItemType::Path(path) => Some((path.fill.as_ref(), path.stroke.as_ref())),
+            PropItemType::Image(_) => None,
+        }
+    }
+
+    /// The fill and stroke of the item, mutably, if it has them (images do not)
+    pub fn get_style_mut(&mut self) -> Option<(&mut Option<Paint>, &mut Option<Stroke>)> {
+        match self {
+            PropItemType::Text(text) => Some((&mut text.fill, &mut text.stroke)),
+            PropItemType::Shape(shape) => Some((&mut shape.fill, &mut shape.stroke)),
+            PropItemType::Path(path) => Some((&mut path.fill, &mut path.stroke)),
+            PropItemType::Image(_) => None,
         }
     }
 }
@@ -63,7 +103,10 @@ pub struct PropItemText {
     #[serde(deserialize_with = "deserialize_nonzero_f32")]
     #[serde(serialize_with = "serialize_nonzero_f32")]
     pub font_size: NonZeroPositiveF32,
-    pub color: (u8, u8, u8),
+    #[serde(alias = "color", default, deserialize_with = "deserialize_text_fill")]
+    pub fill: Option<Paint>,
+    #[serde(default)]
+    pub stroke: Option<Stroke>,
     pub italic: bool,
 }
 
@@ -77,6 +120,22 @@ where
     NonZeroPositiveF32::new(f)
         .ok_or_else(|| serde::de::Error::custom("Font size must be greater than 0"))
 }
+// Older documents store the text color as a bare RGB tuple
+fn deserialize_text_fill<'de, D>(deserializer: D) -> Result<Option<Paint>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum TextFill {
+        Paint(Option<Paint>),
+        Rgb((u8, u8, u8)),
+    }
+    Ok(match TextFill::deserialize(deserializer)? {
+        TextFill::Paint(paint) => paint,
+        TextFill::Rgb(color) => Some(color.into()),
+    })
+}
 fn serialize_nonzero_f32<S>(f: &NonZeroPositiveF32, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: serde::Serializer,
@@ -89,8 +148,7 @@ where
     D: serde::Deserializer<'de>,
 {
     let data = String::deserialize(deserializer)?;
-    let tree =
-        usvg::Tree::from_str(&data, &usvg::Options::default()).map_err(serde::de::Error::custom)?;
+    let tree = parse_svg(&data).map_err(serde::de::Error::custom)?;
     Ok(Rc::new(tree))
 }
 
@@ -108,6 +166,64 @@ pub struct PropItemImage {
     pub data: PropItemImageData,
     pub viewport_width: f32,
     pub viewport_height: f32,
+    /// The part of the image that is shown, if it is cropped
+    /// Cropping does not change the image data, and the image keeps its position
+    #[serde(default)]
+    pub crop: Option<ImageCrop>,
+    /// The size of the image as it was imported, if it was downscaled or recompressed to fit the import limits
+    #[serde(default)]
+    pub original_size: Option<ImageOriginalSize>,
+    /// Color adjustments applied when rendering (raster images only)
+    #[serde(default)]
+    pub adjustments: ImageAdjustments,
+}
+
+/// The dimensions and encoded size (in bytes) of an image before it was imported
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ImageOriginalSize {
+    pub width: u32,
+    pub height: u32,
+    pub bytes: usize,
+}
+
+/// A rectangle within an image's viewport
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ImageCrop {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl ImageCrop {
+    /// Check that the crop has a finite, non-zero size
+    pub fn validate(&self) -> crate::Result<()> {
+        let finite = [self.x, self.y, self.width, self.height]
+            .iter()
+            .all(|v| v.is_finite());
+        if !(finite && self.width > 0.0 && self.height > 0.0) {
+            return Err(crate::PyramusError::InvalidSize(self.width, self.height));
+        }
+        Ok(())
+    }
+}
+
+impl PropItemImage {
+    // x0, y0, x1, y1
+    /// The bounds of the visible part of the image (the crop, limited to the viewport)
+    pub fn get_local_bounds(&self) -> (f32, f32, f32, f32) {
+        let (width, height) = (self.viewport_width, self.viewport_height);
+        match self.crop {
+            Some(crop) => {
+                let x0 = crop.x.max(0.0).min(width);
+                let y0 = crop.y.max(0.0).min(height);
+                let x1 = (crop.x + crop.width).min(width).max(x0);
+                let y1 = (crop.y + crop.height).min(height).max(y0);
+                (x0, y0, x1, y1)
+            }
+            None => (0.0, 0.0, width, height),
+        }
+    }
 }
 
 // TODO: This cannot be directly serializable. It serializes every byte of the image
@@ -0,0 +1,271 @@
+use std::f32::consts::PI;
+
+use resvg::usvg::tiny_skia_path::{Path, PathBuilder, Rect};
+use serde::{Deserialize, Serialize};
+
+use crate::InvalidReason;
+
+use super::paint::{Paint, Stroke};
+
+/// A vector shape prop item, rendered directly as a path
+#[derive(Debug, Clone, Serialize, Deserialize)] // TODO: remove clone
+pub struct PropItemShape {
+    pub shape: Shape,
+
+    /// Fill, or None for no fill (lines are never filled)
+    pub fill: Option<Paint>,
+    /// Stroke, or None for no stroke
+    pub stroke: Option<Stroke>,
+}
+
+/// The geometry of a shape
+/// All shapes are drawn within the box (0, 0) to (width, height) in local space
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Shape {
+    Rectangle {
+        width: f32,
+        height: f32,
+    },
+    /// Rectangle with corners rounded by `radius` (clamped to half the shortest side)
+    RoundedRectangle {
+        width: f32,
+        height: f32,
+        radius: f32,
+    },
+    Ellipse {
+        width: f32,
+        height: f32,
+    },
+    /// Regular polygon with `sides` sides, pointing upwards
+    Polygon {
+        width: f32,
+        height: f32,
+        sides: u32,
+    },
+    /// Star with `points` points, pointing upwards
+    /// `inner_ratio` is the radius of the inner vertices relative to the outer ones (0.0 to 1.0)
+    Star {
+        width: f32,
+        height: f32,
+        points: u32,
+        inner_ratio: f32,
+    },
+    /// Straight line from (0, 0) to (width, height)
+    /// (Either may be negative)
+    Line {
+        width: f32,
+        height: f32,
+    },
+}
+
+impl PropItemShape {
+    /// Create a shape with a fill, and no stroke
+    pub fn filled(shape: Shape, fill: impl Into<Paint>) -> PropItemShape {
+        PropItemShape {
+            shape,
+            fill: Some(fill.into()),
+            stroke: None,
+        }
+    }
+
+    /// Create a shape with a stroke, and no fill
+    pub fn stroked(shape: Shape, stroke: Stroke) -> PropItemShape {
+        PropItemShape {
+            shape,
+            fill: None,
+            stroke: Some(stroke),
+        }
+    }
+
+    // x0, y0, x1, y1
+    pub fn get_local_bounds(&self) -> (f32, f32, f32, f32) {
+        self.shape.get_local_bounds()
+    }
+}
+
+impl Shape {
+    // Most sides of a polygon, or points of a star (more are indistinguishable from an ellipse, and slow to draw)
+    pub const MAX_VERTICES: u32 = 1000;
+
+    /// Check that the shape can be drawn
+    pub fn validate(&self) -> crate::Result<()> {
+        match *self {
+            // Lines may run in any direction, so only need to be finite
+            Shape::Line { width, height } => {
+                if !width.is_finite() || !height.is_finite() {
+                    return invalid_shape("size", InvalidReason::NotFinite);
+                }
+                return Ok(());
+            }
+            Shape::Rectangle { width, height }
+            | Shape::RoundedRectangle { width, height, .. }
+            | Shape::Ellipse { width, height }
+            | Shape::Polygon { width, height, .. }
+            | Shape::Star { width, height, .. } => {
+                if !(width.is_finite() && width >= 0.0 && height.is_finite() && height >= 0.0) {
+                    return invalid_shape("size", InvalidReason::Negative);
+                }
+            }
+        }
+        match *self {
+            Shape::RoundedRectangle { radius, .. } if !(radius.is_finite() && radius >= 0.0) => {
+                invalid_shape("radius", InvalidReason::Negative)
+            }
+            Shape::Polygon { sides, .. } if !(3..=Shape::MAX_VERTICES).contains(&sides) => {
+                invalid_shape(
+                    "sides",
+                    InvalidReason::OutOfRange {
+                        min: 3.0,
+                        max: Shape::MAX_VERTICES as f32,
+                    },
+                )
+            }
+            Shape::Star { points, .. } if !(2..=Shape::MAX_VERTICES).contains(&points) => {
+                invalid_shape(
+                    "points",
+                    InvalidReason::OutOfRange {
+                        min: 2.0,
+                        max: Shape::MAX_VERTICES as f32,
+                    },
+                )
+            }
+            Shape::Star { inner_ratio, .. } if !(0.0..=1.0).contains(&inner_ratio) => {
+                invalid_shape(
+                    "inner_ratio",
+                    InvalidReason::OutOfRange { min: 0.0, max: 1.0 },
+                )
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // x0, y0, x1, y1
+    pub fn get_local_bounds(&self) -> (f32, f32, f32, f32) {
+        match *self {
+            Shape::Line { width, height } => (
+                f32::min(0.0, width),
+                f32::min(0.0, height),
+                f32::max(0.0, width),
+                f32::max(0.0, height),
+            ),
+            Shape::Rectangle { width, height }
+            | Shape::RoundedRectangle { width, height, .. }
+            | Shape::Ellipse { width, height }
+            | Shape::Polygon { width, height, .. }
+            | Shape::Star { width, height, .. } => (0.0, 0.0, width, height),
+        }
+    }
+
+    /// Whether the shape is a closed area that can be filled
+    pub fn is_closed(&self) -> bool {
+        !matches!(self, Shape::Line { .. })
+    }
+
+    /// Build the path of the shape in local space
+    /// None if the shape is degenerate (e.g. zero-sized)
+    pub fn to_path(&self) -> Option<Path> {
+        match *self {
+            Shape::Rectangle { width, height } => Some(PathBuilder::from_rect(Rect::from_xywh(
+                0.0, 0.0, width, height,
+            )?)),
+            Shape::RoundedRectangle {
+                width,
+                height,
+                radius,
+            } => rounded_rect_path(width, height, radius),
+            Shape::Ellipse { width, height } => {
+                PathBuilder::from_oval(Rect::from_xywh(0.0, 0.0, width, height)?)
+            }
+            Shape::Polygon {
+                width,
+                height,
+                sides,
+            } => {
+                // Shapes from loaded documents may not have been validated
+                let sides = sides.min(Shape::MAX_VERTICES);
+                let vertices = (0..sides).map(|i| (i as f32 / sides as f32, 1.0));
+                closed_radial_path(width, height, vertices)
+            }
+            Shape::Star {
+                width,
+                height,
+                points,
+                inner_ratio,
+            } => {
+                let inner_ratio = inner_ratio.clamp(0.0, 1.0);
+                // Alternating outer and inner vertices
+                let count = points.min(Shape::MAX_VERTICES).saturating_mul(2);
+                let vertices = (0..count).map(|i| {
+                    let radius = if i % 2 == 0 { 1.0 } else { inner_ratio };
+                    (i as f32 / count as f32, radius)
+                });
+                closed_radial_path(width, height, vertices)
+            }
+            Shape::Line { width, height } => {
+                let mut builder = PathBuilder::new();
+                builder.move_to(0.0, 0.0);
+                builder.line_to(width, height);
+                builder.finish()
+            }
+        }
+    }
+}
+
+fn invalid_shape(field: &'static str, reason: InvalidReason) -> crate::Result<()> {
+    Err(crate::PyramusError::InvalidShape { field, reason })
+}
+
+/// Build a closed path around the center of the box (0, 0) to (width, height)
+/// Each vertex is (turn, radius), where turn is the fraction of a full turn clockwise from the top,
+/// and radius is relative to the half-size of the box.
+fn closed_radial_path(
+    width: f32,
+    height: f32,
+    vertices: impl Iterator<Item = (f32, f32)>,
+) -> Option<Path> {
+    let (rx, ry) = (width / 2.0, height / 2.0);
+    let mut builder = PathBuilder::new();
+    for (i, (turn, radius)) in vertices.enumerate() {
+        let angle = turn * 2.0 * PI - PI / 2.0;
+        let x = rx + rx * radius * angle.cos();
+        let y = ry + ry * radius * angle.sin();
+        if i == 0 {
+            builder.move_to(x, y);
+        } else {
+            builder.line_to(x, y);
+        }
+    }
+    builder.close();
+    builder.finish()
+}
+
+fn rounded_rect_path(width: f32, height: f32, radius: f32) -> Option<Path> {
+    let r = radius.min(f32::min(width, height) / 2.0).max(0.0);
+    if r == 0.0 {
+        return Some(PathBuilder::from_rect(Rect::from_xywh(
+            0.0, 0.0, width, height,
+        )?));
+    }
+
+    // Distance of the control points of a cubic bezier approximating a quarter circle
+    let k = r * 0.552_284_8;
+    let mut builder = PathBuilder::new();
+    builder.move_to(r, 0.0);
+    builder.line_to(width - r, 0.0);
+    builder.cubic_to(width - r + k, 0.0, width, r - k, width, r);
+    builder.line_to(width, height - r);
+    builder.cubic_to(
+        width,
+        height - r + k,
+        width - r + k,
+        height,
+        width - r,
+        height,
+    );
+    builder.line_to(r, height);
+    builder.cubic_to(r - k, height, 0.0, height - r + k, 0.0, height - r);
+    builder.line_to(0.0, r);
+    builder.cubic_to(0.0, r - k, r - k, 0.0, r, 0.0);
+    builder.close();
+    builder.finish()
+}
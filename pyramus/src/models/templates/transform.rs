@@ -1,6 +1,9 @@
-use js_sys::Math::random;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
+use super::ids::InternalId;
+use crate::PyramusError;
+
 /// A relative transform structure
 /// Represents a position, scale, and rotation relative to a parent
 // TODO: Is this still needed now that we have Affine2?
@@ -24,16 +27,26 @@ impl Default for RelativeTransform {
 // TODO: From?
 impl RelativeTransform {
     // TODO: Remove, this is just to generate random transforms for testing
-    pub fn build_random(variance: u32) -> RelativeTransform {
-        let dx = random() as f32 * variance as f32;
-        let dy = random() as f32 * variance as f32;
+    pub fn build_random(variance: u32, rng: &mut impl Rng) -> RelativeTransform {
+        let dx = rng.gen::<f32>() * variance as f32;
+        let dy = rng.gen::<f32>() * variance as f32;
         RelativeTransform {
             position: (dx, dy),
-            scale: (random() as f32 * 2.0, random() as f32 * 2.0),
-            rotation: random() as f32 * 360.0,
+            // Kept away from 0, which would flatten the item and leave its transform with no inverse
+            scale: (0.5 + rng.gen::<f32>() * 1.5, 0.5 + rng.gen::<f32>() * 1.5),
+            rotation: rng.gen::<f32>() * 360.0,
         }
     }
 
+    /// Whether every part of the transform is finite (not NaN or infinite)
+    pub fn is_finite(&self) -> bool {
+        self.position.0.is_finite()
+            && self.position.1.is_finite()
+            && self.scale.0.is_finite()
+            && self.scale.1.is_finite()
+            && self.rotation.is_finite()
+    }
+
     pub fn to_glam_affine(&self) -> glam::Affine2 {
         let (x, y) = self.position;
         let (sx, sy) = self.scale;
@@ -44,4 +57,33 @@ impl RelativeTransform {
             glam::Vec2::new(x, y),
         )
     }
+
+    /// Get the relative transform closest to an affine transform
+    /// Transforms with a skew (for example, from scaling a rotated item by different amounts on each axis)
+    /// cannot be represented, so the skew is dropped
+    pub fn from_glam_affine(affine: glam::Affine2) -> RelativeTransform {
+        let x_axis = affine.matrix2.x_axis;
+        let scale_x = x_axis.length();
+        // The y scale is negative for mirrored transforms
+        let scale_y = if scale_x == 0.0 {
+            affine.matrix2.y_axis.length()
+        } else {
+            affine.matrix2.determinant() / scale_x
+        };
+        RelativeTransform {
+            position: (affine.translation.x, affine.translation.y),
+            scale: (scale_x, scale_y),
+            rotation: x_axis.y.atan2(x_axis.x).to_degrees(),
+        }
+    }
+}
+
+/// Invert the screen transform of an item, to take screen points into its local space
+/// Fails if the transform has no area (for example, from a scale of 0), as it cannot be inverted
+pub fn invert_transform(transform: glam::Affine2, id: InternalId) -> crate::Result<glam::Affine2> {
+    let inverse = transform.inverse();
+    if !inverse.is_finite() {
+        return Err(PyramusError::SingularTransform(id));
+    }
+    Ok(inverse)
 }
@@ -1,13 +1,27 @@
 use crate::{
+    input::{MouseState, Tool},
     models::{
-        editor::{item::StageItem, stage::Stage, usvg_node::ToUsvgNode},
-        templates::prop_item::{PropItem, PropItemType},
+        editor::{
+            base_item::Base,
+            item::StageItem,
+            stage::Stage,
+            staging::BlendMode,
+            usvg_node::{to_transform, ToUsvgNode},
+        },
+        templates::{
+            effect::Effect,
+            paint::{GradientStop, LineCap, LineJoin, Paint, Stroke},
+            path::{PathNode, PropItemPath},
+            prop_item::{PropItem, PropItemImage, PropItemImageData, PropItemType},
+            shape::{PropItemShape, Shape},
+        },
     },
     PyramusError,
 };
+use std::{cell::RefCell, rc::Rc};
 use svgtypes::parse_font_families;
 
-use resvg::usvg::{self, Font, FontStyle, TextSpan, Transform, XmlOptions};
+use resvg::usvg::{self, filter, Font, FontStyle, TextSpan, Transform, XmlOptions};
 use usvg::fontdb;
 
 impl Stage {
@@ -15,8 +29,9 @@ impl Stage {
     /// This is the main function for rendering the stage
     /// All items on the stage are converted into usvg nodes recursively
     pub fn to_usvg_tree(&self) -> crate::Result<usvg::Tree> {
-        let width = self.base.get_size().0 as f32;
-        let height = self.base.get_size().1 as f32;
+        // While a nested prop is edited, the screen is still the size of the template at the top level
+        let (width, height) = self.get_top_level_base().get_size();
+        let (width, height) = (width as f32, height as f32);
         let mut tree = usvg::Tree {
             size: usvg::Size::from_wh(width, height)
                 .ok_or_else(|| PyramusError::InvalidSize(width, height))?,
@@ -29,16 +44,16 @@ impl Stage {
             root: usvg::Group::default(),
         };
 
-        // Recursively add children to the root node
-        // TODO: A slotmap may improve this, as we no longer need to hold a lock on the root node
-        let root: &StageItem = self
-            .base
-            .get_item(self.base.get_root())
-            .ok_or_else(|| PyramusError::OtherError("Root item not found in stage".to_string()))?;
+        // Check for missing items and cycles before recursing through the item graph
+        self.get_render_order()?;
 
-        {
-            tree.root.children.push(root.to_usvg_node(&self.base)?);
+        // The templates around an entered prop are drawn dimmed behind it, for context
+        for context in &self.edit_contexts {
+            tree.root
+                .children
+                .push(base_to_usvg_node(&context.base, CONTEXT_OPACITY)?);
         }
+        tree.root.children.push(base_to_usvg_node(&self.base, 1.0)?);
 
         // Add outlines overtop of the nodes
         for item in self.get_selections() {
@@ -47,21 +62,120 @@ impl Stage {
                 .push(item.to_outline_svg_node(&self.base)?);
         }
 
-        // Postprocessing step
-        // TODO: We shouldnt be repeatedly creating the fontdb- should be in a state, or something
-        let mut fontdb = fontdb::Database::new();
-        fontdb.load_system_fonts();
+        // Add the guides of the snaps in the current drag
+        if let MouseState::DraggingMovement(drag) = &self.mouse_state {
+            for guide in &drag.guides {
+                let nodes = vec![
+                    PathNode::corner(guide.from.0, guide.from.1),
+                    PathNode::corner(guide.to.0, guide.to.1),
+                ];
+                let stroke = Stroke::solid(Paint::Solid((255, 0, 255, 255)), 1.0);
+                tree.root
+                    .children
+                    .push(PropItemPath::stroked(nodes, stroke).to_usvg_node());
+            }
+        }
 
+        // Add the in-progress pencil stroke overtop of everything
+        if let (MouseState::Drawing(points), Tool::Pencil { stroke }) =
+            (&self.mouse_state, &self.tool)
+        {
+            let nodes = points
+                .iter()
+                .map(|&(x, y)| PathNode::corner(x, y))
+                .collect();
+            tree.root
+                .children
+                .push(PropItemPath::stroked(nodes, stroke.clone()).to_usvg_node());
+        }
+
+        // Postprocessing step
+        // `resvg` cannot render text as is, but text items are already converted into paths when their nodes
+        // are built (see `flatten_text`), as is text in SVG images when they are loaded (see `parse_svg`).
+        // So this only computes the transforms and bounding boxes of the nodes.
         let steps = resvg::usvg::PostProcessingSteps {
-            // `resvg` cannot render text as is. We have to convert it into paths first.
-            convert_text_into_paths: true,
+            convert_text_into_paths: false,
         };
-        tree.postprocess(steps, &fontdb);
+        with_fontdb(|fontdb| tree.postprocess(steps, fontdb));
 
         Ok(tree)
     }
 }
 
+/// Opacity of the templates drawn around an entered prop
+const CONTEXT_OPACITY: f32 = 0.3;
+
+/// Converts the items of a base into a usvg node, placed on the screen by its view transform
+fn base_to_usvg_node(base: &Base, opacity: f32) -> crate::Result<usvg::Node> {
+    // Recursively add children to the root node
+    // TODO: A slotmap may improve this, as we no longer need to hold a lock on the root node
+    let root: &StageItem = base
+        .get_item(base.get_root())
+        .ok_or(PyramusError::ItemNotFound(base.get_root()))?;
+
+    Ok(usvg::Node::Group(Box::new(usvg::Group {
+        transform: to_transform(base.view_transform),
+        opacity: usvg::Opacity::new_clamped(opacity),
+        children: vec![root.to_usvg_node(base.get_template(), &base.render_cache)?],
+        ..Default::default()
+    })))
+}
+
+thread_local! {
+    // Loading the system fonts is slow, so it is only done once
+    static FONTDB: fontdb::Database = {
+        let mut fontdb = fontdb::Database::new();
+        fontdb.load_system_fonts();
+        fontdb
+    };
+}
+
+/// Run a function with the database of system fonts
+pub fn with_fontdb<T>(f: impl FnOnce(&fontdb::Database) -> T) -> T {
+    FONTDB.with(f)
+}
+
+/// Parse an SVG image, converting its text into paths
+/// Images are drawn within the stage, which is postprocessed without converting text (see `Stage::to_usvg_tree`)
+pub fn parse_svg(svg: &str) -> Result<usvg::Tree, usvg::Error> {
+    let mut tree = usvg::Tree::from_str(svg, &usvg::Options::default())?;
+    let steps = usvg::PostProcessingSteps {
+        convert_text_into_paths: true,
+    };
+    with_fontdb(|fontdb| tree.postprocess(steps, fontdb));
+    Ok(tree)
+}
+
+/// Convert a text node into a group of paths
+/// Shaping text is the slowest part of rendering, so it is done once when the node is built
+/// (and cached with it), rather than every time the stage is postprocessed.
+fn flatten_text(node: usvg::Node) -> usvg::Node {
+    let mut tree = usvg::Tree {
+        size: usvg::Size::from_wh(1.0, 1.0).expect("1x1 is a valid size"),
+        view_box: usvg::ViewBox {
+            rect: usvg::NonZeroRect::from_xywh(0.0, 0.0, 1.0, 1.0).expect("1x1 is a valid size"),
+            aspect: usvg::AspectRatio::default(),
+        },
+        root: usvg::Group {
+            children: vec![node],
+            ..Default::default()
+        },
+    };
+    let steps = usvg::PostProcessingSteps {
+        convert_text_into_paths: true,
+    };
+    with_fontdb(|fontdb| tree.postprocess(steps, fontdb));
+
+    match tree.root.children.pop() {
+        Some(usvg::Node::Text(text)) => match text.flattened {
+            Some(flattened) => usvg::Node::Group(flattened),
+            // No font could shape the text, so leave it as is (it renders nothing)
+            None => usvg::Node::Text(text),
+        },
+        node => node.unwrap_or_else(|| usvg::Node::Group(Box::default())),
+    }
+}
+
 // TODO: Trait renderable
 impl PropItem {
     // From Graphite
@@ -76,10 +190,6 @@ impl PropItemType {
                 // An alternative would be simply parsing a string- but that's hacky, and it might reload fonts.
                 // TODO: Check if it reloads fonts, and/or find a way to do this more simply.
 
-                // TODO: pass fontdb or a config containing it through, or get from a state
-                let mut fontdb = fontdb::Database::new();
-                fontdb.load_system_fonts();
-
                 // todo: shouldn't hardcode this
                 let font_families = parse_font_families(&text.font_family)?;
 
@@ -117,15 +227,8 @@ impl PropItemType {
                                 weight: 12,
                                 stretch: usvg::FontStretch::Normal,
                             },
-                            fill: Some(usvg::Fill {
-                                paint: usvg::Paint::Color(usvg::Color {
-                                    red: text.color.0,
-                                    green: text.color.1,
-                                    blue: text.color.2,
-                                }),
-                                ..Default::default()
-                            }),
-                            stroke: None,
+                            fill: text.fill.as_ref().and_then(Paint::to_usvg_fill),
+                            stroke: text.stroke.as_ref().and_then(Stroke::to_usvg_stroke),
                             small_caps: false,
                             word_spacing: 0.0,
                             letter_spacing: 0.0,
@@ -146,28 +249,544 @@ impl PropItemType {
                         text_flow: usvg::TextFlow::Linear,
                     }],
                 }));
-                Ok(node)
+                Ok(flatten_text(node))
+            }
+            PropItemType::Shape(shape) => Ok(shape.to_usvg_node()),
+            PropItemType::Path(path) => Ok(path.to_usvg_node()),
+            PropItemType::Image(image) => {
+                let node = usvg::Node::Image(Box::new(usvg::Image {
+                    id: String::new(),
+                    abs_transform: Transform::identity(), // Set on postprocessing, not here
+                    bounding_box: None,
+                    visibility: usvg::Visibility::Visible,
+                    view_box: usvg::ViewBox {
+                        rect: usvg::NonZeroRect::from_xywh(
+                            0.,
+                            0.,
+                            image.viewport_width,
+                            image.viewport_height,
+                        )
+                        .ok_or_else(|| {
+                            PyramusError::InvalidSize(image.viewport_width, image.viewport_height)
+                        })?,
+                        aspect: usvg::AspectRatio::default(),
+                    },
+                    rendering_mode: usvg::ImageRendering::OptimizeSpeed,
+                    kind: image.to_image_kind()?,
+                }));
+                if image.crop.is_none() {
+                    return Ok(node);
+                }
+
+                // Cropping clips the image, without changing its data or position
+                let (x0, y0, x1, y1) = image.get_local_bounds();
+                let clip = PropItemShape::filled(
+                    Shape::Rectangle {
+                        width: x1 - x0,
+                        height: y1 - y0,
+                    },
+                    (0, 0, 0),
+                );
+                let clip_path = usvg::ClipPath {
+                    transform: Transform::from_translate(x0, y0),
+                    root: usvg::Group {
+                        children: vec![clip.to_usvg_node()],
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                };
+                Ok(usvg::Node::Group(Box::new(usvg::Group {
+                    clip_path: Some(Rc::new(RefCell::new(clip_path))),
+                    children: vec![node],
+                    ..Default::default()
+                })))
+            }
+        }
+    }
+}
+
+impl PropItemImage {
+    /// The image data to render, with any adjustments applied (SVG images are not adjusted)
+    pub fn to_image_kind(&self) -> crate::Result<usvg::ImageKind> {
+        match &self.data {
+            PropItemImageData::Png(bytes)
+            | PropItemImageData::Jpeg(bytes)
+            | PropItemImageData::Gif(bytes)
+                if !self.adjustments.is_identity() =>
+            {
+                Ok(usvg::ImageKind::PNG(self.adjustments.apply(bytes)?))
+            }
+            data => Ok(data.clone().into()),
+        }
+    }
+}
+
+impl PropItemShape {
+    /// Converts the shape into a usvg path node
+    /// Degenerate shapes (e.g. zero-sized) have no path, and produce an empty group
+    pub fn to_usvg_node(&self) -> usvg::Node {
+        let Some(path) = self.shape.to_path() else {
+            return usvg::Node::Group(Box::default());
+        };
+
+        let mut node = usvg::Path::new(Rc::new(path));
+        node.fill = self
+            .fill
+            .as_ref()
+            .filter(|_| self.shape.is_closed())
+            .and_then(Paint::to_usvg_fill);
+        node.stroke = self.stroke.as_ref().and_then(Stroke::to_usvg_stroke);
+        usvg::Node::Path(Box::new(node))
+    }
+}
+
+impl PropItemPath {
+    /// Converts the path into a usvg path node
+    /// Empty paths produce an empty group
+    pub fn to_usvg_node(&self) -> usvg::Node {
+        let Some(path) = self.to_path() else {
+            return usvg::Node::Group(Box::default());
+        };
+
+        let mut node = usvg::Path::new(Rc::new(path));
+        node.fill = self
+            .fill
+            .as_ref()
+            .filter(|_| self.closed)
+            .and_then(Paint::to_usvg_fill);
+        node.stroke = self.stroke.as_ref().and_then(Stroke::to_usvg_stroke);
+        usvg::Node::Path(Box::new(node))
+    }
+}
+
+impl Paint {
+    /// Converts the paint into a usvg paint, and the opacity it should be drawn with
+    /// None if the paint cannot be rendered (see `Paint::validate`)
+    pub fn to_usvg_paint(&self) -> Option<(usvg::Paint, usvg::Opacity)> {
+        match self {
+            Paint::Solid(color) => Some((
+                usvg::Paint::Color(to_usvg_color(*color)),
+                to_opacity(*color),
+            )),
+            Paint::LinearGradient { start, end, stops } => {
+                let gradient = usvg::LinearGradient {
+                    x1: start.0,
+                    y1: start.1,
+                    x2: end.0,
+                    y2: end.1,
+                    base: to_base_gradient(stops)?,
+                };
+                Some((
+                    usvg::Paint::LinearGradient(Rc::new(gradient)),
+                    usvg::Opacity::ONE,
+                ))
+            }
+            Paint::RadialGradient {
+                center,
+                radius,
+                stops,
+            } => {
+                let gradient = usvg::RadialGradient {
+                    cx: center.0,
+                    cy: center.1,
+                    r: usvg::PositiveF32::new(*radius)?,
+                    fx: center.0,
+                    fy: center.1,
+                    base: to_base_gradient(stops)?,
+                };
+                Some((
+                    usvg::Paint::RadialGradient(Rc::new(gradient)),
+                    usvg::Opacity::ONE,
+                ))
+            }
+            Paint::Pattern {
+                image,
+                width,
+                height,
+            } => {
+                let rect = usvg::NonZeroRect::from_xywh(0.0, 0.0, *width, *height)?;
+                let tile = usvg::Node::Image(Box::new(usvg::Image {
+                    id: String::new(),
+                    abs_transform: Transform::identity(), // Set on postprocessing, not here
+                    bounding_box: None,
+                    visibility: usvg::Visibility::Visible,
+                    view_box: usvg::ViewBox {
+                        rect,
+                        aspect: usvg::AspectRatio::default(),
+                    },
+                    rendering_mode: usvg::ImageRendering::OptimizeSpeed,
+                    kind: image.clone().into(),
+                }));
+                let pattern = usvg::Pattern {
+                    id: String::new(),
+                    units: usvg::Units::UserSpaceOnUse,
+                    content_units: usvg::Units::UserSpaceOnUse,
+                    transform: Transform::identity(),
+                    rect,
+                    view_box: None,
+                    root: usvg::Group {
+                        children: vec![tile],
+                        ..Default::default()
+                    },
+                };
+                Some((
+                    usvg::Paint::Pattern(Rc::new(RefCell::new(pattern))),
+                    usvg::Opacity::ONE,
+                ))
             }
-            PropItemType::Image(image) => Ok(usvg::Node::Image(Box::new(usvg::Image {
-                id: String::new(),
-                abs_transform: Transform::identity(), // Set on postprocessing, not here
-                bounding_box: None,
-                visibility: usvg::Visibility::Visible,
-                view_box: usvg::ViewBox {
-                    rect: usvg::NonZeroRect::from_xywh(
-                        0.,
-                        0.,
-                        image.viewport_width,
-                        image.viewport_height,
-                    )
-                    .ok_or_else(|| {
-                        PyramusError::InvalidSize(image.viewport_width, image.viewport_height)
-                    })?,
-                    aspect: usvg::AspectRatio::default(),
-                },
-                rendering_mode: usvg::ImageRendering::OptimizeSpeed,
-                kind: image.data.clone().into(),
-            }))),
+        }
+    }
+
+    /// Converts the paint into a usvg fill
+    pub fn to_usvg_fill(&self) -> Option<usvg::Fill> {
+        let (paint, opacity) = self.to_usvg_paint()?;
+        Some(usvg::Fill {
+            paint,
+            opacity,
+            ..Default::default()
+        })
+    }
+}
+
+impl Stroke {
+    /// Converts the stroke into a usvg stroke
+    /// None if the stroke has no width, or cannot be rendered
+    pub fn to_usvg_stroke(&self) -> Option<usvg::Stroke> {
+        let (paint, opacity) = self.paint.to_usvg_paint()?;
+
+        // An odd number of dash lengths is repeated to make it even, as in SVG
+        let dasharray = if self.dash.is_empty() || self.dash.iter().sum::<f32>() <= 0.0 {
+            None
+        } else if self.dash.len() % 2 == 1 {
+            Some(self.dash.repeat(2))
+        } else {
+            Some(self.dash.clone())
+        };
+
+        Some(usvg::Stroke {
+            paint,
+            opacity,
+            width: usvg::StrokeWidth::new(self.width)?,
+            dasharray,
+            dashoffset: self.dash_offset,
+            linecap: self.line_cap.into(),
+            linejoin: self.line_join.into(),
+            ..Default::default()
+        })
+    }
+}
+
+fn to_usvg_color((red, green, blue, _): (u8, u8, u8, u8)) -> usvg::Color {
+    usvg::Color { red, green, blue }
+}
+
+fn to_opacity((_, _, _, alpha): (u8, u8, u8, u8)) -> usvg::Opacity {
+    usvg::Opacity::new_clamped(alpha as f32 / 255.0)
+}
+
+fn to_base_gradient(stops: &[GradientStop]) -> Option<usvg::BaseGradient> {
+    if stops.is_empty() {
+        return None;
+    }
+    Some(usvg::BaseGradient {
+        id: String::new(),
+        // Gradients are positioned relative to the bounding box of the painted item
+        units: usvg::Units::ObjectBoundingBox,
+        transform: Transform::identity(),
+        spread_method: usvg::SpreadMethod::Pad,
+        stops: stops
+            .iter()
+            .map(|stop| usvg::Stop {
+                offset: usvg::StopOffset::new_clamped(stop.offset),
+                color: to_usvg_color(stop.color),
+                opacity: to_opacity(stop.color),
+            })
+            .collect(),
+    })
+}
+
+/// Convert the effects of an item into a single filter, where each effect is applied to the result of the previous one
+/// None if there are no effects
+pub fn effects_to_usvg_filter(effects: &[Effect]) -> Option<filter::SharedFilter> {
+    if effects.is_empty() {
+        return None;
+    }
+
+    let mut builder = FilterBuilder::default();
+    let mut input = filter::Input::SourceGraphic;
+    for effect in effects {
+        input = effect.push_primitives(&mut builder, input);
+    }
+
+    Some(Rc::new(RefCell::new(filter::Filter {
+        id: String::new(),
+        // The filter region extends past the item by half its size on each side, to leave room for shadows and blurs
+        units: usvg::Units::ObjectBoundingBox,
+        primitive_units: usvg::Units::UserSpaceOnUse,
+        rect: usvg::NonZeroRect::from_xywh(-0.5, -0.5, 2.0, 2.0)?,
+        primitives: builder.primitives,
+    })))
+}
+
+/// Builds a list of filter primitives, giving each a unique result name
+#[derive(Default)]
+struct FilterBuilder {
+    primitives: Vec<filter::Primitive>,
+}
+
+impl FilterBuilder {
+    /// Add a primitive, returning an input referencing its result
+    fn push(&mut self, kind: filter::Kind) -> filter::Input {
+        let result = format!("effect{}", self.primitives.len());
+        self.primitives.push(filter::Primitive {
+            x: None,
+            y: None,
+            width: None,
+            height: None,
+            color_interpolation: filter::ColorInterpolation::SRGB,
+            result: result.clone(),
+            kind,
+        });
+        filter::Input::Reference(result)
+    }
+
+    fn color_matrix(&mut self, input: filter::Input, matrix: Vec<f32>) -> filter::Input {
+        self.push(filter::Kind::ColorMatrix(filter::ColorMatrix {
+            input,
+            kind: filter::ColorMatrixKind::Matrix(matrix),
+        }))
+    }
+
+    fn composite(
+        &mut self,
+        input1: filter::Input,
+        input2: filter::Input,
+        operator: filter::CompositeOperator,
+    ) -> filter::Input {
+        self.push(filter::Kind::Composite(filter::Composite {
+            input1,
+            input2,
+            operator,
+        }))
+    }
+
+    fn fractal_noise(&mut self, frequency: f32, octaves: u32, seed: i32) -> filter::Input {
+        let frequency = positive(frequency);
+        self.push(filter::Kind::Turbulence(filter::Turbulence {
+            base_frequency_x: frequency,
+            base_frequency_y: frequency,
+            num_octaves: octaves,
+            seed,
+            stitch_tiles: false,
+            kind: filter::TurbulenceKind::FractalNoise,
+        }))
+    }
+
+    /// Blend a texture over the input, only where the input is visible
+    fn blend_texture(
+        &mut self,
+        texture: filter::Input,
+        input: filter::Input,
+        mode: usvg::BlendMode,
+    ) -> filter::Input {
+        let texture = self.composite(texture, input.clone(), filter::CompositeOperator::In);
+        self.push(filter::Kind::Blend(filter::Blend {
+            input1: texture,
+            input2: input,
+            mode,
+        }))
+    }
+}
+
+impl Effect {
+    /// Add the filter primitives for the effect, returning an input referencing the final result
+    fn push_primitives(&self, builder: &mut FilterBuilder, input: filter::Input) -> filter::Input {
+        match *self {
+            Effect::DropShadow {
+                offset: (dx, dy),
+                blur,
+                color,
+            } => builder.push(filter::Kind::DropShadow(filter::DropShadow {
+                input,
+                dx,
+                dy,
+                // As in CSS, the blur radius is twice the standard deviation
+                std_dev_x: positive(blur / 2.0),
+                std_dev_y: positive(blur / 2.0),
+                color: to_usvg_color(color),
+                opacity: to_opacity(color),
+            })),
+            Effect::Blur { radius } => {
+                builder.push(filter::Kind::GaussianBlur(filter::GaussianBlur {
+                    input,
+                    std_dev_x: positive(radius),
+                    std_dev_y: positive(radius),
+                }))
+            }
+            Effect::Sepia { amount } => {
+                // The sepia matrix from the CSS filter effects spec
+                let a = 1.0 - amount.clamp(0.0, 1.0);
+                #[rustfmt::skip]
+                let matrix = vec![
+                    0.393 + 0.607 * a, 0.769 - 0.769 * a, 0.189 - 0.189 * a, 0.0, 0.0,
+                    0.349 - 0.349 * a, 0.686 + 0.314 * a, 0.168 - 0.168 * a, 0.0, 0.0,
+                    0.272 - 0.272 * a, 0.534 - 0.534 * a, 0.131 + 0.869 * a, 0.0, 0.0,
+                    0.0, 0.0, 0.0, 1.0, 0.0,
+                ];
+                builder.color_matrix(input, matrix)
+            }
+            Effect::Grayscale { amount } => {
+                // The grayscale matrix from the CSS filter effects spec
+                let a = 1.0 - amount.clamp(0.0, 1.0);
+                #[rustfmt::skip]
+                let matrix = vec![
+                    0.2126 + 0.7874 * a, 0.7152 - 0.7152 * a, 0.0722 - 0.0722 * a, 0.0, 0.0,
+                    0.2126 - 0.2126 * a, 0.7152 + 0.2848 * a, 0.0722 - 0.0722 * a, 0.0, 0.0,
+                    0.2126 - 0.2126 * a, 0.7152 - 0.7152 * a, 0.0722 + 0.9278 * a, 0.0, 0.0,
+                    0.0, 0.0, 0.0, 1.0, 0.0,
+                ];
+                builder.color_matrix(input, matrix)
+            }
+            Effect::Noise {
+                amount,
+                scale,
+                seed,
+            } => {
+                let noise = builder.fractal_noise(1.0 / scale, 2, seed);
+                // Gray noise, at a constant alpha of `amount`
+                #[rustfmt::skip]
+                let grain = builder.color_matrix(noise, vec![
+                    1.0, 0.0, 0.0, 0.0, 0.0,
+                    1.0, 0.0, 0.0, 0.0, 0.0,
+                    1.0, 0.0, 0.0, 0.0, 0.0,
+                    0.0, 0.0, 0.0, 0.0, amount,
+                ]);
+                builder.blend_texture(grain, input, usvg::BlendMode::Overlay)
+            }
+            Effect::PaperTexture {
+                amount,
+                scale,
+                color: (red, green, blue),
+                seed,
+            } => {
+                let noise = builder.fractal_noise(1.0 / scale, 4, seed);
+                // Blotches of the paper color, lighter where the noise is stronger
+                let (r, g, b) = (
+                    red as f32 / 255.0,
+                    green as f32 / 255.0,
+                    blue as f32 / 255.0,
+                );
+                #[rustfmt::skip]
+                let paper = builder.color_matrix(noise, vec![
+                    0.6 * r, 0.0, 0.0, 0.0, 0.5 * r,
+                    0.6 * g, 0.0, 0.0, 0.0, 0.5 * g,
+                    0.6 * b, 0.0, 0.0, 0.0, 0.5 * b,
+                    0.0, 0.0, 0.0, 0.0, amount,
+                ]);
+                builder.blend_texture(paper, input, usvg::BlendMode::Multiply)
+            }
+            Effect::BurnedEdges { width, color, seed } => {
+                if !(width.is_finite() && width > 0.0) {
+                    return input;
+                }
+                // Roughen the edges by displacing the item with noise
+                // The noise is made opaque, as resvg reads the displacement from premultiplied colors
+                let noise = builder.fractal_noise(0.05, 3, seed);
+                #[rustfmt::skip]
+                let noise = builder.color_matrix(noise, vec![
+                    1.0, 0.0, 0.0, 0.0, 0.0,
+                    0.0, 1.0, 0.0, 0.0, 0.0,
+                    0.0, 0.0, 1.0, 0.0, 0.0,
+                    0.0, 0.0, 0.0, 0.0, 1.0,
+                ]);
+                let ragged = builder.push(filter::Kind::DisplacementMap(filter::DisplacementMap {
+                    input1: input,
+                    input2: noise,
+                    // resvg applies the scale twice
+                    scale: width.sqrt(),
+                    x_channel_selector: filter::ColorChannel::R,
+                    y_channel_selector: filter::ColorChannel::G,
+                }));
+
+                // The edge is where the item is visible, but its blurred alpha has faded
+                #[rustfmt::skip]
+                let alpha = builder.color_matrix(ragged.clone(), vec![
+                    0.0, 0.0, 0.0, 0.0, 0.0,
+                    0.0, 0.0, 0.0, 0.0, 0.0,
+                    0.0, 0.0, 0.0, 0.0, 0.0,
+                    0.0, 0.0, 0.0, 1.0, 0.0,
+                ]);
+                let faded = builder.push(filter::Kind::GaussianBlur(filter::GaussianBlur {
+                    input: alpha.clone(),
+                    std_dev_x: positive(width / 2.0),
+                    std_dev_y: positive(width / 2.0),
+                }));
+                let edge = builder.composite(
+                    alpha,
+                    faded,
+                    filter::CompositeOperator::Arithmetic {
+                        k1: -2.0,
+                        k2: 2.0,
+                        k3: 0.0,
+                        k4: 0.0,
+                    },
+                );
+
+                // Scorch the edge with the burn color
+                let burn = builder.push(filter::Kind::Flood(filter::Flood {
+                    color: to_usvg_color((color.0, color.1, color.2, 255)),
+                    opacity: usvg::Opacity::ONE,
+                }));
+                let scorch = builder.composite(burn, edge, filter::CompositeOperator::In);
+                builder.composite(scorch, ragged, filter::CompositeOperator::Over)
+            }
+        }
+    }
+}
+
+fn positive(value: f32) -> usvg::PositiveF32 {
+    usvg::PositiveF32::new(value).unwrap_or(usvg::PositiveF32::ZERO)
+}
+
+impl From<LineCap> for usvg::LineCap {
+    fn from(cap: LineCap) -> Self {
+        match cap {
+            LineCap::Butt => usvg::LineCap::Butt,
+            LineCap::Round => usvg::LineCap::Round,
+            LineCap::Square => usvg::LineCap::Square,
+        }
+    }
+}
+
+impl From<LineJoin> for usvg::LineJoin {
+    fn from(join: LineJoin) -> Self {
+        match join {
+            LineJoin::Miter => usvg::LineJoin::Miter,
+            LineJoin::Round => usvg::LineJoin::Round,
+            LineJoin::Bevel => usvg::LineJoin::Bevel,
+        }
+    }
+}
+
+impl From<BlendMode> for usvg::BlendMode {
+    fn from(mode: BlendMode) -> Self {
+        match mode {
+            BlendMode::Normal => usvg::BlendMode::Normal,
+            BlendMode::Multiply => usvg::BlendMode::Multiply,
+            BlendMode::Screen => usvg::BlendMode::Screen,
+            BlendMode::Overlay => usvg::BlendMode::Overlay,
+            BlendMode::Darken => usvg::BlendMode::Darken,
+            BlendMode::Lighten => usvg::BlendMode::Lighten,
+            BlendMode::ColorDodge => usvg::BlendMode::ColorDodge,
+            BlendMode::ColorBurn => usvg::BlendMode::ColorBurn,
+            BlendMode::HardLight => usvg::BlendMode::HardLight,
+            BlendMode::SoftLight => usvg::BlendMode::SoftLight,
+            BlendMode::Difference => usvg::BlendMode::Difference,
+            BlendMode::Exclusion => usvg::BlendMode::Exclusion,
+            BlendMode::Hue => usvg::BlendMode::Hue,
+            BlendMode::Saturation => usvg::BlendMode::Saturation,
+            BlendMode::Color => usvg::BlendMode::Color,
+            BlendMode::Luminosity => usvg::BlendMode::Luminosity,
         }
     }
 }